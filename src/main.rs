@@ -12,18 +12,30 @@ MIT
 */
 #![allow(dead_code)]
 use subgraph_matching::{
-    enumerate, filter,
+    filter,
     graph::{self, LoadConfig},
-    order, Filter,
+    order, Config, Enumeration, Filter, Graph,
 };
 
-use std::time::Instant;
+use std::{path::Path, time::Instant};
 
 use eyre::Result;
 
 fn main() -> Result<()> {
     let args = cli::main()?;
 
+    let mut config = Config::builder()
+        .filter(args.filter)
+        .enumeration(if args.induced {
+            Enumeration::GqlInduced
+        } else {
+            Enumeration::Gql
+        });
+    if let Some(limit) = args.limit {
+        config = config.limit(limit);
+    }
+    let config = config.build();
+
     let load_config = if args.filter == Filter::Nlf {
         LoadConfig::with_neighbor_label_frequency()
     } else {
@@ -34,13 +46,9 @@ fn main() -> Result<()> {
     let total = Instant::now();
 
     println!("------");
-    let query_graph = measure("Load query graph", || {
-        graph::load(&args.query_graph, load_config)
-    })?;
+    let query_graph = measure("Load query graph", || load(&args.query_graph, load_config))?;
     println!("------");
-    let data_graph = measure("Load data graph", || {
-        graph::load(&args.data_graph, load_config)
-    })?;
+    let data_graph = measure("Load data graph", || load(&args.data_graph, load_config))?;
     println!("------");
 
     let loading = loading.elapsed();
@@ -49,13 +57,16 @@ fn main() -> Result<()> {
     println!("Query Graph Meta Information:\n{}", query_graph);
     println!("Data Graph Meta Information:\n{}", data_graph);
     println!("------");
+    println!("Config = {}", config);
+    match config.limit {
+        Some(limit) => println!("Limit = {}", limit),
+        None => println!("Limit = none"),
+    }
+    println!("------");
 
     let candidates = measure("Filter candidates", || {
-        let mut candidates = match args.filter {
-            Filter::Ldf => filter::ldf_filter(&data_graph, &query_graph).unwrap_or_default(),
-            Filter::Gql => filter::gql_filter(&data_graph, &query_graph).unwrap_or_default(),
-            Filter::Nlf => filter::nlf_filter(&data_graph, &query_graph).unwrap_or_default(),
-        };
+        let mut candidates =
+            filter::filter_candidates(&data_graph, &query_graph, config).unwrap_or_default();
         // sorting candidates to support set intersection
         candidates.sort();
         candidates
@@ -66,11 +77,21 @@ fn main() -> Result<()> {
     let order = measure("Generate matching order", || {
         order::gql_order(&data_graph, &query_graph, &candidates)
     });
-    println!("Matching order: {:?}", order);
+    println!(
+        "Matching order: {}",
+        order::describe(&order, &query_graph, &candidates)
+    );
     println!("------");
 
     let embedding_count = measure("Enumerate", || {
-        enumerate::gql(&data_graph, &query_graph, &candidates, &order)
+        subgraph_matching::find_with_candidates(
+            &data_graph,
+            &query_graph,
+            &candidates,
+            &order,
+            |_| {},
+            config,
+        )
     });
     println!("Embedding count = {}", embedding_count);
     println!("------");
@@ -82,6 +103,17 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+fn load(path: &Path, load_config: LoadConfig) -> Result<Graph, subgraph_matching::Error> {
+    println!("Reading from: {:?}", path);
+    let (graph, metrics) = graph::load_with_metrics(path, load_config)?;
+    println!("Parsing graph: {:?}", metrics.parse);
+    println!("Building graph: {:?}", metrics.build);
+    if let Some(frequencies) = metrics.frequencies {
+        println!("Computing neighbor label frequencies: {:?}", frequencies);
+    }
+    Ok(graph)
+}
+
 fn measure<R>(desc: &str, func: impl FnOnce() -> R) -> R {
     println!("Start :: {}", desc);
     let start = Instant::now();
@@ -102,11 +134,17 @@ mod cli {
         pub(crate) query_graph: std::path::PathBuf,
         pub(crate) data_graph: std::path::PathBuf,
         pub(crate) filter: subgraph_matching::Filter,
+        /// The maximum number of embeddings, `None` if unlimited.
+        pub(crate) limit: Option<usize>,
+        /// Only enumerates induced embeddings.
+        pub(crate) induced: bool,
     }
 
     pub(crate) fn main() -> Result<AppArgs> {
-        let mut pargs = Arguments::from_env();
+        parse(Arguments::from_env())
+    }
 
+    fn parse(mut pargs: Arguments) -> Result<AppArgs> {
         fn as_path_buf(arg: &OsStr) -> Result<PathBuf> {
             Ok(arg.into())
         }
@@ -118,6 +156,11 @@ mod cli {
                 .opt_value_from_fn(["-f", "--filter"], FilterWrapper::from_str)?
                 .unwrap_or(FilterWrapper(Filter::Ldf))
                 .into(),
+            // A limit of 0 means no limit.
+            limit: pargs
+                .opt_value_from_str::<_, usize>(["-l", "--limit"])?
+                .filter(|&limit| limit > 0),
+            induced: pargs.contains("--induced"),
         };
 
         Ok(args)
@@ -139,8 +182,53 @@ mod cli {
                 "LDF" | "ldf" => Ok(FilterWrapper(Filter::Ldf)),
                 "GQL" | "gql" => Ok(FilterWrapper(Filter::Gql)),
                 "NLF" | "nlf" => Ok(FilterWrapper(Filter::Nlf)),
+                "NLF2" | "nlf2" => Ok(FilterWrapper(Filter::Nlf2)),
+                "CFL" | "cfl" => Ok(FilterWrapper(Filter::Cfl)),
+                "LDFCORE" | "ldfcore" => Ok(FilterWrapper(Filter::LdfCore)),
                 _ => Err(eyre::eyre!("Unsupported filter {}", s)),
             }
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        fn args(args: &[&str]) -> Result<AppArgs> {
+            parse(Arguments::from_vec(args.iter().map(Into::into).collect()))
+        }
+
+        #[test]
+        fn test_parse() {
+            let parsed = args(&["-q", "query.graph", "-d", "data.graph"]).unwrap();
+
+            assert_eq!(parsed.query_graph, PathBuf::from("query.graph"));
+            assert_eq!(parsed.data_graph, PathBuf::from("data.graph"));
+            assert_eq!(parsed.filter, Filter::Ldf);
+            assert_eq!(parsed.limit, None);
+            assert!(!parsed.induced);
+
+            let parsed = args(&[
+                "-q",
+                "query.graph",
+                "-d",
+                "data.graph",
+                "-f",
+                "gql",
+                "--limit",
+                "10",
+                "--induced",
+            ])
+            .unwrap();
+
+            assert_eq!(parsed.filter, Filter::Gql);
+            assert_eq!(parsed.limit, Some(10));
+            assert!(parsed.induced);
+
+            let parsed = args(&["-q", "query.graph", "-d", "data.graph", "-l", "0"]).unwrap();
+
+            assert_eq!(parsed.limit, None);
+            assert!(args(&["-q", "query.graph", "-d", "data.graph", "-l", "ten"]).is_err());
+        }
+    }
 }
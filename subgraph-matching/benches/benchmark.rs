@@ -2,9 +2,9 @@ use std::path::PathBuf;
 
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
 use subgraph_matching::{
-    find,
-    graph::{load, Graph, LoadConfig},
-    Config, Enumeration, Filter, Order,
+    enumerate, filter, find,
+    graph::{load, GdlGraph, Graph, LoadConfig},
+    order, CandidateBackend, Config, Enumeration, Filter, Order,
 };
 
 const CRATE_ROOT: &str = env!("CARGO_MANIFEST_DIR");
@@ -16,6 +16,12 @@ const QUERY_PATH: &[&str] = &[
     "query_dense_16_2.graph",
 ];
 
+const ENUMERATE_QUERIES: &[&str] = &[
+    "query_dense_16_1.graph",
+    "query_dense_16_10.graph",
+    "query_dense_16_100.graph",
+];
+
 fn graphs(load_config: LoadConfig) -> (Graph, Graph) {
     let data_graph = load(&HPRD_PATH.iter().collect::<PathBuf>(), load_config).unwrap();
     let query_graph = load(&QUERY_PATH.iter().collect::<PathBuf>(), load_config).unwrap();
@@ -32,14 +38,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
 
     let mut group = c.benchmark_group("find");
 
-    for filter in vec![Filter::Ldf, Filter::Gql, Filter::Nlf] {
-        for order in vec![Order::Gql] {
-            for enumeration in vec![Enumeration::Gql] {
-                let config = Config {
-                    filter,
-                    order,
-                    enumeration,
-                };
+    for filter in [Filter::Ldf, Filter::Gql, Filter::Nlf, Filter::Cfl] {
+        for order in [Order::Gql, Order::Ri] {
+            for enumeration in [Enumeration::Gql] {
+                let config = Config::new(filter, order, enumeration);
 
                 group.bench_with_input(
                     BenchmarkId::from_parameter(config),
@@ -52,6 +54,101 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         }
     }
     group.finish();
+
+    // The global refinement of the GQL filter tracks the valid candidates of
+    // each query node in a set over the whole data graph. The candidates are
+    // the same for both backends.
+    let (data_graph, query_graph) = &graphs;
+
+    let mut group = c.benchmark_group("gql_candidate_backend");
+    for backend in [CandidateBackend::Vec, CandidateBackend::Bitset] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{:?}", backend)),
+            &backend,
+            |b, &backend| {
+                b.iter(|| {
+                    black_box(filter::gql_filter_with_backend(
+                        data_graph,
+                        query_graph,
+                        filter::GQL_REFINEMENT_THRESHOLD,
+                        filter::GQL_MAX_REFINEMENT_ROUNDS,
+                        backend,
+                    ))
+                })
+            },
+        );
+    }
+    group.finish();
+
+    // Only the enumeration phase, which is dominated by the traversal.
+    let mut candidates = filter::gql_filter(data_graph, query_graph).unwrap();
+    candidates.sort();
+    let order = order::gql_order(data_graph, query_graph, &candidates);
+
+    let mut group = c.benchmark_group("enumerate");
+    group.bench_function("gql", |b| {
+        b.iter(|| black_box(enumerate::gql(data_graph, query_graph, &candidates, &order)))
+    });
+    // Counts the last query node's valid candidates instead of visiting them.
+    group.bench_function("count", |b| {
+        b.iter(|| {
+            black_box(enumerate::count(
+                data_graph,
+                query_graph,
+                &candidates,
+                &order,
+            ))
+        })
+    });
+    group.finish();
+
+    // The candidates of each query node are intersected with the adjacency
+    // list of a visited neighbor, which dominates the deeper levels of the
+    // search. The queries differ in how many embeddings they have.
+    let mut group = c.benchmark_group("enumerate_queries");
+    for query in ENUMERATE_QUERIES {
+        let query_path = [CRATE_ROOT, "resources", "query_graph", query]
+            .iter()
+            .collect::<PathBuf>();
+        let query_graph = load(&query_path, LoadConfig::default()).unwrap();
+        let mut candidates = filter::gql_filter(data_graph, &query_graph).unwrap();
+        candidates.sort();
+        let order = order::gql_order(data_graph, &query_graph, &candidates);
+
+        group.bench_with_input(BenchmarkId::from_parameter(query), query, |b, _| {
+            b.iter(|| {
+                black_box(enumerate::gql(
+                    data_graph,
+                    &query_graph,
+                    &candidates,
+                    &order,
+                ))
+            })
+        });
+    }
+    group.finish();
+
+    // A 4-clique of wildcard nodes, which is cyclic enough that intersecting
+    // all adjacency lists at once beats checking the remaining edges.
+    let clique = "
+        (n0),(n1),(n2),(n3),
+        (n0)-->(n1),(n0)-->(n2),(n0)-->(n3),(n1)-->(n2),(n1)-->(n3),(n2)-->(n3)
+        "
+    .parse::<GdlGraph>()
+    .unwrap();
+    let candidates = filter::ldf_filter(data_graph, &clique).unwrap();
+    let order = order::gql_order(data_graph, &clique, &candidates);
+
+    let mut group = c.benchmark_group("enumerate_clique");
+    // Each run takes a few hundred milliseconds.
+    group.sample_size(10);
+    group.bench_function("gql", |b| {
+        b.iter(|| black_box(enumerate::gql(data_graph, &clique, &candidates, &order)))
+    });
+    group.bench_function("lftj", |b| {
+        b.iter(|| black_box(enumerate::lftj(data_graph, &clique, &candidates, &order)))
+    });
+    group.finish();
 }
 
 criterion_group!(benches, criterion_benchmark);
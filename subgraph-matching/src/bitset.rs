@@ -0,0 +1,106 @@
+/// A set of nodes that uses a single bit per node, e.g. of the data nodes
+/// of a partial embedding, of the query nodes of a failing set or of the
+/// candidates of a query node.
+#[derive(Debug, Clone)]
+pub(crate) struct Bitset {
+    blocks: Box<[u64]>,
+}
+
+impl Bitset {
+    /// Creates an empty set for the nodes `0..node_count`.
+    pub(crate) fn new(node_count: usize) -> Self {
+        Self {
+            blocks: vec![0; node_count.div_ceil(64)].into_boxed_slice(),
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.blocks.iter_mut().for_each(|block| *block = 0);
+    }
+
+    #[inline]
+    pub(crate) fn insert(&mut self, node: usize) {
+        self.blocks[node / 64] |= 1 << (node % 64);
+    }
+
+    #[inline]
+    pub(crate) fn remove(&mut self, node: usize) {
+        self.blocks[node / 64] &= !(1 << (node % 64));
+    }
+
+    #[inline]
+    pub(crate) fn contains(&self, node: usize) -> bool {
+        self.blocks[node / 64] & (1 << (node % 64)) != 0
+    }
+
+    /// Returns the nodes in the set in ascending order.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.blocks.iter().enumerate().flat_map(|(idx, &block)| {
+            // Clears the lowest set bit in each step.
+            std::iter::successors(Some(block).filter(|&rest| rest != 0), |&rest| {
+                Some(rest & (rest - 1)).filter(|&rest| rest != 0)
+            })
+            .map(move |rest| idx * 64 + rest.trailing_zeros() as usize)
+        })
+    }
+
+    /// Returns the number of nodes in the set.
+    pub(crate) fn count(&self) -> usize {
+        self.blocks
+            .iter()
+            .map(|block| block.count_ones() as usize)
+            .sum()
+    }
+
+    /// Returns the number of bytes that the set allocates for its nodes.
+    pub(crate) fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(&*self.blocks)
+    }
+
+    /// Adds the nodes of `other`, which needs to have the same size.
+    pub(crate) fn union_with(&mut self, other: &Bitset) {
+        for (block, other) in self.blocks.iter_mut().zip(other.blocks.iter()) {
+            *block |= other;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bitset() {
+        let mut set = Bitset::new(130);
+
+        for node in [0, 63, 64, 129] {
+            assert!(!set.contains(node));
+            set.insert(node);
+            assert!(set.contains(node));
+        }
+
+        assert!(!set.contains(1));
+        assert!(!set.contains(65));
+
+        set.remove(64);
+        assert!(!set.contains(64));
+        assert!(set.contains(63));
+        assert!(set.contains(129));
+
+        let mut other = Bitset::new(130);
+        other.insert(1);
+        other.insert(64);
+        set.union_with(&other);
+
+        assert!(set.contains(1));
+        assert!(set.contains(64));
+        assert!(set.contains(129));
+        assert!(!set.contains(2));
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![0, 1, 63, 64, 129]);
+        assert_eq!(set.count(), 5);
+
+        set.clear();
+        assert!(!set.contains(0));
+        assert!(!set.contains(129));
+    }
+}
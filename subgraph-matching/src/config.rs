@@ -2,19 +2,43 @@ use std::fmt::Display;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Filter {
-    Ldf, // label-degree-filter
-    Gql, // graphql-filter
-    Nlf, // neighbor-label-frequency-filter
+    Ldf,     // label-degree-filter
+    Gql,     // graphql-filter
+    Nlf,     // neighbor-label-frequency-filter
+    Nlf2,    // two-hop neighbor-label-frequency-filter
+    Cfl,     // core-forest-leaf-filter
+    LdfCore, // label-degree-filter followed by k-core pruning
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Order {
     Gql,
+    Ri,           // rapid-isomorphism-order
+    Dpiso,        // dynamic-programming-isomorphism-order
+    Bfs,          // breadth-first spanning tree order
+    Nlf,          // neighbor-label-selectivity order
+    Connectivity, // most-edges-to-selected-nodes order
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Enumeration {
     Gql,
+    GqlInduced,      // only induced embeddings
+    GqlHomomorphism, // query nodes may map to the same data node, ignores the filter
+    GqlFailingSet,   // skips candidates that fail for the same reason
+    Vf2,             // VF2 state space search with terminal sets
+    Lftj,            // Leapfrog Triejoin over the sorted adjacency lists
+}
+
+/// The representation of the valid candidates during the global refinement
+/// of [`Filter::Gql`], see [`crate::filter::CandidateSet`].
+///
+/// Other filters ignore it and the resulting candidates are the same for
+/// both representations.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CandidateBackend {
+    Vec,    // one bool per data node
+    Bitset, // one bit per data node
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -22,6 +46,25 @@ pub struct Config {
     pub filter: Filter,
     pub order: Order,
     pub enumeration: Enumeration,
+    /// Stops the enumeration after the given number of edge existence checks.
+    pub max_edge_checks: Option<u64>,
+    /// The GQL filter keeps refining candidates while a refinement round
+    /// removes more than this fraction of the remaining candidates.
+    pub gql_refinement_threshold: f64,
+    /// The maximum number of refinement rounds of the GQL filter, `0`
+    /// disables the global refinement.
+    pub gql_max_refinement_rounds: usize,
+    /// The representation of the valid candidates during the global
+    /// refinement of the GQL filter.
+    pub gql_candidate_backend: CandidateBackend,
+    /// Stops the enumeration after the given number of embeddings.
+    pub limit: Option<usize>,
+    /// Only maps query edges to data edges whose weight is within the
+    /// inclusive `(min, max)` bounds, see [`crate::graph::Graph::edge_weight`].
+    pub edge_weight_bounds: Option<(f64, f64)>,
+    /// Skips the enumeration if the product of the candidate counts after
+    /// filtering exceeds this budget, see [`crate::Error::TooExpensive`].
+    pub max_candidate_product: Option<u128>,
 }
 
 impl Display for Filter {
@@ -54,8 +97,116 @@ impl Config {
             filter,
             order,
             enumeration,
+            ..Config::default()
+        }
+    }
+
+    /// Returns a builder that starts from the default config.
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+}
+
+/// Builds a [`Config`] by overriding individual settings of the default.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    pub fn filter(self, filter: Filter) -> Self {
+        Self {
+            config: Config {
+                filter,
+                ..self.config
+            },
+        }
+    }
+
+    pub fn order(self, order: Order) -> Self {
+        Self {
+            config: Config {
+                order,
+                ..self.config
+            },
+        }
+    }
+
+    pub fn enumeration(self, enumeration: Enumeration) -> Self {
+        Self {
+            config: Config {
+                enumeration,
+                ..self.config
+            },
+        }
+    }
+
+    pub fn max_edge_checks(self, max_edge_checks: u64) -> Self {
+        Self {
+            config: Config {
+                max_edge_checks: Some(max_edge_checks),
+                ..self.config
+            },
+        }
+    }
+
+    pub fn gql_refinement_threshold(self, gql_refinement_threshold: f64) -> Self {
+        Self {
+            config: Config {
+                gql_refinement_threshold,
+                ..self.config
+            },
         }
     }
+
+    pub fn gql_max_refinement_rounds(self, gql_max_refinement_rounds: usize) -> Self {
+        Self {
+            config: Config {
+                gql_max_refinement_rounds,
+                ..self.config
+            },
+        }
+    }
+
+    pub fn gql_candidate_backend(self, gql_candidate_backend: CandidateBackend) -> Self {
+        Self {
+            config: Config {
+                gql_candidate_backend,
+                ..self.config
+            },
+        }
+    }
+
+    pub fn limit(self, limit: usize) -> Self {
+        Self {
+            config: Config {
+                limit: Some(limit),
+                ..self.config
+            },
+        }
+    }
+
+    pub fn edge_weight_bounds(self, min: f64, max: f64) -> Self {
+        Self {
+            config: Config {
+                edge_weight_bounds: Some((min, max)),
+                ..self.config
+            },
+        }
+    }
+
+    pub fn max_candidate_product(self, max_candidate_product: u128) -> Self {
+        Self {
+            config: Config {
+                max_candidate_product: Some(max_candidate_product),
+                ..self.config
+            },
+        }
+    }
+
+    pub fn build(self) -> Config {
+        self.config
+    }
 }
 
 impl Default for Config {
@@ -64,6 +215,13 @@ impl Default for Config {
             filter: Filter::Ldf,
             order: Order::Gql,
             enumeration: Enumeration::Gql,
+            max_edge_checks: None,
+            gql_refinement_threshold: crate::filter::GQL_REFINEMENT_THRESHOLD,
+            gql_max_refinement_rounds: crate::filter::GQL_MAX_REFINEMENT_ROUNDS,
+            gql_candidate_backend: CandidateBackend::Vec,
+            limit: None,
+            edge_weight_bounds: None,
+            max_candidate_product: None,
         }
     }
 }
@@ -94,3 +252,35 @@ impl From<Enumeration> for Config {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let config = Config::builder().build();
+
+        assert_eq!(config.filter, Filter::Ldf);
+        assert_eq!(config.order, Order::Gql);
+        assert_eq!(config.enumeration, Enumeration::Gql);
+        assert_eq!(config.limit, None);
+
+        let config = Config::builder()
+            .filter(Filter::Gql)
+            .enumeration(Enumeration::GqlInduced)
+            .limit(100)
+            .build();
+
+        assert_eq!(config.filter, Filter::Gql);
+        assert_eq!(config.order, Order::Gql);
+        assert_eq!(config.enumeration, Enumeration::GqlInduced);
+        assert_eq!(config.limit, Some(100));
+        assert_eq!(config.gql_candidate_backend, CandidateBackend::Vec);
+        assert_eq!(config.max_edge_checks, None);
+        assert_eq!(
+            config.gql_max_refinement_rounds,
+            crate::filter::GQL_MAX_REFINEMENT_ROUNDS
+        );
+    }
+}
@@ -1,4 +1,122 @@
-use crate::{filter::Candidates, graph::Graph};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    ops::ControlFlow,
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        mpsc::SyncSender,
+    },
+};
+
+use rand::{seq::SliceRandom, Rng};
+use rayon::prelude::*;
+
+use crate::{
+    bitset::Bitset,
+    filter::Candidates,
+    graph::{EdgeDirection, Graph},
+    Enumeration,
+};
+
+mod lftj;
+mod vf2;
+
+pub(crate) use lftj::lftj_with_budget;
+pub use lftj::{lftj, lftj_with};
+pub(crate) use vf2::vf2_with_budget;
+pub use vf2::{vf2, vf2_with};
+
+pub(crate) type Comparator = fn(usize, usize, usize) -> Ordering;
+
+/// Reorders the valid candidates of a query node before they are visited.
+pub(crate) type Arrangement = fn(usize, &mut [usize]);
+
+/// Summarizes an enumeration that might have been stopped early.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Summary {
+    /// The number of embeddings found before the enumeration finished.
+    pub embedding_count: usize,
+    /// The number of edge existence checks performed on the data graph.
+    pub edge_checks: u64,
+    /// `true`, if the enumeration stopped because it hit the edge check budget.
+    pub budget_exceeded: bool,
+}
+
+/// Reports how far an enumeration has progressed, see [`gql_with_progress`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// The number of embeddings found so far.
+    pub embeddings_so_far: usize,
+    /// The depth of the search when the progress was reported, i.e. the last
+    /// depth for a found embedding and `0` after finishing root candidates.
+    pub current_depth: usize,
+    /// The number of root candidates whose subtrees have been enumerated.
+    pub root_candidates_done: usize,
+}
+
+/// Enumerates the embeddings of the query graph for the given candidates and
+/// matching order, e.g. an experimental search strategy, see
+/// [`crate::find_with_enumerator`].
+///
+/// Returns the number of embeddings that have been passed to `action`.
+pub trait Enumerator {
+    fn enumerate(
+        &self,
+        data_graph: &Graph,
+        query_graph: &Graph,
+        candidates: &Candidates,
+        order: &[usize],
+        action: &mut dyn FnMut(&[usize]),
+    ) -> usize;
+
+    /// Returns the built-in enumeration this enumerator runs, if any.
+    ///
+    /// The filter phase then prepares the candidates for that enumeration,
+    /// e.g. without requiring distinct data nodes for homomorphisms.
+    fn enumeration(&self) -> Option<Enumeration> {
+        None
+    }
+}
+
+/// Runs the built-in enumeration with its default options, e.g. without an
+/// edge check budget.
+impl Enumerator for Enumeration {
+    fn enumerate(
+        &self,
+        data_graph: &Graph,
+        query_graph: &Graph,
+        candidates: &Candidates,
+        order: &[usize],
+        action: &mut dyn FnMut(&[usize]),
+    ) -> usize {
+        if *self == Enumeration::Vf2 {
+            return vf2_with(data_graph, query_graph, candidates, order, action);
+        }
+        if *self == Enumeration::Lftj {
+            return lftj_with(data_graph, query_graph, candidates, order, action);
+        }
+
+        gql_with_options(
+            data_graph,
+            query_graph,
+            candidates,
+            order,
+            None::<Arrangement>,
+            Options {
+                induced: *self == Enumeration::GqlInduced,
+                homomorphism: *self == Enumeration::GqlHomomorphism,
+                failing_sets: *self == Enumeration::GqlFailingSet,
+                ..Options::default()
+            },
+            continuing(action),
+        )
+        .embedding_count
+    }
+
+    fn enumeration(&self) -> Option<Enumeration> {
+        Some(*self)
+    }
+}
 
 pub fn gql(
     data_graph: &Graph,
@@ -9,11 +127,95 @@ pub fn gql(
     gql_with(data_graph, query_graph, candidates, order, |_| {})
 }
 
+/// Counts all embeddings like [`gql`], but does not map the last query node
+/// to each of its valid candidates, since each of them completes a distinct
+/// embedding.
+pub fn count(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+) -> usize {
+    gql_with_options(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        None::<Arrangement>,
+        Options {
+            count_only: true,
+            ..Options::default()
+        },
+        continuing(|_| {}),
+    )
+    .embedding_count
+}
+
 pub fn gql_with<F>(
     data_graph: &Graph,
     query_graph: &Graph,
     candidates: &Candidates,
     order: &[usize],
+    action: F,
+) -> usize
+where
+    F: FnMut(&[usize]),
+{
+    gql_with_options(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        None::<Arrangement>,
+        Options::default(),
+        continuing(action),
+    )
+    .embedding_count
+}
+
+/// Enumerates all embeddings like [`gql_with`], but stops as soon as the
+/// number of edge existence checks on the data graph reaches `max_edge_checks`.
+///
+/// Edge checks are the dominant cost of the enumeration. In contrast to a
+/// wall-clock deadline, bounding them leads to reproducible results that are
+/// independent of the executing platform.
+pub fn gql_with_budget<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    max_edge_checks: u64,
+    action: F,
+) -> Summary
+where
+    F: FnMut(&[usize]),
+{
+    gql_with_options(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        None::<Arrangement>,
+        Options {
+            max_edge_checks: Some(max_edge_checks),
+            ..Options::default()
+        },
+        continuing(action),
+    )
+}
+
+/// Enumerates embeddings like [`gql_with`], but stops as soon as `limit`
+/// embeddings have been found. A limit of `0` means no limit.
+///
+/// This is useful to check if at least `limit` embeddings exist without
+/// enumerating all of them. Returns the number of found embeddings, which
+/// is at most `limit`.
+pub fn gql_with_limit<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    limit: usize,
     mut action: F,
 ) -> usize
 where
@@ -21,83 +223,1075 @@ where
 {
     let mut embedding_count = 0;
 
-    // Stores the neighbors for each query node that have already been visited
-    // according to the defined order.
-    let visited_neighbors = visited_neighbors(query_graph, order);
+    gql_with_options(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        None::<Arrangement>,
+        Options::default(),
+        |embedding: &[usize]| {
+            action(embedding);
+            embedding_count += 1;
 
-    // The root of the traversal.
-    let start_node = order[0];
-    let max_depth = query_graph.node_count();
+            if embedding_count == limit {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        },
+    )
+    .embedding_count
+}
 
-    // TODO bit set?
-    // Tracks which data node has already been visited during the traversal.
-    let mut visited = vec![false; data_graph.node_count()];
+/// Enumerates all embeddings like [`gql_with`], but emits them in a custom
+/// order within the subtree of each root candidate.
+///
+/// After the valid candidates of a query node `u` have been computed, they are
+/// sorted using `cmp(u, v1, v2)`, where `v1` and `v2` are two data nodes that
+/// `u` can be mapped to. The order of the root candidates is not affected,
+/// i.e., only the emission order within each root's subtree can be changed.
+pub fn gql_with_ordered<C, F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    cmp: C,
+    action: F,
+) -> usize
+where
+    C: FnMut(usize, usize, usize) -> Ordering,
+    F: FnMut(&[usize]),
+{
+    gql_with_options(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        Some(sorting(cmp)),
+        Options::default(),
+        continuing(action),
+    )
+    .embedding_count
+}
 
-    // Represents the valid next candidates out of the possible candidates for each depth.
-    // For depth 0, this is equivalent to the candidates of query node at order[0].
-    let mut valid_candidates = Vec::with_capacity(max_depth);
-    // TODO: can we avoid copying from slice (this array is never updated)
-    valid_candidates.push(Vec::from(candidates.candidates(start_node)));
-    for u in order[1..].iter() {
-        // We pre-allocate the vec with the number of candidates since we can't
-        // know how many of them will be valid neighbors according to the query.
-        valid_candidates.push(vec![0; candidates.candidate_count(*u)]);
-    }
+/// Enumerates all induced embeddings, i.e., two data nodes in an embedding
+/// may only be adjacent if their corresponding query nodes are adjacent.
+pub fn gql_induced_with<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    action: F,
+) -> usize
+where
+    F: FnMut(&[usize]),
+{
+    gql_with_options(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        None::<Arrangement>,
+        Options {
+            induced: true,
+            ..Options::default()
+        },
+        continuing(action),
+    )
+    .embedding_count
+}
 
-    // Idx tracks the currently processed candidate at each depth.
-    let mut idx = vec![0_usize; max_depth];
-    // Idx_count tracks the number of valid candidates at each depth.
-    let mut idx_count = vec![0_usize; max_depth];
-    // Stores the mapping between query and data nodes according to order.
-    let mut embedding = vec![0_usize; max_depth];
+/// Enumerates embeddings like [`gql_with`], but stops as soon as `stop`
+/// is set, e.g. by another thread on a timeout.
+///
+/// The flag is checked whenever the search descends to the next depth, not
+/// for every candidate. Returns the number of embeddings found until then.
+pub fn gql_with_cancel<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    stop: &AtomicBool,
+    action: F,
+) -> usize
+where
+    F: FnMut(&[usize]),
+{
+    gql_with_options(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        None::<Arrangement>,
+        Options {
+            stop: Some(stop),
+            ..Options::default()
+        },
+        continuing(action),
+    )
+    .embedding_count
+}
 
-    let mut cur_depth = 0;
+/// Enumerates embeddings like [`gql_with`], but sends each embedding over
+/// the given channel, e.g. to stream them to a consumer on another thread.
+///
+/// Blocks while the channel is full, so the search does not run ahead of
+/// the consumer by more than the channel capacity. Stops as soon as the
+/// receiver has been dropped. Returns the number of sent embeddings.
+pub fn gql_into_sender(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    sender: SyncSender<Vec<usize>>,
+) -> usize {
+    let mut sent_count = 0;
 
-    idx[cur_depth] = 0;
-    idx_count[cur_depth] = candidates.candidate_count(start_node);
+    gql_with_options(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        None::<Arrangement>,
+        Options::default(),
+        |embedding: &[usize]| match sender.send(embedding.to_vec()) {
+            Ok(()) => {
+                sent_count += 1;
+                ControlFlow::Continue(())
+            }
+            Err(_) => ControlFlow::Break(()),
+        },
+    );
 
-    loop {
-        while idx[cur_depth] < idx_count[cur_depth] {
-            let u = order[cur_depth];
-            let v = valid_candidates[cur_depth][idx[cur_depth]];
+    sent_count
+}
 
-            embedding[u] = v;
-            visited[v] = true;
-            idx[cur_depth] += 1;
+/// Enumerates all embeddings like [`gql_with`] and reports the progress
+/// every `every` embeddings and every `every` root candidates, e.g. to
+/// drive a progress bar.
+///
+/// The root candidates are enumerated in chunks of `every` candidates, after
+/// each of which the progress is reported. The last reported progress holds
+/// the final number of embeddings.
+pub fn gql_with_progress<F, P>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    every: usize,
+    mut progress: P,
+    mut action: F,
+) -> usize
+where
+    F: FnMut(&[usize]),
+    P: FnMut(Progress),
+{
+    let every = every.max(1);
+    let last_depth = query_graph.node_count() - 1;
+    let mut visited = VisitedSet::new(data_graph.node_count());
+    let mut embedding_count = 0;
+    let mut root_candidates_done = 0;
 
-            if cur_depth == max_depth - 1 {
+    for roots in candidates.candidates(order[0]).chunks(every) {
+        gql_with_visited(
+            data_graph,
+            query_graph,
+            candidates,
+            order,
+            None::<Arrangement>,
+            Options {
+                roots: Some(roots),
+                ..Options::default()
+            },
+            &mut visited,
+            continuing(|embedding| {
+                action(embedding);
                 embedding_count += 1;
-                visited[v] = false;
-                action(&embedding);
-                // TODO output limit
+
+                if embedding_count % every == 0 {
+                    progress(Progress {
+                        embeddings_so_far: embedding_count,
+                        current_depth: last_depth,
+                        root_candidates_done,
+                    });
+                }
+            }),
+        );
+
+        root_candidates_done += roots.len();
+        progress(Progress {
+            embeddings_so_far: embedding_count,
+            current_depth: 0,
+            root_candidates_done,
+        });
+    }
+
+    embedding_count
+}
+
+/// Enumerates all homomorphisms of the query graph in the data graph.
+///
+/// In contrast to [`gql_with`], multiple query nodes may map to the same
+/// data node, as long as every query edge maps to a data edge.
+pub fn gql_homomorphism_with<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    action: F,
+) -> usize
+where
+    F: FnMut(&[usize]),
+{
+    gql_with_options(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        None::<Arrangement>,
+        Options {
+            homomorphism: true,
+            ..Options::default()
+        },
+        continuing(action),
+    )
+    .embedding_count
+}
+
+/// Enumerates each embedding only once up to automorphisms of the query graph.
+///
+/// Symmetric query graphs, e.g. a diamond, match the same data nodes under
+/// different assignments. Enforcing the constraints of
+/// [`order::symmetry_constraints`](crate::order::symmetry_constraints)
+/// keeps exactly one of those assignments.
+pub fn gql_canonical_with<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    action: F,
+) -> usize
+where
+    F: FnMut(&[usize]),
+{
+    let symmetry_constraints = crate::order::symmetry_constraints(query_graph);
+
+    gql_with_options(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        None::<Arrangement>,
+        Options {
+            symmetry_constraints: &symmetry_constraints,
+            ..Options::default()
+        },
+        continuing(action),
+    )
+    .embedding_count
+}
+
+pub fn gql_canonical(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+) -> usize {
+    gql_canonical_with(data_graph, query_graph, candidates, order, |_| {})
+}
+
+/// Collects up to `k` embeddings using a randomized backtracking search.
+///
+/// The root candidates are shuffled and the valid candidates at each depth
+/// are visited in random order. The search stops after the first `k`
+/// embeddings, so this is not a uniform sampler: the search completes a
+/// subtree before it backtracks, which means that embeddings sharing a
+/// prefix with an already sampled embedding are more likely to be sampled.
+/// Passing a seeded `rng` leads to reproducible samples.
+pub fn sample<R>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    k: usize,
+    rng: &mut R,
+) -> Vec<Vec<usize>>
+where
+    R: Rng,
+{
+    let mut embeddings = Vec::new();
+
+    if k == 0 {
+        return embeddings;
+    }
+
+    let mut roots = candidates.candidates(order[0]).to_vec();
+    roots.shuffle(rng);
+
+    gql_with_options(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        Some(|_, valid: &mut [usize]| valid.shuffle(rng)),
+        Options {
+            roots: Some(&roots),
+            ..Options::default()
+        },
+        |embedding: &[usize]| {
+            embeddings.push(embedding.to_vec());
+
+            if embeddings.len() == k {
+                ControlFlow::Break(())
             } else {
-                // Go down into the rabbit hole.
-                cur_depth += 1;
-                idx[cur_depth] = 0;
+                ControlFlow::Continue(())
+            }
+        },
+    );
 
+    embeddings
+}
+
+/// Estimates the number of embeddings from `samples` random walks along the
+/// matching order, similar to WanderJoin.
+///
+/// Each walk maps the query nodes in order and picks one of the valid
+/// candidates of each query node uniformly at random. A walk that maps all
+/// query nodes contributes the inverse of its probability, i.e. the product
+/// of the valid candidate counts along the way, and a walk that gets stuck
+/// contributes `0`. The average of the walks is an unbiased estimate of the
+/// embedding count, but its variance can be high if the candidate counts
+/// along the walks differ a lot, so more samples lead to a better estimate.
+/// Passing a seeded `rng` leads to reproducible estimates.
+pub fn estimate<R>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    samples: usize,
+    rng: &mut R,
+) -> f64
+where
+    R: Rng,
+{
+    estimate_with_options(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        Options::default(),
+        samples,
+        rng,
+    )
+}
+
+/// Estimates the number of embeddings like [`estimate`], but respects the
+/// given options. The budget and the stop flag are ignored.
+pub(crate) fn estimate_with_options<R>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    options: Options,
+    samples: usize,
+    rng: &mut R,
+) -> f64
+where
+    R: Rng,
+{
+    if samples == 0 || order.is_empty() {
+        return 0.0;
+    }
+
+    let injective = !options.homomorphism;
+    let mut edge_checks = 0;
+
+    let sorted_candidates;
+    let candidates = if is_sorted(candidates, order.len()) {
+        candidates
+    } else {
+        sorted_candidates = sorted(candidates, order.len());
+        &sorted_candidates
+    };
+
+    let visited_neighbors = visited_neighbors(query_graph, order);
+    let edge_directions = edge_directions(query_graph, order, &visited_neighbors);
+    let edge_labels = edge_labels(query_graph, order, &visited_neighbors);
+    let visited_non_neighbors = visited_non_neighbors(query_graph, order, options.induced);
+    let symmetry_bounds = symmetry_bounds(order, options.symmetry_constraints);
+
+    let max_depth = query_graph.node_count();
+    let roots = options
+        .roots
+        .unwrap_or_else(|| candidates.candidates(order[0]));
+
+    let mut visited = VisitedSet::new(data_graph.node_count());
+    let mut valid_candidates = order
+        .iter()
+        .map(|&u| vec![0; candidates.candidate_count(u)])
+        .collect::<Vec<_>>();
+    let mut idx_count = vec![0_usize; max_depth];
+    let mut embedding = vec![0_usize; max_depth];
+
+    let mut total = 0.0;
+
+    for _ in 0..samples {
+        // The inverse of the probability of the walk so far.
+        let mut weight = 1.0;
+        let mut mapped = 0;
+
+        while mapped < max_depth {
+            let valid = if mapped == 0 {
+                roots
+            } else {
                 generate_valid_candidates(
                     data_graph,
-                    cur_depth,
+                    mapped,
                     &embedding,
                     &mut idx_count,
                     &mut valid_candidates,
-                    &visited,
+                    if injective { Some(&visited) } else { None },
                     &visited_neighbors,
+                    &edge_directions,
+                    &edge_labels,
+                    &visited_non_neighbors,
+                    &symmetry_bounds,
+                    options.edge_weight_bounds,
                     order,
                     candidates,
+                    None,
+                    &mut edge_checks,
+                    u64::MAX,
                 );
+                &valid_candidates[mapped][..idx_count[mapped]]
+            };
+
+            if valid.is_empty() {
+                weight = 0.0;
+                break;
             }
+
+            let v = valid[rng.gen_range(0..valid.len())];
+            weight *= valid.len() as f64;
+
+            embedding[order[mapped]] = v;
+            if injective {
+                visited.insert(v);
+            }
+            mapped += 1;
         }
 
-        if cur_depth == 0 {
-            break;
+        total += weight;
+
+        if injective {
+            for &u in &order[..mapped] {
+                visited.remove(embedding[u]);
+            }
         }
-        // backtrack
-        cur_depth -= 1;
-        visited[embedding[order[cur_depth]]] = false;
     }
 
-    embedding_count
+    total / samples as f64
+}
+
+/// Counts all embeddings like [`gql`], but splits the candidates of the start
+/// node into chunks and enumerates them in parallel using rayon.
+///
+/// Each chunk is processed by an independent search with its own buffers,
+/// so there is no shared mutable state between threads.
+pub fn gql_par(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+) -> usize {
+    let roots = candidates.candidates(order[0]);
+    // Use more chunks than threads, since subtrees differ a lot in size.
+    let chunk_size = usize::max(1, roots.len() / (rayon::current_num_threads() * 8));
+
+    roots
+        .par_chunks(chunk_size)
+        .map(|roots| {
+            gql_with_options(
+                data_graph,
+                query_graph,
+                candidates,
+                order,
+                None::<Arrangement>,
+                Options {
+                    roots: Some(roots),
+                    count_only: true,
+                    ..Options::default()
+                },
+                continuing(|_| {}),
+            )
+            .embedding_count
+        })
+        .sum()
+}
+
+/// Settings of the core enumeration that are used by the different variants.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct Options<'a> {
+    /// Stops the enumeration after the given number of edge existence checks.
+    pub(crate) max_edge_checks: Option<u64>,
+    /// Only enumerates induced embeddings.
+    pub(crate) induced: bool,
+    /// Allows multiple query nodes to map to the same data node.
+    pub(crate) homomorphism: bool,
+    /// Replaces the candidates of the start node, e.g. to split them across threads.
+    pub(crate) roots: Option<&'a [usize]>,
+    /// Pairs `(u, v)` of query nodes where `u` must be mapped to a smaller
+    /// data node than `v`.
+    pub(crate) symmetry_constraints: &'a [(usize, usize)],
+    /// Stops the enumeration once the flag is set.
+    pub(crate) stop: Option<&'a AtomicBool>,
+    /// Skips the remaining candidates of a query node if they are known to
+    /// fail for the same reason as the current one.
+    pub(crate) failing_sets: bool,
+    /// Only maps query edges to data edges whose weight is within the
+    /// inclusive `(min, max)` bounds.
+    pub(crate) edge_weight_bounds: Option<(f64, f64)>,
+    /// Counts the valid candidates of the last query node instead of mapping
+    /// them one by one, i.e. the action is not called for these embeddings.
+    pub(crate) count_only: bool,
+}
+
+pub(crate) fn gql_with_options<A, F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    arrange: Option<A>,
+    options: Options,
+    action: F,
+) -> Summary
+where
+    A: FnMut(usize, &mut [usize]),
+    F: FnMut(&[usize]) -> ControlFlow<()>,
+{
+    gql_with_visited(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        arrange,
+        options,
+        &mut VisitedSet::new(data_graph.node_count()),
+        action,
+    )
+}
+
+/// Runs the enumeration like [`gql_with_options`], but tracks the visited
+/// data nodes in the given set, which allows reusing it across queries.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn gql_with_visited<A, F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    mut arrange: Option<A>,
+    options: Options,
+    visited: &mut VisitedSet,
+    mut action: F,
+) -> Summary
+where
+    A: FnMut(usize, &mut [usize]),
+    F: FnMut(&[usize]) -> ControlFlow<()>,
+{
+    // Candidate generation intersects sorted lists.
+    let candidates = if is_sorted(candidates, order.len()) {
+        Cow::Borrowed(candidates)
+    } else {
+        Cow::Owned(sorted(candidates, order.len()))
+    };
+
+    // The search owns the visited set until it is done.
+    let visited_set = std::mem::replace(visited, VisitedSet::new(0));
+    let mut search = Search::new(
+        data_graph,
+        query_graph,
+        candidates,
+        Cow::Borrowed(order),
+        options,
+        visited_set,
+    );
+
+    let budget_exceeded = loop {
+        match search.advance(&mut arrange) {
+            Step::Embedding => {
+                if let ControlFlow::Break(()) = action(&search.embedding) {
+                    break false;
+                }
+            }
+            Step::Done | Step::Stopped => break false,
+            Step::BudgetExceeded => break true,
+        }
+    };
+
+    *visited = search.visited;
+
+    Summary {
+        embedding_count: search.embedding_count,
+        edge_checks: search.edge_checks,
+        budget_exceeded,
+    }
+}
+
+/// Describes why [`Search::advance`] returned.
+enum Step {
+    /// The embedding of the search is complete.
+    Embedding,
+    /// All candidates have been explored.
+    Done,
+    /// The stop flag has been set.
+    Stopped,
+    /// The edge check budget has been exhausted.
+    BudgetExceeded,
+}
+
+/// The state of the backtracking search, which is shared by the callback
+/// based enumeration and the [`Embeddings`] iterator.
+struct Search<'a> {
+    data_graph: &'a Graph,
+    candidates: Cow<'a, Candidates>,
+    order: Cow<'a, [usize]>,
+    options: Options<'a>,
+    injective: bool,
+    max_edge_checks: u64,
+    // Stores the neighbors for each query node that have already been visited
+    // according to the defined order.
+    visited_neighbors: Vec<Vec<usize>>,
+    // Stores the direction of the edges to the visited neighbors.
+    edge_directions: Vec<Vec<EdgeDirection>>,
+    // Stores the labels of the edges to the visited neighbors.
+    edge_labels: Vec<Vec<EdgeLabels>>,
+    // Stores the non-adjacent query nodes that have already been visited,
+    // which must not be adjacent in the data graph for induced embeddings
+    // or if they are connected by an anti-edge.
+    visited_non_neighbors: Vec<Vec<usize>>,
+    // Stores how a candidate needs to compare to already mapped data nodes.
+    symmetry_bounds: Vec<Vec<(usize, Ordering)>>,
+    // Stores why the explored subtrees did not lead to an embedding.
+    failing_sets: Option<FailingSets>,
+    // Stores the query nodes whose data nodes are valid candidates at the
+    // current depth, but cannot be mapped again.
+    conflicts: Vec<usize>,
+    // Tracks which data node has already been visited during the traversal.
+    visited: VisitedSet,
+    // Represents the valid next candidates out of the possible candidates for each depth.
+    // For depth 0, this is equivalent to the candidates of query node at order[0].
+    valid_candidates: Vec<Vec<usize>>,
+    // Idx tracks the currently processed candidate at each depth.
+    idx: Vec<usize>,
+    // Idx_count tracks the number of valid candidates at each depth.
+    idx_count: Vec<usize>,
+    // Stores the mapping between query and data nodes according to order.
+    embedding: Vec<usize>,
+    cur_depth: usize,
+    embedding_count: usize,
+    edge_checks: u64,
+}
+
+impl<'a> Search<'a> {
+    /// Prepares the search, `candidates` need to be sorted.
+    fn new(
+        data_graph: &'a Graph,
+        query_graph: &Graph,
+        candidates: Cow<'a, Candidates>,
+        order: Cow<'a, [usize]>,
+        options: Options<'a>,
+        mut visited: VisitedSet,
+    ) -> Self {
+        let visited_neighbors = visited_neighbors(query_graph, &order);
+        let edge_directions = edge_directions(query_graph, &order, &visited_neighbors);
+        let edge_labels = edge_labels(query_graph, &order, &visited_neighbors);
+        let visited_non_neighbors = visited_non_neighbors(query_graph, &order, options.induced);
+        let symmetry_bounds = symmetry_bounds(&order, options.symmetry_constraints);
+        let failing_sets = if options.failing_sets {
+            Some(FailingSets::new(
+                &order,
+                &visited_neighbors,
+                &visited_non_neighbors,
+                &symmetry_bounds,
+            ))
+        } else {
+            None
+        };
+
+        // The root of the traversal.
+        let start_node = order[0];
+        let max_depth = query_graph.node_count();
+        // The candidates of the root, unless a subset has been requested.
+        let roots = options
+            .roots
+            .unwrap_or_else(|| candidates.candidates(start_node));
+
+        // A previous enumeration that stopped early leaves its nodes behind.
+        visited.clear();
+
+        let mut valid_candidates = Vec::with_capacity(max_depth);
+        // TODO: can we avoid copying from slice (this array is never updated)
+        valid_candidates.push(Vec::from(roots));
+        for u in order[1..].iter() {
+            // We pre-allocate the vec with the number of candidates since we can't
+            // know how many of them will be valid neighbors according to the query.
+            valid_candidates.push(vec![0; candidates.candidate_count(*u)]);
+        }
+
+        let mut idx_count = vec![0_usize; max_depth];
+        idx_count[0] = roots.len();
+
+        Self {
+            data_graph,
+            candidates,
+            order,
+            options,
+            injective: !options.homomorphism,
+            max_edge_checks: options.max_edge_checks.unwrap_or(u64::MAX),
+            visited_neighbors,
+            edge_directions,
+            edge_labels,
+            visited_non_neighbors,
+            symmetry_bounds,
+            failing_sets,
+            conflicts: Vec::new(),
+            visited,
+            valid_candidates,
+            idx: vec![0_usize; max_depth],
+            idx_count,
+            embedding: vec![0_usize; max_depth],
+            cur_depth: 0,
+            embedding_count: 0,
+            edge_checks: 0,
+        }
+    }
+
+    /// Continues the traversal where the last call stopped until the next
+    /// embedding is complete or the search is over.
+    fn advance<A>(&mut self, arrange: &mut Option<A>) -> Step
+    where
+        A: FnMut(usize, &mut [usize]),
+    {
+        let max_depth = self.order.len();
+        let injective = self.injective;
+
+        loop {
+            while self.idx[self.cur_depth] < self.idx_count[self.cur_depth] {
+                let cur_depth = self.cur_depth;
+                let u = self.order[cur_depth];
+                let v = self.valid_candidates[cur_depth][self.idx[cur_depth]];
+
+                self.embedding[u] = v;
+                if injective {
+                    self.visited.insert(v);
+                }
+                self.idx[cur_depth] += 1;
+
+                if cur_depth == max_depth - 1 {
+                    self.embedding_count += 1;
+                    if injective {
+                        self.visited.remove(v);
+                    }
+                    if let Some(failing_sets) = self.failing_sets.as_mut() {
+                        failing_sets.found(cur_depth);
+                    }
+                    return Step::Embedding;
+                }
+
+                if self.options.stop.is_some_and(|stop| stop.load(Relaxed)) {
+                    return Step::Stopped;
+                }
+
+                // Go down into the rabbit hole.
+                let cur_depth = cur_depth + 1;
+                self.cur_depth = cur_depth;
+                self.idx[cur_depth] = 0;
+
+                let conflicts = &mut self.conflicts;
+                let within_budget = generate_valid_candidates(
+                    self.data_graph,
+                    cur_depth,
+                    &self.embedding,
+                    &mut self.idx_count,
+                    &mut self.valid_candidates,
+                    if injective { Some(&self.visited) } else { None },
+                    &self.visited_neighbors,
+                    &self.edge_directions,
+                    &self.edge_labels,
+                    &self.visited_non_neighbors,
+                    &self.symmetry_bounds,
+                    self.options.edge_weight_bounds,
+                    &self.order,
+                    &self.candidates,
+                    self.failing_sets.as_ref().map(|_| conflicts),
+                    &mut self.edge_checks,
+                    self.max_edge_checks,
+                );
+
+                if let Some(failing_sets) = self.failing_sets.as_mut() {
+                    failing_sets.descend(
+                        cur_depth,
+                        self.order[cur_depth],
+                        &self.conflicts,
+                        self.idx_count[cur_depth] > 0,
+                    );
+                }
+
+                if !within_budget {
+                    return Step::BudgetExceeded;
+                }
+
+                if let Some(arrange) = arrange.as_mut() {
+                    let u = self.order[cur_depth];
+                    arrange(
+                        u,
+                        &mut self.valid_candidates[cur_depth][..self.idx_count[cur_depth]],
+                    );
+                }
+
+                // Each valid candidate of the last query node completes a
+                // distinct embedding. Failing sets need to see each of them.
+                if self.options.count_only
+                    && cur_depth == max_depth - 1
+                    && self.failing_sets.is_none()
+                {
+                    self.embedding_count += self.idx_count[cur_depth];
+                    self.idx[cur_depth] = self.idx_count[cur_depth];
+                }
+            }
+
+            if self.cur_depth == 0 {
+                return Step::Done;
+            }
+            // The remaining candidates of the parent fail like the current one.
+            if let Some(failing_sets) = self.failing_sets.as_mut() {
+                if failing_sets.backtrack(self.cur_depth, &self.order) {
+                    self.idx[self.cur_depth - 1] = self.idx_count[self.cur_depth - 1];
+                }
+            }
+            // backtrack
+            self.cur_depth -= 1;
+            if injective {
+                self.visited
+                    .remove(self.embedding[self.order[self.cur_depth]]);
+            }
+        }
+    }
+}
+
+/// A lazy enumeration of all embeddings that yields one embedding per call
+/// to `next`.
+///
+/// Embeddings are produced in the same order as by [`gql_with`]. In contrast
+/// to the callback, the iterator owns the search state, which allows it to be
+/// suspended after each embedding, e.g. to only consume the first `n` of them
+/// via `take(n)`.
+pub struct Embeddings<'a> {
+    search: Search<'a>,
+    done: bool,
+}
+
+impl<'a> Embeddings<'a> {
+    pub fn new(
+        data_graph: &'a Graph,
+        query_graph: &Graph,
+        candidates: &Candidates,
+        order: &[usize],
+    ) -> Self {
+        Self::with_options(
+            data_graph,
+            query_graph,
+            candidates,
+            order,
+            Options::default(),
+        )
+    }
+
+    /// Creates the iterator for the given options, e.g. an edge check budget.
+    ///
+    /// The iterator ends early if the budget is exhausted or the stop flag is
+    /// set. The candidates of the last query node are always mapped one by
+    /// one, i.e. `count_only` is ignored.
+    pub(crate) fn with_options(
+        data_graph: &'a Graph,
+        query_graph: &Graph,
+        candidates: &Candidates,
+        order: &[usize],
+        options: Options<'a>,
+    ) -> Self {
+        // The iterator outlives the borrowed candidates.
+        let candidates = sorted(candidates, order.len());
+
+        let search = Search::new(
+            data_graph,
+            query_graph,
+            Cow::Owned(candidates),
+            Cow::Owned(order.to_vec()),
+            Options {
+                count_only: false,
+                ..options
+            },
+            VisitedSet::new(data_graph.node_count()),
+        );
+
+        Self {
+            search,
+            done: false,
+        }
+    }
+}
+
+impl Iterator for Embeddings<'_> {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.search.advance(&mut None::<Arrangement>) {
+            Step::Embedding => Some(self.search.embedding.clone()),
+            Step::Done | Step::Stopped | Step::BudgetExceeded => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+/// Tracks the data nodes that are part of the current partial embedding.
+///
+/// Uses a single bit per data node, which keeps the set small enough to
+/// stay in cache for large data graphs.
+pub(crate) type VisitedSet = Bitset;
+
+/// Tracks the failing sets along the current path of the search tree.
+///
+/// The failing set of a partial embedding contains the query nodes whose
+/// mapping is responsible for the partial embedding not being extensible to
+/// a full embedding. If the failing set of a partial embedding that maps `u`
+/// does not contain `u`, mapping `u` to any of its remaining candidates is
+/// bound to fail as well, and these candidates can be skipped.
+///
+/// The implementation follows the failing sets described in
+///
+/// Myoungji Han, Hyunjoon Kim, Geonmo Gu, Kunsoo Park, Wook-Shin Han:
+/// Efficient Subgraph Matching: Harmonizing Dynamic Programming,
+/// Adaptive Matching Order, and Failing Set Together.
+struct FailingSets {
+    /// For each query node, the query nodes that constrain its candidates,
+    /// including itself.
+    ancestors: Vec<Bitset>,
+    /// For each depth, the union of the failing sets of the explored
+    /// candidates of the query node at that depth.
+    failing: Vec<Bitset>,
+    /// For each depth, if one of the explored candidates of the query node
+    /// at that depth led to an embedding, i.e. the failing set is empty.
+    found: Vec<bool>,
+}
+
+impl FailingSets {
+    fn new(
+        order: &[usize],
+        visited_neighbors: &[Vec<usize>],
+        visited_non_neighbors: &[Vec<usize>],
+        symmetry_bounds: &[Vec<(usize, Ordering)>],
+    ) -> Self {
+        let node_count = order.len();
+        let mut ancestors = vec![Bitset::new(node_count); node_count];
+
+        for (depth, &u) in order.iter().enumerate() {
+            let mut u_ancestors = Bitset::new(node_count);
+            u_ancestors.insert(u);
+
+            let dependencies = visited_neighbors[depth]
+                .iter()
+                .chain(visited_non_neighbors[depth].iter())
+                .chain(symmetry_bounds[depth].iter().map(|(u_other, _)| u_other));
+
+            for &dependency in dependencies {
+                u_ancestors.union_with(&ancestors[dependency]);
+            }
+
+            ancestors[u] = u_ancestors;
+        }
+
+        Self {
+            ancestors,
+            failing: vec![Bitset::new(node_count); node_count],
+            found: vec![false; node_count],
+        }
+    }
+
+    /// Starts exploring the candidates of `u` at the given depth.
+    ///
+    /// A candidate that is already mapped to another query node fails due to
+    /// both query nodes. If there are no candidates at all, `u` fails due to
+    /// the query nodes that constrain its candidates.
+    fn descend(&mut self, depth: usize, u: usize, conflicts: &[usize], has_candidates: bool) {
+        let failing = &mut self.failing[depth];
+        failing.clear();
+        self.found[depth] = false;
+
+        if !has_candidates || !conflicts.is_empty() {
+            failing.union_with(&self.ancestors[u]);
+        }
+        for &u_other in conflicts {
+            failing.union_with(&self.ancestors[u_other]);
+        }
+    }
+
+    /// Marks that a candidate at the given depth completed an embedding.
+    fn found(&mut self, depth: usize) {
+        self.found[depth] = true;
+    }
+
+    /// Finishes exploring the candidates at the given depth and adds their
+    /// failing set to the candidate of the parent depth.
+    ///
+    /// Returns `true` if the remaining candidates of the parent can be
+    /// skipped, because the failing set does not contain the parent.
+    fn backtrack(&mut self, depth: usize, order: &[usize]) -> bool {
+        let (parent, child) = self.failing.split_at_mut(depth);
+        let (parent, child) = (&mut parent[depth - 1], &child[0]);
+
+        if self.found[depth] {
+            self.found[depth - 1] = true;
+            false
+        } else if !child.contains(order[depth - 1]) {
+            if !self.found[depth - 1] {
+                parent.clone_from(child);
+            }
+            true
+        } else {
+            if !self.found[depth - 1] {
+                parent.union_with(child);
+            }
+            false
+        }
+    }
+}
+
+/// Adapts an action that consumes all embeddings to the core enumeration,
+/// which allows the action to stop early.
+pub(crate) fn continuing<F>(mut action: F) -> impl FnMut(&[usize]) -> ControlFlow<()>
+where
+    F: FnMut(&[usize]),
+{
+    move |embedding| {
+        action(embedding);
+        ControlFlow::Continue(())
+    }
+}
+
+/// Adapts a comparator of data nodes to the core enumeration, which sorts
+/// the valid candidates of each query node using the comparator.
+pub(crate) fn sorting<C>(mut cmp: C) -> impl FnMut(usize, &mut [usize])
+where
+    C: FnMut(usize, usize, usize) -> Ordering,
+{
+    move |u, valid| valid.sort_unstable_by(|v1, v2| cmp(u, *v1, *v2))
 }
 
 /// For each node in the query graph stores which
@@ -124,45 +1318,436 @@ fn visited_neighbors(query_graph: &Graph, order: &[usize]) -> Vec<Vec<usize>> {
     blacklist
 }
 
+/// For each node in the query graph stores which of the nodes that must
+/// not be adjacent to it in the data graph already have been visited
+/// according to the matching order.
+///
+/// For induced embeddings, these are all non-adjacent query nodes, which
+/// includes the anti-edges. Otherwise, only anti-edges are considered.
+fn visited_non_neighbors(query_graph: &Graph, order: &[usize], induced: bool) -> Vec<Vec<usize>> {
+    order
+        .iter()
+        .enumerate()
+        .map(|(depth, &node)| {
+            order[..depth]
+                .iter()
+                .copied()
+                .filter(|&visited| {
+                    if induced {
+                        !query_graph.exists(node, visited)
+                    } else {
+                        query_graph.is_anti_edge(node, visited)
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Translates the symmetry constraints into bounds for each depth, i.e. the
+/// already visited query nodes and how a candidate must compare to their
+/// data nodes.
+fn symmetry_bounds(order: &[usize], constraints: &[(usize, usize)]) -> Vec<Vec<(usize, Ordering)>> {
+    let mut position = vec![0; order.len()];
+    for (depth, &u) in order.iter().enumerate() {
+        position[u] = depth;
+    }
+
+    let mut bounds = vec![Vec::new(); order.len()];
+
+    for &(smaller, larger) in constraints {
+        if position[smaller] < position[larger] {
+            bounds[position[larger]].push((smaller, Ordering::Greater));
+        } else {
+            bounds[position[smaller]].push((larger, Ordering::Less));
+        }
+    }
+
+    bounds
+}
+
+/// For each visited neighbor, stores the direction of the
+/// query edge as seen from the query node at that depth.
+fn edge_directions(
+    query_graph: &Graph,
+    order: &[usize],
+    visited_neighbors: &[Vec<usize>],
+) -> Vec<Vec<EdgeDirection>> {
+    visited_neighbors
+        .iter()
+        .enumerate()
+        .map(|(depth, neighbors)| {
+            neighbors
+                .iter()
+                .map(|&neighbor| edge_direction(query_graph, order[depth], neighbor))
+                .collect()
+        })
+        .collect()
+}
+
+/// The labels of a query edge as seen from the query node at the current
+/// depth, i.e. of the edge to a visited neighbor and of the edge back, see
+/// [`query_edge_labels`].
+pub(crate) type EdgeLabels = (Option<usize>, Option<usize>);
+
+/// For each visited neighbor, stores the labels of the query edge,
+/// if the query graph has been loaded with edge labels.
+fn edge_labels(
+    query_graph: &Graph,
+    order: &[usize],
+    visited_neighbors: &[Vec<usize>],
+) -> Vec<Vec<EdgeLabels>> {
+    visited_neighbors
+        .iter()
+        .enumerate()
+        .map(|(depth, neighbors)| {
+            neighbors
+                .iter()
+                .map(|&neighbor| {
+                    let direction = edge_direction(query_graph, order[depth], neighbor);
+                    query_edge_labels(query_graph, order[depth], neighbor, direction)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns the labels that the data edges need to have to match the query
+/// edge between `source` and `target` in the given direction.
+///
+/// The first label is the one of the edge from `source` to `target`, or of
+/// any edge if the direction does not matter, the second one is the label
+/// of the edge back, if the query edge has one.
+fn query_edge_labels(
+    query_graph: &Graph,
+    source: usize,
+    target: usize,
+    direction: EdgeDirection,
+) -> EdgeLabels {
+    let label = |direction| {
+        query_graph
+            .edge_labels_between(source, target, direction)
+            .next()
+    };
+
+    match direction {
+        EdgeDirection::Any | EdgeDirection::Outgoing => (label(direction), None),
+        EdgeDirection::Incoming => (None, label(direction)),
+        EdgeDirection::Both => (
+            label(EdgeDirection::Outgoing),
+            label(EdgeDirection::Incoming),
+        ),
+    }
+}
+
+/// Returns `true` if the data edges between `source` and `target` have the
+/// labels of a query edge in the given direction, see [`query_edge_labels`].
+/// Parallel data edges match if one of them has the label.
+pub(crate) fn has_edge_labels(
+    data_graph: &Graph,
+    source: usize,
+    target: usize,
+    direction: EdgeDirection,
+    (label, reverse_label): EdgeLabels,
+) -> bool {
+    let has_label = |direction, label: Option<usize>| {
+        label.is_none_or(|label| {
+            data_graph
+                .edge_labels_between(source, target, direction)
+                .any(|data_label| data_label == label)
+        })
+    };
+
+    match direction {
+        EdgeDirection::Any => has_label(EdgeDirection::Any, label),
+        _ => {
+            has_label(EdgeDirection::Outgoing, label)
+                && has_label(EdgeDirection::Incoming, reverse_label)
+        }
+    }
+}
+
+fn edge_direction(query_graph: &Graph, source: usize, target: usize) -> EdgeDirection {
+    if !query_graph.is_directed() || query_graph.is_undirected_edge(source, target) {
+        return EdgeDirection::Any;
+    }
+
+    match (
+        query_graph.exists_directed(source, target),
+        query_graph.exists_directed(target, source),
+    ) {
+        (true, true) => EdgeDirection::Both,
+        (true, false) => EdgeDirection::Outgoing,
+        (false, true) => EdgeDirection::Incoming,
+        (false, false) => EdgeDirection::Any,
+    }
+}
+
+/// Returns `true` if there are no bounds or the weight of a data edge
+/// between `source` and `target` in the direction of the query edge is
+/// within them. Parallel data edges match if one of them is within the
+/// bounds, edges without weight never satisfy bounds.
+pub(crate) fn has_weight_within(
+    data_graph: &Graph,
+    source: usize,
+    target: usize,
+    direction: EdgeDirection,
+    bounds: Option<(f64, f64)>,
+) -> bool {
+    bounds.is_none_or(|(min, max)| {
+        let is_within = |direction| {
+            data_graph
+                .edge_weights_between(source, target, direction)
+                .any(|weight| min <= weight && weight <= max)
+        };
+
+        match direction {
+            EdgeDirection::Both => {
+                is_within(EdgeDirection::Outgoing) && is_within(EdgeDirection::Incoming)
+            }
+            _ => is_within(direction),
+        }
+    })
+}
+
+/// Computes the valid candidates for the query node at the given depth.
+///
+/// The candidates adjacent to the data node of a visited neighbor are found
+/// by intersecting the sorted candidates with its sorted adjacency list,
+/// choosing the neighbor with the fewest adjacent data nodes. The remaining
+/// edges are checked for each of those candidates.
+///
+/// Returns `false` if the edge check budget is exhausted before all
+/// candidates could be validated. The intersection counts as a single check.
+#[allow(clippy::too_many_arguments)]
 fn generate_valid_candidates(
     data_graph: &Graph,
     depth: usize,
     embedding: &[usize],
     idx_count: &mut [usize],
     valid_candidates: &mut [Vec<usize>],
-    visited: &[bool],
+    visited: Option<&VisitedSet>,
     visited_neighbors: &[Vec<usize>],
+    edge_directions: &[Vec<EdgeDirection>],
+    edge_labels: &[Vec<EdgeLabels>],
+    visited_non_neighbors: &[Vec<usize>],
+    symmetry_bounds: &[Vec<(usize, Ordering)>],
+    edge_weight_bounds: Option<(f64, f64)>,
     order: &[usize],
     candidates: &Candidates,
-) {
+    mut conflicts: Option<&mut Vec<usize>>,
+    edge_checks: &mut u64,
+    max_edge_checks: u64,
+) -> bool {
     let u = order[depth];
 
-    idx_count[depth] = 0;
+    if let Some(conflicts) = conflicts.as_mut() {
+        conflicts.clear();
+    }
+    let u_candidates = candidates.candidates(u);
+    let valid = &mut valid_candidates[depth];
+
+    let adjacent = |nbr_idx: usize| {
+        let u_nbr_v = embedding[visited_neighbors[depth][nbr_idx]];
+        match edge_directions[depth][nbr_idx] {
+            EdgeDirection::Any => data_graph.neighbors(u_nbr_v),
+            EdgeDirection::Outgoing => data_graph.in_neighbors(u_nbr_v),
+            EdgeDirection::Incoming | EdgeDirection::Both => data_graph.out_neighbors(u_nbr_v),
+        }
+    };
+
+    // Intersecting with the smallest adjacency list is the cheapest.
+    let intersected =
+        (0..visited_neighbors[depth].len()).min_by_key(|&nbr_idx| adjacent(nbr_idx).len());
+
+    match intersected {
+        Some(nbr_idx) => {
+            if *edge_checks == max_edge_checks {
+                return false;
+            }
+            *edge_checks += 1;
+
+            intersect_sorted(u_candidates, adjacent(nbr_idx), valid);
+        }
+        None => {
+            valid.clear();
+            valid.extend_from_slice(u_candidates);
+        }
+    }
+
+    let mut count = 0;
+
+    for idx in 0..valid.len() {
+        let v = valid[idx];
+
+        // Without a visited set, data nodes may be mapped more than once.
+        let is_visited = visited.is_some_and(|visited| visited.contains(v));
+
+        // Conflicts are only relevant for otherwise valid candidates.
+        if is_visited && conflicts.is_none() {
+            continue;
+        }
+
+        // Symmetry breaking only compares node ids and needs no edge checks.
+        if !symmetry_bounds[depth]
+            .iter()
+            .all(|&(u_other, ordering)| v.cmp(&embedding[u_other]) == ordering)
+        {
+            continue;
+        }
+
+        let mut is_valid = true;
+
+        // Visited neighbors contains the adjacent query nodes that
+        // we already evaluated and mapped to a data node. We need
+        // to make sure that for each relationship to those neighbors
+        // there exists a relationship in the data graph that points
+        // to the candidate node v.
+        for (nbr_idx, ((u_nbr, direction), labels)) in visited_neighbors[depth]
+            .iter()
+            .zip(edge_directions[depth].iter())
+            .zip(edge_labels[depth].iter())
+            .enumerate()
+        {
+            // The intersection already found an edge to this neighbor,
+            // but not the reverse edge, the edge labels or the edge weight.
+            if Some(nbr_idx) == intersected
+                && *direction != EdgeDirection::Both
+                && *labels == (None, None)
+                && edge_weight_bounds.is_none()
+            {
+                continue;
+            }
+
+            let u_nbr_v = embedding[*u_nbr];
+
+            if *edge_checks == max_edge_checks {
+                return false;
+            }
+            *edge_checks += 1;
+
+            let exists = match direction {
+                EdgeDirection::Any => data_graph.exists(v, u_nbr_v),
+                EdgeDirection::Outgoing => data_graph.exists_directed(v, u_nbr_v),
+                EdgeDirection::Incoming => data_graph.exists_directed(u_nbr_v, v),
+                EdgeDirection::Both => {
+                    data_graph.exists_directed(v, u_nbr_v) && data_graph.exists_directed(u_nbr_v, v)
+                }
+            };
+
+            // A labeled query edge requires the same label in the data graph.
+            let label_matches = has_edge_labels(data_graph, v, u_nbr_v, *direction, *labels);
+
+            if !exists
+                || !label_matches
+                || !has_weight_within(data_graph, v, u_nbr_v, *direction, edge_weight_bounds)
+            {
+                is_valid = false;
+                break;
+            }
+        }
+
+        // For induced embeddings, v must not be adjacent to a data node
+        // that is mapped to a query node which is not adjacent to u. The
+        // same holds for query nodes that are connected to u by an anti-edge.
+        if is_valid {
+            for u_non_nbr in visited_non_neighbors[depth].iter() {
+                if *edge_checks == max_edge_checks {
+                    return false;
+                }
+                *edge_checks += 1;
+
+                if data_graph.exists(v, embedding[*u_non_nbr]) {
+                    is_valid = false;
+                    break;
+                }
+            }
+        }
+
+        if is_valid && is_visited {
+            if let Some(conflicts) = conflicts.as_mut() {
+                if let Some(&u_other) = order[..depth]
+                    .iter()
+                    .find(|&&u_other| embedding[u_other] == v)
+                {
+                    conflicts.push(u_other);
+                }
+            }
+            continue;
+        }
+
+        // We could successfully map each relationship from the query
+        // graph to a relationship in the data graph that ends in v.
+        // Therefore, v is a validate candidate for the current depth.
+        if is_valid {
+            valid[count] = v;
+            count += 1;
+        }
+    }
+
+    idx_count[depth] = count;
+
+    true
+}
+
+fn is_sorted(candidates: &Candidates, query_node_count: usize) -> bool {
+    (0..query_node_count).all(|u| {
+        candidates
+            .candidates(u)
+            .windows(2)
+            .all(|window| window[0] <= window[1])
+    })
+}
+
+/// Returns a sorted copy of the candidates.
+fn sorted(candidates: &Candidates, query_node_count: usize) -> Candidates {
+    let mut sorted = Candidates::new(
+        (0..query_node_count)
+            .map(|u| candidates.candidates(u).to_vec())
+            .collect(),
+    );
+    sorted.sort();
+    sorted
+}
 
-    for v in candidates.candidates(u) {
-        if !visited[*v] {
-            let mut valid = true;
+/// Writes the elements that are contained in both sorted slices to `out`.
+///
+/// Gallops through the larger slice if the sizes differ a lot, e.g. for a
+/// few candidates and the neighbors of a high degree node, and merges both
+/// slices otherwise.
+pub(crate) fn intersect_sorted(a: &[usize], b: &[usize], out: &mut Vec<usize>) {
+    out.clear();
 
-            // Visited neighbors contains the adjacent query nodes that
-            // we already evaluated and mapped to a data node. We need
-            // to make sure that for each relationship to those neighbors
-            // there exists a relationship in the data graph that points
-            // to the candidate node v.
-            for u_nbr in &visited_neighbors[depth][..] {
-                let u_nbr_v = embedding[*u_nbr];
+    let (small, large) = if a.len() <= b.len() { (a, b) } else { (b, a) };
 
-                if !data_graph.exists(*v, u_nbr_v) {
-                    valid = false;
-                    break;
+    if small.len() * 16 < large.len() {
+        let mut large = large;
+        for &value in small {
+            // Find the range that contains value by doubling the step size.
+            let mut bound = 1;
+            while bound < large.len() && large[bound] < value {
+                bound *= 2;
+            }
+            let end = usize::min(bound + 1, large.len());
+            match large[..end].binary_search(&value) {
+                Ok(idx) => {
+                    out.push(value);
+                    large = &large[idx + 1..];
                 }
+                Err(idx) => large = &large[idx..],
             }
-
-            // We could successfully map each relationship from the query
-            // graph to a relationship in the data graph that ends in v.
-            // Therefore, v is a validate candidate for the current depth.
-            if valid {
-                valid_candidates[depth][idx_count[depth]] = *v;
-                idx_count[depth] += 1;
+        }
+    } else {
+        let (mut i, mut j) = (0, 0);
+        while i < small.len() && j < large.len() {
+            match small[i].cmp(&large[j]) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    out.push(small[i]);
+                    i += 1;
+                    j += 1;
+                }
             }
         }
     }
@@ -171,13 +1756,26 @@ fn generate_valid_candidates(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{filter, graph::GdlGraph, order};
+    use crate::{
+        filter,
+        graph::{GdlGraph, LoadConfig},
+        order,
+    };
+    use std::sync::atomic::AtomicUsize;
     use trim_margin::MarginTrimmable;
 
     fn graph(gdl: &str) -> GdlGraph {
         gdl.trim_margin().unwrap().parse::<GdlGraph>().unwrap()
     }
 
+    /// Builds a clique with the given number of nodes that all have label 0.
+    fn clique(node_count: usize) -> Graph {
+        let edges = (0..node_count)
+            .flat_map(|source| (source + 1..node_count).map(move |target| (source, target)))
+            .collect::<Vec<_>>();
+        Graph::from_edges(&vec![0; node_count], &edges).unwrap()
+    }
+
     const TEST_GRAPH: &str = "
         |(n0:L0)
         |(n1:L1)
@@ -192,6 +1790,172 @@ mod tests {
         |(n3)-->(n4)
         |";
 
+    #[test]
+    fn test_intersect_sorted() {
+        let mut out = Vec::new();
+
+        intersect_sorted(&[1, 3, 5, 7], &[2, 3, 4, 7, 8], &mut out);
+        assert_eq!(out, vec![3, 7]);
+
+        intersect_sorted(&[], &[1, 2], &mut out);
+        assert_eq!(out, Vec::<usize>::new());
+
+        // Gallops through the larger slice.
+        let large = (0..1000).map(|n| n * 3).collect::<Vec<_>>();
+        intersect_sorted(&[0, 4, 9, 2997, 3000], &large, &mut out);
+        assert_eq!(out, vec![0, 9, 2997]);
+        intersect_sorted(&large, &[1, 3, 6], &mut out);
+        assert_eq!(out, vec![3, 6]);
+    }
+
+    #[test]
+    fn test_counts_match_brute_force() {
+        // Counts all injective, label and edge preserving mappings.
+        fn brute_force(
+            data_graph: &Graph,
+            query_graph: &Graph,
+            embedding: &mut Vec<usize>,
+        ) -> usize {
+            let u = embedding.len();
+            if u == query_graph.node_count() {
+                return 1;
+            }
+
+            let mut count = 0;
+            for v in data_graph.nodes_by_label(query_graph.label(u)).to_vec() {
+                let valid = !embedding.contains(&v)
+                    && query_graph
+                        .neighbors(u)
+                        .iter()
+                        .filter(|&&u_nbr| u_nbr < u)
+                        .all(|&u_nbr| data_graph.exists(v, embedding[u_nbr]));
+
+                if valid {
+                    embedding.push(v);
+                    count += brute_force(data_graph, query_graph, embedding);
+                    embedding.pop();
+                }
+            }
+            count
+        }
+
+        // A pseudo-random graph with 40 nodes, 3 labels and 120 edges.
+        let mut state = 42_u64;
+        let mut next = |bound: usize| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as usize % bound
+        };
+        let labels = (0..40).map(|_| next(3)).collect::<Vec<_>>();
+        let mut edges = Vec::new();
+        while edges.len() < 120 {
+            let (source, target) = (next(40), next(40));
+            if source != target
+                && !edges.contains(&(source, target))
+                && !edges.contains(&(target, source))
+            {
+                edges.push((source, target));
+            }
+        }
+        let data_graph = Graph::from_edges(&labels, &edges).unwrap();
+
+        let queries = [
+            // triangle
+            (vec![0, 1, 2], vec![(0, 1), (1, 2), (2, 0)]),
+            // 4-cycle
+            (vec![0, 1, 0, 2], vec![(0, 1), (1, 2), (2, 3), (3, 0)]),
+            // star
+            (vec![1, 0, 0, 2], vec![(0, 1), (0, 2), (0, 3)]),
+            // single edge
+            (vec![0, 1], vec![(0, 1)]),
+        ];
+
+        for (labels, edges) in queries {
+            let query_graph = Graph::from_edges(&labels, &edges).unwrap();
+            let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+            let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+            let expected = brute_force(&data_graph, &query_graph, &mut Vec::new());
+
+            assert_eq!(
+                gql(&data_graph, &query_graph, &candidates, &order),
+                expected
+            );
+            assert_eq!(
+                count(&data_graph, &query_graph, &candidates, &order),
+                expected
+            );
+            assert_eq!(
+                gql_with_options(
+                    &data_graph,
+                    &query_graph,
+                    &candidates,
+                    &order,
+                    None::<Arrangement>,
+                    Options {
+                        failing_sets: true,
+                        ..Options::default()
+                    },
+                    continuing(|_| {}),
+                )
+                .embedding_count,
+                expected
+            );
+            assert_eq!(
+                vf2(&data_graph, &query_graph, &candidates, &order),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_failing_sets() {
+        // n7 is the only candidate of u3, but it is not adjacent to n0.
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L1),(n3:L1),(n4:L2),(n5:L2),(n6:L2),(n7:L3)
+            |(n0)-->(n1),(n0)-->(n2),(n0)-->(n3)
+            |(n1)-->(n4),(n1)-->(n5),(n2)-->(n5),(n2)-->(n6),(n3)-->(n4),(n3)-->(n6)
+            |(n1)-->(n7)
+            |",
+        );
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L2),(n3:L3)
+            |(n0)-->(n1),(n1)-->(n2),(n0)-->(n3)
+            |",
+        );
+
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let order = [0, 1, 2, 3];
+
+        let enumerate = |failing_sets: bool| {
+            gql_with_options(
+                &data_graph,
+                &query_graph,
+                &candidates,
+                &order,
+                None::<Arrangement>,
+                Options {
+                    failing_sets,
+                    ..Options::default()
+                },
+                continuing(|_| {}),
+            )
+        };
+
+        let summary = enumerate(false);
+        let pruned = enumerate(true);
+
+        assert_eq!(summary.embedding_count, 0);
+        assert_eq!(pruned.embedding_count, 0);
+        // Mapping u3 fails due to u0 only, so the first candidates of u1
+        // and u2 are the only ones that need to be explored.
+        assert_eq!(summary.edge_checks, 10);
+        assert_eq!(pruned.edge_checks, 3);
+    }
+
     #[test]
     fn test_visited_neighbors() {
         let graph = graph(TEST_GRAPH);
@@ -272,4 +2036,457 @@ mod tests {
         assert_eq!(embeddings[0], vec![1, 2, 3, 4]);
         assert_eq!(embeddings[1], vec![3, 4, 1, 2]);
     }
+
+    #[test]
+    fn test_cancel() {
+        // A clique of 8 nodes contains 8 * 7 * 6 embeddings of a triangle.
+        let data_graph = clique(8);
+        let query_graph = clique(3);
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+        let stop = AtomicBool::new(false);
+        let mut seen = 0;
+        let count = gql_with_cancel(
+            &data_graph,
+            &query_graph,
+            &candidates,
+            &order,
+            &stop,
+            |_| {
+                seen += 1;
+                if seen == 10 {
+                    stop.store(true, Relaxed);
+                }
+            },
+        );
+
+        // The first two query nodes have 6 common candidates for the last
+        // one. Those are still checked after the 10th embedding, the search
+        // stops before descending again.
+        assert_eq!(count, 12);
+        assert_eq!(seen, count);
+
+        stop.store(false, Relaxed);
+        assert_eq!(
+            gql_with_cancel(
+                &data_graph,
+                &query_graph,
+                &candidates,
+                &order,
+                &stop,
+                |_| {}
+            ),
+            336
+        );
+
+        // A clique of 30 nodes contains 30 * 29 * ... * 25 embeddings of a
+        // 6-clique, which takes far longer than it takes to cancel.
+        let data_graph = clique(30);
+        let query_graph = clique(6);
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+        let full_count = (25..=30).product::<usize>();
+
+        let stop = AtomicBool::new(false);
+        let found = AtomicUsize::new(0);
+        let count = std::thread::scope(|scope| {
+            let search = scope.spawn(|| {
+                gql_with_cancel(
+                    &data_graph,
+                    &query_graph,
+                    &candidates,
+                    &order,
+                    &stop,
+                    |_| {
+                        found.fetch_add(1, Relaxed);
+                    },
+                )
+            });
+
+            // Cancels the search once it is running.
+            while found.load(Relaxed) == 0 {
+                std::thread::yield_now();
+            }
+            stop.store(true, Relaxed);
+
+            search.join().unwrap()
+        });
+
+        assert!(count > 0);
+        assert!(count <= full_count);
+        assert_eq!(count, found.load(Relaxed));
+    }
+
+    #[test]
+    fn test_progress() {
+        // A clique of 8 nodes contains 8 * 7 * 6 embeddings of a triangle.
+        let data_graph = clique(8);
+        let query_graph = clique(3);
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+        for every in [3, 50] {
+            let mut events = Vec::new();
+            let count = gql_with_progress(
+                &data_graph,
+                &query_graph,
+                &candidates,
+                &order,
+                every,
+                |progress| events.push(progress),
+                |_| {},
+            );
+
+            assert_eq!(count, 336);
+            assert_eq!(count, gql(&data_graph, &query_graph, &candidates, &order));
+
+            let last = events.last().unwrap();
+            assert_eq!(last.embeddings_so_far, count);
+            assert_eq!(last.root_candidates_done, 8);
+            assert!(events
+                .windows(2)
+                .all(|w| w[0].embeddings_so_far <= w[1].embeddings_so_far));
+
+            let embedding_events = events.iter().filter(|p| p.current_depth == 2).count();
+            let root_events = events.iter().filter(|p| p.current_depth == 0).count();
+            assert_eq!(embedding_events, count / every);
+            assert_eq!(root_events, 8_usize.div_ceil(every));
+        }
+    }
+
+    #[test]
+    fn test_diamond_canonical() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L1),(n1:L2),(n2:L1),(n3:L2)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |",
+        );
+
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+
+        // Mapping n0 to a smaller data node than n2 also determines
+        // the mapping of n1 and n3 and rules out the mirrored embedding.
+        for order in [vec![0, 1, 2, 3], vec![2, 3, 0, 1], vec![1, 0, 3, 2]] {
+            let mut embeddings = Vec::new();
+            let embedding_count = gql_canonical_with(
+                &data_graph,
+                &query_graph,
+                &candidates,
+                &order,
+                |embedding| embeddings.push(Vec::from(embedding)),
+            );
+
+            assert_eq!(embedding_count, 1);
+            assert_eq!(embeddings, vec![vec![1, 2, 3, 4]]);
+        }
+
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+        assert_eq!(gql(&data_graph, &query_graph, &candidates, &order), 2);
+        assert_eq!(
+            gql_canonical(&data_graph, &query_graph, &candidates, &order),
+            1
+        );
+    }
+
+    #[test]
+    fn test_embeddings() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L1),(n1:L2),(n2:L1),(n3:L2)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |",
+        );
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+        let mut expected = Vec::new();
+        gql_with(
+            &data_graph,
+            &query_graph,
+            &candidates,
+            &order,
+            |embedding| expected.push(Vec::from(embedding)),
+        );
+        assert_eq!(
+            Embeddings::new(&data_graph, &query_graph, &candidates, &order).collect::<Vec<_>>(),
+            expected
+        );
+
+        let embeddings = |options| {
+            Embeddings::with_options(&data_graph, &query_graph, &candidates, &order, options)
+                .collect::<Vec<_>>()
+        };
+
+        // The iterator runs the same search as the callback, including the
+        // symmetry constraints, the edge check budget and the stop flag.
+        let symmetry_constraints = [(0, 2)];
+        assert_eq!(
+            embeddings(Options {
+                symmetry_constraints: &symmetry_constraints,
+                ..Options::default()
+            }),
+            vec![vec![1, 2, 3, 4]]
+        );
+
+        let mut budget_exceeded = false;
+        for max_edge_checks in 0..10 {
+            let options = Options {
+                max_edge_checks: Some(max_edge_checks),
+                ..Options::default()
+            };
+            let mut expected = Vec::new();
+            let summary = gql_with_options(
+                &data_graph,
+                &query_graph,
+                &candidates,
+                &order,
+                None::<Arrangement>,
+                options,
+                continuing(|embedding| expected.push(Vec::from(embedding))),
+            );
+            budget_exceeded |= summary.budget_exceeded;
+
+            assert_eq!(embeddings(options), expected);
+        }
+        assert!(budget_exceeded);
+
+        let stop = AtomicBool::new(true);
+        assert!(embeddings(Options {
+            stop: Some(&stop),
+            ..Options::default()
+        })
+        .is_empty());
+    }
+
+    #[test]
+    fn test_sample() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        // A clique of 8 nodes contains 8 * 7 * 6 embeddings of a triangle.
+        let data_graph = clique(8);
+        let query_graph = clique(3);
+
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+        let mut all = Vec::new();
+        gql_with(
+            &data_graph,
+            &query_graph,
+            &candidates,
+            &order,
+            |embedding| all.push(Vec::from(embedding)),
+        );
+        assert_eq!(all.len(), 336);
+
+        let sample = |k: usize, seed: u64| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            super::sample(&data_graph, &query_graph, &candidates, &order, k, &mut rng)
+        };
+
+        let samples = sample(10, 42);
+        assert_eq!(samples.len(), 10);
+        assert!(samples.iter().all(|embedding| all.contains(embedding)));
+        assert_eq!(samples, sample(10, 42));
+        // The first embedding of the unshuffled enumeration is unlikely.
+        assert_ne!(samples, all[..10].to_vec());
+
+        assert_eq!(sample(1000, 42).len(), all.len());
+        assert!(sample(0, 42).is_empty());
+    }
+
+    #[test]
+    fn test_estimate() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let mut rng = StdRng::seed_from_u64(42);
+
+        // Each walk in a clique has the same probability, so every sample
+        // is exact.
+        let edges = (0..8)
+            .flat_map(|source| (source + 1..8).map(move |target| (source, target)))
+            .collect::<Vec<_>>();
+        let data_graph = Graph::from_edges(&[0; 8], &edges).unwrap();
+        let query_graph = Graph::from_edges(&[0; 3], &[(0, 1), (0, 2), (1, 2)]).unwrap();
+
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+        let estimate = |samples: usize, rng: &mut StdRng| {
+            super::estimate(&data_graph, &query_graph, &candidates, &order, samples, rng)
+        };
+
+        assert_eq!(estimate(10, &mut rng), 336.0);
+        assert_eq!(estimate(0, &mut rng), 0.0);
+
+        // Walks in a graph without embeddings always get stuck.
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L1),(n1:L1),(n2:L1)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |(n2)-->(n0)
+            |",
+        );
+
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+        assert_eq!(
+            super::estimate(&data_graph, &query_graph, &candidates, &order, 10, &mut rng),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_diamond_into_sender() {
+        use std::{sync::mpsc::sync_channel, thread};
+
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L1),(n1:L2),(n2:L1),(n3:L2)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |",
+        );
+
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+        // The search blocks until the first embedding has been received.
+        let (sender, receiver) = sync_channel(1);
+        let (sent_count, embeddings) = thread::scope(|scope| {
+            let search = scope
+                .spawn(|| gql_into_sender(&data_graph, &query_graph, &candidates, &order, sender));
+            let embeddings = receiver.iter().collect::<Vec<_>>();
+            (search.join().unwrap(), embeddings)
+        });
+
+        assert_eq!(sent_count, 2);
+        assert_eq!(embeddings, vec![vec![1, 2, 3, 4], vec![3, 4, 1, 2]]);
+
+        // Without a receiver, the search stops at the first embedding.
+        let (sender, receiver) = sync_channel(1);
+        drop(receiver);
+
+        assert_eq!(
+            gql_into_sender(&data_graph, &query_graph, &candidates, &order, sender),
+            0
+        );
+    }
+
+    #[test]
+    fn test_diamond_par() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L1),(n1:L2),(n2:L1),(n3:L2)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |",
+        );
+
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+        assert_eq!(gql_par(&data_graph, &query_graph, &candidates, &order), 2);
+    }
+
+    #[test]
+    fn test_diamond_with_limit() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L1),(n1:L2),(n2:L1),(n3:L2)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |",
+        );
+
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+        let mut embeddings = Vec::new();
+        let embedding_count = gql_with_limit(
+            &data_graph,
+            &query_graph,
+            &candidates,
+            &order,
+            1,
+            |embedding| embeddings.push(Vec::from(embedding)),
+        );
+
+        assert_eq!(embedding_count, 1);
+        assert_eq!(embeddings, vec![vec![1, 2, 3, 4]]);
+
+        let unlimited = gql_with_limit(&data_graph, &query_graph, &candidates, &order, 0, |_| {});
+        assert_eq!(unlimited, 2);
+
+        let exceeding = gql_with_limit(&data_graph, &query_graph, &candidates, &order, 3, |_| {});
+        assert_eq!(exceeding, 2);
+    }
+
+    #[test]
+    fn test_mixed_direction_query() {
+        fn directed_graph(gdl: &str) -> Graph {
+            Graph::from_gdl(
+                &gdl.trim_margin().unwrap(),
+                LoadConfig::default().directed(),
+            )
+            .unwrap()
+        }
+
+        fn count(data_graph: &Graph, query_graph: &Graph) -> usize {
+            let candidates = filter::ldf_filter(data_graph, query_graph).unwrap();
+            let order = order::gql_order(data_graph, query_graph, &candidates);
+            gql(data_graph, query_graph, &candidates, &order)
+        }
+
+        let data_graph = directed_graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L2),(n3:L2),(n4:L0)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |(n3)-->(n1)
+            |(n1)-->(n4)
+            |",
+        );
+
+        // a directed "manages" edge and an undirected "colleague" edge
+        let mixed_query = "
+            |(n0:L0),(n1:L1),(n2:L2)
+            |(n0)-->(n1)
+            |(n1)-[{undirected: true}]->(n2)
+            |";
+        let directed_query = "
+            |(n0:L0),(n1:L1),(n2:L2)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |";
+
+        assert!(directed_graph(mixed_query).is_undirected_edge(2, 1));
+        assert!(!directed_graph(mixed_query).is_undirected_edge(0, 1));
+
+        assert_eq!(count(&data_graph, &directed_graph(mixed_query)), 2);
+        assert_eq!(count(&data_graph, &directed_graph(directed_query)), 1);
+        assert_eq!(count(&data_graph, &graph(directed_query)), 4);
+    }
 }
@@ -0,0 +1,469 @@
+use std::ops::ControlFlow;
+
+use crate::{filter::Candidates, graph::Graph};
+
+use super::{
+    continuing, edge_directions, edge_labels, has_edge_labels, has_weight_within, is_sorted,
+    sorted, visited_neighbors, visited_non_neighbors, EdgeDirection, EdgeLabels, Summary,
+    VisitedSet,
+};
+
+/// Counts all embeddings using a Leapfrog Triejoin.
+pub fn lftj(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+) -> usize {
+    lftj_with(data_graph, query_graph, candidates, order, |_| {})
+}
+
+/// Enumerates all embeddings like [`super::gql_with`], but computes the
+/// valid candidates of each query node using a Leapfrog Triejoin.
+///
+/// Each query edge is treated as a relation over the data edges and the
+/// query nodes are bound in the given order. The domain of a query node is
+/// the intersection of its candidates with the adjacency lists of the data
+/// nodes that its visited neighbors are mapped to. All of these sorted lists
+/// are intersected at once by seeking each list to the largest value seen
+/// so far, which is worst-case optimal for cyclic queries, e.g. cliques,
+/// where GQL intersects with a single adjacency list and checks the
+/// remaining edges for each candidate.
+///
+/// Edge labels, edge weights and anti-edges are checked for each value of
+/// the intersection. Embeddings are emitted in the same order as by GQL.
+pub fn lftj_with<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    action: F,
+) -> usize
+where
+    F: FnMut(&[usize]),
+{
+    lftj_with_budget(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        None,
+        None,
+        continuing(action),
+    )
+    .embedding_count
+}
+
+/// Runs the Leapfrog Triejoin like [`lftj_with`], but stops after the given
+/// number of edge existence checks or once the action breaks. Query edges
+/// are only mapped to data edges whose weight is within the given bounds,
+/// if any.
+///
+/// Each intersection counts as a single check, like in GQL.
+pub(crate) fn lftj_with_budget<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    max_edge_checks: Option<u64>,
+    edge_weight_bounds: Option<(f64, f64)>,
+    mut action: F,
+) -> Summary
+where
+    F: FnMut(&[usize]) -> ControlFlow<()>,
+{
+    let query_node_count = query_graph.node_count();
+    if query_node_count == 0 {
+        return Summary::default();
+    }
+
+    // The leapfrog seeks require sorted candidates.
+    let sorted_candidates;
+    let candidates = if is_sorted(candidates, query_node_count) {
+        candidates
+    } else {
+        sorted_candidates = sorted(candidates, query_node_count);
+        &sorted_candidates
+    };
+
+    let visited_neighbors = visited_neighbors(query_graph, order);
+
+    let mut state = State {
+        data_graph,
+        candidates,
+        order,
+        edge_directions: edge_directions(query_graph, order, &visited_neighbors),
+        edge_labels: edge_labels(query_graph, order, &visited_neighbors),
+        anti_neighbors: visited_non_neighbors(query_graph, order, false),
+        visited_neighbors,
+        edge_weight_bounds,
+        embedding: vec![0; query_node_count],
+        visited: VisitedSet::new(data_graph.node_count()),
+        valid: vec![Vec::new(); query_node_count],
+        relations: Vec::new(),
+        embedding_count: 0,
+        edge_checks: 0,
+        max_edge_checks: max_edge_checks.unwrap_or(u64::MAX),
+        budget_exceeded: false,
+    };
+
+    let _ = state.extend(0, &mut action);
+
+    Summary {
+        embedding_count: state.embedding_count,
+        edge_checks: state.edge_checks,
+        budget_exceeded: state.budget_exceeded,
+    }
+}
+
+/// The partial embedding and the buffers of the join.
+struct State<'a> {
+    data_graph: &'a Graph,
+    candidates: &'a Candidates,
+    order: &'a [usize],
+    /// The visited query neighbors of the query node at each depth.
+    visited_neighbors: Vec<Vec<usize>>,
+    edge_directions: Vec<Vec<EdgeDirection>>,
+    edge_labels: Vec<Vec<EdgeLabels>>,
+    /// The visited query nodes that are connected to the query node at each
+    /// depth by an anti-edge.
+    anti_neighbors: Vec<Vec<usize>>,
+    edge_weight_bounds: Option<(f64, f64)>,
+    /// The data node of each query node, which is the emitted embedding.
+    embedding: Vec<usize>,
+    visited: VisitedSet,
+    /// The intersection at each depth.
+    valid: Vec<Vec<usize>>,
+    /// The sorted lists that are intersected at the current depth.
+    relations: Vec<&'a [usize]>,
+    embedding_count: usize,
+    edge_checks: u64,
+    max_edge_checks: u64,
+    budget_exceeded: bool,
+}
+
+impl State<'_> {
+    fn extend<F>(&mut self, depth: usize, action: &mut F) -> ControlFlow<()>
+    where
+        F: FnMut(&[usize]) -> ControlFlow<()>,
+    {
+        if depth == self.order.len() {
+            self.embedding_count += 1;
+            return action(&self.embedding);
+        }
+
+        if self.check_budget().is_none() {
+            return ControlFlow::Break(());
+        }
+        self.join(depth);
+
+        let u = self.order[depth];
+        let valid = std::mem::take(&mut self.valid[depth]);
+        let mut flow = ControlFlow::Continue(());
+
+        for &v in valid.iter() {
+            if self.visited.contains(v) {
+                continue;
+            }
+
+            match self.is_valid(depth, v) {
+                Some(true) => {}
+                Some(false) => continue,
+                None => {
+                    flow = ControlFlow::Break(());
+                    break;
+                }
+            }
+
+            self.embedding[u] = v;
+            self.visited.insert(v);
+            flow = self.extend(depth + 1, action);
+            self.visited.remove(v);
+
+            if let ControlFlow::Break(()) = flow {
+                break;
+            }
+        }
+
+        self.valid[depth] = valid;
+        flow
+    }
+
+    /// Intersects the candidates of the query node at the given depth with
+    /// the adjacency lists of the data nodes of its visited neighbors.
+    fn join(&mut self, depth: usize) {
+        let data_graph = self.data_graph;
+
+        self.relations.clear();
+        self.relations
+            .push(self.candidates.candidates(self.order[depth]));
+
+        for (&u_nbr, direction) in self.visited_neighbors[depth]
+            .iter()
+            .zip(self.edge_directions[depth].iter())
+        {
+            let u_nbr_v = self.embedding[u_nbr];
+            match direction {
+                EdgeDirection::Any => self.relations.push(data_graph.neighbors(u_nbr_v)),
+                EdgeDirection::Outgoing => self.relations.push(data_graph.in_neighbors(u_nbr_v)),
+                EdgeDirection::Incoming => self.relations.push(data_graph.out_neighbors(u_nbr_v)),
+                EdgeDirection::Both => {
+                    self.relations.push(data_graph.in_neighbors(u_nbr_v));
+                    self.relations.push(data_graph.out_neighbors(u_nbr_v));
+                }
+            }
+        }
+
+        leapfrog_join(&mut self.relations, &mut self.valid[depth]);
+    }
+
+    /// Checks the edge labels, edge weights and anti-edges of a value of the
+    /// intersection at the given depth.
+    ///
+    /// Returns `None` if the edge check budget is exhausted.
+    fn is_valid(&mut self, depth: usize, v: usize) -> Option<bool> {
+        let data_graph = self.data_graph;
+
+        for idx in 0..self.visited_neighbors[depth].len() {
+            let labels = self.edge_labels[depth][idx];
+            if labels == (None, None) && self.edge_weight_bounds.is_none() {
+                continue;
+            }
+
+            self.check_budget()?;
+
+            let u_nbr_v = self.embedding[self.visited_neighbors[depth][idx]];
+            let direction = self.edge_directions[depth][idx];
+            if !has_edge_labels(data_graph, v, u_nbr_v, direction, labels)
+                || !has_weight_within(data_graph, v, u_nbr_v, direction, self.edge_weight_bounds)
+            {
+                return Some(false);
+            }
+        }
+
+        for idx in 0..self.anti_neighbors[depth].len() {
+            self.check_budget()?;
+
+            if data_graph.exists(v, self.embedding[self.anti_neighbors[depth][idx]]) {
+                return Some(false);
+            }
+        }
+
+        Some(true)
+    }
+
+    fn check_budget(&mut self) -> Option<()> {
+        if self.edge_checks == self.max_edge_checks {
+            self.budget_exceeded = true;
+            return None;
+        }
+        self.edge_checks += 1;
+        Some(())
+    }
+}
+
+/// Writes the values that are contained in all of the given sorted lists
+/// to `out`, each value once.
+///
+/// Each list is seeked in turn to the largest value seen so far, until all
+/// lists agree on a value or one of them is exhausted. The lists are
+/// advanced in place.
+fn leapfrog_join(lists: &mut [&[usize]], out: &mut Vec<usize>) {
+    out.clear();
+
+    if lists.is_empty() || lists.iter().any(|list| list.is_empty()) {
+        return;
+    }
+
+    // Starting with the smallest list skips most of the others fastest.
+    let smallest = (0..lists.len())
+        .min_by_key(|&idx| lists[idx].len())
+        .unwrap_or_default();
+    lists.swap(0, smallest);
+
+    let mut target = 0;
+    let mut agreeing = 0;
+    let mut idx = 0;
+
+    loop {
+        let list = seek(lists[idx], target);
+        lists[idx] = list;
+
+        let value = match list.first() {
+            Some(&value) => value,
+            None => return,
+        };
+
+        if value == target {
+            agreeing += 1;
+        } else {
+            target = value;
+            agreeing = 1;
+        }
+
+        if agreeing == lists.len() {
+            out.push(value);
+            target = value + 1;
+            agreeing = 0;
+        }
+
+        idx += 1;
+        if idx == lists.len() {
+            idx = 0;
+        }
+    }
+}
+
+/// Returns the suffix of the sorted list that starts with the first value
+/// that is not smaller than `target`.
+///
+/// Consecutive seeks are close, so the first values are probed one by one
+/// before galloping through the rest of the list.
+fn seek(list: &[usize], target: usize) -> &[usize] {
+    const PROBES: usize = 4;
+
+    match list.iter().take(PROBES).position(|&value| value >= target) {
+        Some(idx) => return &list[idx..],
+        None if list.len() <= PROBES => return &[],
+        None => {}
+    }
+
+    // Find the range that contains target by doubling the step size.
+    let mut bound = PROBES;
+    while bound < list.len() && list[bound] < target {
+        bound *= 2;
+    }
+    let start = bound / 2;
+    let end = usize::min(bound + 1, list.len());
+
+    &list[start + list[start..end].partition_point(|&value| value < target)..]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        enumerate::{gql, gql_with},
+        filter,
+        graph::{GdlGraph, LoadConfig},
+        order,
+    };
+    use trim_margin::MarginTrimmable;
+
+    fn graph(gdl: &str) -> GdlGraph {
+        gdl.trim_margin().unwrap().parse::<GdlGraph>().unwrap()
+    }
+
+    #[test]
+    fn test_leapfrog_join() {
+        let mut out = Vec::new();
+
+        leapfrog_join(&mut [], &mut out);
+        assert!(out.is_empty());
+
+        let (a, b, c) = (
+            &[0, 2, 3, 5, 7, 9][..],
+            &[1, 2, 3, 7, 8][..],
+            &[2, 3, 4, 6, 7][..],
+        );
+        leapfrog_join(&mut [a, b, c], &mut out);
+        assert_eq!(out, vec![2, 3, 7]);
+
+        // Duplicates, e.g. of parallel edges, are emitted once.
+        leapfrog_join(&mut [&[1, 1, 4][..], &[1, 1, 2, 4][..]], &mut out);
+        assert_eq!(out, vec![1, 4]);
+
+        leapfrog_join(&mut [&[0, 3, 6][..]], &mut out);
+        assert_eq!(out, vec![0, 3, 6]);
+
+        leapfrog_join(&mut [&[0, 3, 6][..], &[][..]], &mut out);
+        assert!(out.is_empty());
+
+        leapfrog_join(&mut [&[0, 3, 6][..], &[1, 4, 7][..]], &mut out);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_seek() {
+        let list = [1, 3, 5, 7, 9, 11, 13, 15, 17];
+
+        assert_eq!(seek(&list, 0), &list);
+        assert_eq!(seek(&list, 1), &list);
+        assert_eq!(seek(&list, 4), &list[2..]);
+        assert_eq!(seek(&list, 15), &list[7..]);
+        assert_eq!(seek(&list, 17), &list[8..]);
+        assert!(seek(&list, 18).is_empty());
+        assert!(seek(&[], 1).is_empty());
+    }
+
+    #[test]
+    fn test_lftj_matches_gql() {
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0),(n4:L1),(n5:L1),(n6:L1),(n7:L0)
+            |(n0)-->(n1),(n0)-->(n2),(n0)-->(n3),(n1)-->(n2),(n1)-->(n3),(n2)-->(n3)
+            |(n0)-->(n4),(n1)-->(n4),(n2)-->(n5),(n3)-->(n5),(n4)-->(n5),(n5)-->(n6)
+            |(n6)-->(n2),(n6)-->(n7)
+            |",
+        );
+        let queries = [
+            // triangle
+            "(n0:L0),(n1:L0),(n2:L0),(n0)-->(n1),(n1)-->(n2),(n2)-->(n0)",
+            // 4-clique
+            "(n0:L0),(n1:L0),(n2:L0),(n3:L0),(n0)-->(n1),(n0)-->(n2),(n0)-->(n3),(n1)-->(n2),(n1)-->(n3),(n2)-->(n3)",
+            // diamond
+            "(n0:L0),(n1:L0),(n2:L1),(n3:L0),(n0)-->(n1),(n0)-->(n2),(n1)-->(n2),(n1)-->(n3),(n0)-->(n3)",
+            // path with wildcards
+            "(n0),(n1:L1),(n2),(n0)-->(n1),(n1)-->(n2)",
+            // open triangle whose end nodes must not be adjacent
+            "(n0:L1),(n1:L0),(n2:L0),(n0)-->(n1),(n0)-->(n2),(n1)-[{anti: true}]->(n2)",
+        ];
+
+        for gdl in queries.iter() {
+            let query_graph = graph(gdl);
+            let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+            let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+            let mut expected = Vec::new();
+            gql_with(&data_graph, &query_graph, &candidates, &order, |e| {
+                expected.push(e.to_vec())
+            });
+            let mut actual = Vec::new();
+            let count = lftj_with(&data_graph, &query_graph, &candidates, &order, |e| {
+                actual.push(e.to_vec())
+            });
+
+            assert!(!expected.is_empty(), "query: {}", gdl);
+            assert_eq!(count, expected.len(), "query: {}", gdl);
+            assert_eq!(actual, expected, "query: {}", gdl);
+        }
+    }
+
+    #[test]
+    fn test_lftj_directed_and_labeled() {
+        let load_config = LoadConfig::default().directed().edge_labeled();
+        let data_graph = Graph::from_gdl(
+            "(n0:L0),(n1:L0),(n2:L0),(n0)-[:E1]->(n1),(n1)-[:E1]->(n2),(n2)-[:E2]->(n0),(n1)-[:E1]->(n0)",
+            load_config,
+        )
+        .unwrap();
+        let queries = [
+            "(n0:L0),(n1:L0),(n0)-[:E1]->(n1)",
+            "(n0:L0),(n1:L0),(n0)-[:E1]->(n1),(n1)-[:E1]->(n0)",
+            "(n0:L0),(n1:L0),(n2:L0),(n0)-[:E1]->(n1),(n1)-[:E1]->(n2),(n2)-[:E2]->(n0)",
+        ];
+
+        for gdl in queries.iter() {
+            let query_graph = Graph::from_gdl(gdl, load_config).unwrap();
+            let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+            let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+            assert_eq!(
+                lftj(&data_graph, &query_graph, &candidates, &order),
+                gql(&data_graph, &query_graph, &candidates, &order),
+                "query: {}",
+                gdl
+            );
+        }
+    }
+}
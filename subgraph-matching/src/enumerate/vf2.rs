@@ -0,0 +1,427 @@
+use std::ops::ControlFlow;
+
+use crate::{filter::Candidates, graph::Graph};
+
+use super::{
+    continuing, edge_direction, has_edge_labels, has_weight_within, query_edge_labels,
+    EdgeDirection, Summary,
+};
+
+/// Marks a query node or data node that is not part of the current mapping.
+const UNMAPPED: usize = usize::MAX;
+
+/// Counts all embeddings using a VF2 state space search.
+pub fn vf2(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+) -> usize {
+    vf2_with(data_graph, query_graph, candidates, order, |_| {})
+}
+
+/// Enumerates all embeddings like [`super::gql_with`], but explores the
+/// search space following VF2.
+///
+/// The query nodes are mapped in the given order and each query node is only
+/// mapped to its candidates, i.e. the candidates still restrict the search
+/// space. A candidate pair `(u, v)` is feasible if it passes the following
+/// rules, which are adapted to undirected monomorphisms:
+///
+/// * `R_core`: each edge between `u` and a mapped query node maps to an edge
+///   between `v` and the corresponding data node. This replaces `R_pred` and
+///   `R_succ`, which only differ for directed graphs.
+/// * `R_term`: `v` has at least as many unmapped neighbors in the data
+///   terminal set as `u` has in the query terminal set. The terminal sets
+///   contain the unmapped nodes that are adjacent to a mapped node.
+/// * `R_new`: `v` has at least as many unmapped neighbors as `u`. Since
+///   monomorphisms allow additional data edges, query neighbors outside the
+///   terminal set may be mapped to data neighbors within it, so both sets are
+///   counted together.
+///
+/// Embeddings are emitted in a different order than by GQL, but the same
+/// embeddings are found.
+pub fn vf2_with<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    action: F,
+) -> usize
+where
+    F: FnMut(&[usize]),
+{
+    vf2_with_budget(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        None,
+        None,
+        continuing(action),
+    )
+    .embedding_count
+}
+
+/// Runs the VF2 search like [`vf2_with`], but stops after the given number of
+/// edge existence checks or once the action breaks. Query edges are only
+/// mapped to data edges whose weight is within the given bounds, if any.
+pub(crate) fn vf2_with_budget<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+    order: &[usize],
+    max_edge_checks: Option<u64>,
+    edge_weight_bounds: Option<(f64, f64)>,
+    mut action: F,
+) -> Summary
+where
+    F: FnMut(&[usize]) -> ControlFlow<()>,
+{
+    // There is nothing to map, like in the other enumerations.
+    if query_graph.node_count() == 0 {
+        return Summary::default();
+    }
+
+    let mut state = State {
+        data_graph,
+        query_graph,
+        anti_neighbors: anti_neighbors(query_graph),
+        edge_weight_bounds,
+        core_query: vec![UNMAPPED; query_graph.node_count()],
+        core_data: vec![UNMAPPED; data_graph.node_count()],
+        terminal_query: vec![0; query_graph.node_count()],
+        terminal_data: vec![0; data_graph.node_count()],
+        embedding_count: 0,
+        edge_checks: 0,
+        max_edge_checks: max_edge_checks.unwrap_or(u64::MAX),
+        budget_exceeded: false,
+    };
+
+    let _ = state.extend(candidates, order, 0, &mut action);
+
+    Summary {
+        embedding_count: state.embedding_count,
+        edge_checks: state.edge_checks,
+        budget_exceeded: state.budget_exceeded,
+    }
+}
+
+/// The partial mapping and the terminal sets of the search.
+struct State<'a> {
+    data_graph: &'a Graph,
+    query_graph: &'a Graph,
+    /// The query nodes that are connected to each query node by an anti-edge.
+    anti_neighbors: Vec<Vec<usize>>,
+    edge_weight_bounds: Option<(f64, f64)>,
+    /// The data node of each query node, which is the emitted embedding.
+    core_query: Vec<usize>,
+    /// The query node of each data node.
+    core_data: Vec<usize>,
+    /// The depth plus one at which a query node entered the terminal set,
+    /// or `0` if it is not part of it.
+    terminal_query: Vec<usize>,
+    /// The depth plus one at which a data node entered the terminal set,
+    /// or `0` if it is not part of it.
+    terminal_data: Vec<usize>,
+    embedding_count: usize,
+    edge_checks: u64,
+    max_edge_checks: u64,
+    budget_exceeded: bool,
+}
+
+impl State<'_> {
+    fn extend<F>(
+        &mut self,
+        candidates: &Candidates,
+        order: &[usize],
+        depth: usize,
+        action: &mut F,
+    ) -> ControlFlow<()>
+    where
+        F: FnMut(&[usize]) -> ControlFlow<()>,
+    {
+        if depth == order.len() {
+            self.embedding_count += 1;
+            return action(&self.core_query);
+        }
+
+        let u = order[depth];
+
+        for &v in candidates.candidates(u) {
+            if self.core_data[v] != UNMAPPED {
+                continue;
+            }
+            // A query node that is adjacent to a mapped query node needs to
+            // be mapped to a data node that is adjacent to a mapped data node.
+            if self.terminal_query[u] != 0 && self.terminal_data[v] == 0 {
+                continue;
+            }
+
+            match self.is_feasible(u, v) {
+                Some(true) => {}
+                Some(false) => continue,
+                None => return ControlFlow::Break(()),
+            }
+
+            self.add_pair(u, v, depth + 1);
+            let flow = self.extend(candidates, order, depth + 1, action);
+            self.remove_pair(u, v, depth + 1);
+
+            if let ControlFlow::Break(()) = flow {
+                return flow;
+            }
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    /// Checks the feasibility rules for mapping `u` to `v`.
+    ///
+    /// Returns `None` if the edge check budget is exhausted.
+    fn is_feasible(&mut self, u: usize, v: usize) -> Option<bool> {
+        let (query_graph, data_graph) = (self.query_graph, self.data_graph);
+
+        // R_core
+        for &u_nbr in query_graph.neighbors(u) {
+            let v_nbr = self.core_query[u_nbr];
+            if v_nbr == UNMAPPED {
+                continue;
+            }
+
+            self.check_budget()?;
+
+            let direction = edge_direction(query_graph, u, u_nbr);
+            let exists = match direction {
+                EdgeDirection::Any => data_graph.exists(v, v_nbr),
+                EdgeDirection::Outgoing => data_graph.exists_directed(v, v_nbr),
+                EdgeDirection::Incoming => data_graph.exists_directed(v_nbr, v),
+                EdgeDirection::Both => {
+                    data_graph.exists_directed(v, v_nbr) && data_graph.exists_directed(v_nbr, v)
+                }
+            };
+            let labels = query_edge_labels(query_graph, u, u_nbr, direction);
+
+            if !exists
+                || !has_edge_labels(data_graph, v, v_nbr, direction, labels)
+                || !has_weight_within(data_graph, v, v_nbr, direction, self.edge_weight_bounds)
+            {
+                return Some(false);
+            }
+        }
+
+        for idx in 0..self.anti_neighbors[u].len() {
+            let v_other = self.core_query[self.anti_neighbors[u][idx]];
+            if v_other == UNMAPPED {
+                continue;
+            }
+
+            self.check_budget()?;
+
+            if data_graph.exists(v, v_other) {
+                return Some(false);
+            }
+        }
+
+        // R_term and R_new
+        let (query_terminal, query_unmapped) = count_unmapped(
+            query_graph.neighbors(u),
+            &self.core_query,
+            &self.terminal_query,
+        );
+        let (data_terminal, data_unmapped) = count_unmapped(
+            data_graph.neighbors(v),
+            &self.core_data,
+            &self.terminal_data,
+        );
+
+        Some(query_terminal <= data_terminal && query_unmapped <= data_unmapped)
+    }
+
+    fn check_budget(&mut self) -> Option<()> {
+        if self.edge_checks == self.max_edge_checks {
+            self.budget_exceeded = true;
+            return None;
+        }
+        self.edge_checks += 1;
+        Some(())
+    }
+
+    fn add_pair(&mut self, u: usize, v: usize, tag: usize) {
+        self.core_query[u] = v;
+        self.core_data[v] = u;
+
+        for &u_nbr in self.query_graph.neighbors(u) {
+            if self.terminal_query[u_nbr] == 0 {
+                self.terminal_query[u_nbr] = tag;
+            }
+        }
+        for &v_nbr in self.data_graph.neighbors(v) {
+            if self.terminal_data[v_nbr] == 0 {
+                self.terminal_data[v_nbr] = tag;
+            }
+        }
+    }
+
+    fn remove_pair(&mut self, u: usize, v: usize, tag: usize) {
+        self.core_query[u] = UNMAPPED;
+        self.core_data[v] = UNMAPPED;
+
+        for &u_nbr in self.query_graph.neighbors(u) {
+            if self.terminal_query[u_nbr] == tag {
+                self.terminal_query[u_nbr] = 0;
+            }
+        }
+        for &v_nbr in self.data_graph.neighbors(v) {
+            if self.terminal_data[v_nbr] == tag {
+                self.terminal_data[v_nbr] = 0;
+            }
+        }
+    }
+}
+
+/// Returns the number of unmapped nodes among the given neighbors that are
+/// in the terminal set and the number of all unmapped neighbors.
+fn count_unmapped(neighbors: &[usize], core: &[usize], terminal: &[usize]) -> (usize, usize) {
+    neighbors
+        .iter()
+        .filter(|&&neighbor| core[neighbor] == UNMAPPED)
+        .fold((0, 0), |(in_terminal, unmapped), &neighbor| {
+            (
+                in_terminal + usize::from(terminal[neighbor] != 0),
+                unmapped + 1,
+            )
+        })
+}
+
+fn anti_neighbors(query_graph: &Graph) -> Vec<Vec<usize>> {
+    let mut anti_neighbors = vec![Vec::new(); query_graph.node_count()];
+    for &(source, target) in query_graph.anti_edges() {
+        anti_neighbors[source].push(target);
+        anti_neighbors[target].push(source);
+    }
+    anti_neighbors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        enumerate::{gql, gql_with},
+        filter,
+        graph::{GdlGraph, LoadConfig},
+        order,
+    };
+    use trim_margin::MarginTrimmable;
+
+    fn graph(gdl: &str) -> GdlGraph {
+        gdl.trim_margin().unwrap().parse::<GdlGraph>().unwrap()
+    }
+
+    const TEST_GRAPH: &str = "
+        |(n0:L0)
+        |(n1:L1)
+        |(n2:L2)
+        |(n3:L1)
+        |(n4:L2)
+        |(n0)-->(n1)
+        |(n0)-->(n2)
+        |(n1)-->(n2)
+        |(n1)-->(n3)
+        |(n2)-->(n4)
+        |(n3)-->(n4)
+        |";
+
+    #[test]
+    fn test_vf2_matches_gql() {
+        let data_graph = graph(TEST_GRAPH);
+        let queries = [
+            // line
+            "(n0:L0),(n1:L1),(n2:L2),(n0)-->(n1),(n1)-->(n2)",
+            // triangle
+            "(n0:L0),(n1:L1),(n2:L2),(n0)-->(n1),(n1)-->(n2),(n2)-->(n0)",
+            // diamond
+            "(n0:L1),(n1:L2),(n2:L1),(n3:L2),(n0)-->(n1),(n0)-->(n2),(n1)-->(n3),(n2)-->(n3)",
+            // open triangle with wildcards
+            "(n0),(n1),(n2),(n0)-->(n1),(n1)-->(n2)",
+            // open triangle whose end nodes must not be adjacent
+            "(n0),(n1),(n2),(n0)-->(n1),(n1)-->(n2),(n0)-[{anti: true}]->(n2)",
+        ];
+
+        for query in queries {
+            let query_graph = graph(query);
+            let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+
+            for order in [
+                order::gql_order(&data_graph, &query_graph, &candidates),
+                order::ri_order(&query_graph, &candidates),
+            ] {
+                let mut expected = Vec::new();
+                gql_with(&data_graph, &query_graph, &candidates, &order, |e| {
+                    expected.push(e.to_vec())
+                });
+                let mut actual = Vec::new();
+                let count = vf2_with(&data_graph, &query_graph, &candidates, &order, |e| {
+                    actual.push(e.to_vec())
+                });
+
+                expected.sort();
+                actual.sort();
+
+                assert_eq!(count, expected.len(), "query: {}", query);
+                assert_eq!(actual, expected, "query: {}", query);
+                assert_eq!(
+                    vf2(&data_graph, &query_graph, &candidates, &order),
+                    gql(&data_graph, &query_graph, &candidates, &order)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_vf2_directed() {
+        let directed_graph = |gdl: &str| {
+            Graph::from_gdl(
+                &gdl.trim_margin().unwrap(),
+                LoadConfig::with_neighbor_label_frequency().directed(),
+            )
+            .unwrap()
+        };
+
+        let data_graph = directed_graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L1)
+            |(n0)-->(n1)
+            |(n2)-->(n0)
+            |",
+        );
+        let query_graph = directed_graph("(n0:L0),(n1:L1),(n0)-->(n1)");
+
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+        assert_eq!(vf2(&data_graph, &query_graph, &candidates, &order), 1);
+    }
+
+    #[test]
+    fn test_vf2_budget() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph("(n0),(n1),(n2),(n0)-->(n1),(n1)-->(n2)");
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+        let summary = vf2_with_budget(
+            &data_graph,
+            &query_graph,
+            &candidates,
+            &order,
+            Some(3),
+            None,
+            |_: &[usize]| ControlFlow::Continue(()),
+        );
+
+        assert!(summary.budget_exceeded);
+        assert_eq!(summary.edge_checks, 3);
+        assert!(summary.embedding_count < 18);
+    }
+}
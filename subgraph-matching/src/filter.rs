@@ -1,18 +1,152 @@
 use std::{fmt::Display, usize};
 
-use crate::graph::Graph;
+use crate::{
+    graph::{Graph, ANY_LABEL},
+    Config, Filter,
+};
 
+mod candidate_set;
+mod cfl;
 mod gql;
+mod kcore;
 mod ldf;
 mod nlf;
 
-pub use gql::gql_filter;
+pub use candidate_set::{BitsetCandidates, CandidateSet, VecCandidates};
+pub use cfl::cfl_filter;
+pub use gql::{
+    gql_filter, gql_filter_with, gql_filter_with_backend, gql_filter_with_scratch,
+    GqlFilterScratch, GQL_MAX_REFINEMENT_ROUNDS, GQL_REFINEMENT_THRESHOLD,
+};
+pub use kcore::core_prune;
+pub(crate) use kcore::core_prune_with;
 pub use ldf::ldf_filter;
-pub use nlf::nlf_filter;
+pub use nlf::{nlf2_filter, nlf_filter};
 
 const INVALID_NODE_ID: usize = usize::MAX;
 
-#[derive(Debug, Default)]
+/// Returns the data nodes that have all of the given query node labels,
+/// which are all data nodes for the wildcard label.
+pub(crate) fn nodes_with_labels<'a>(
+    data_graph: &'a Graph,
+    labels: &'a [usize],
+) -> impl Iterator<Item = usize> + 'a {
+    // Without label index, all nodes are scanned and checked for all labels.
+    let (all_nodes, nodes_by_label, checked_labels) = if labels[0] == ANY_LABEL {
+        (0..data_graph.node_count(), &[][..], &labels[1..])
+    } else if !data_graph.has_node_label_index() {
+        (0..data_graph.node_count(), &[][..], labels)
+    } else {
+        (0..0, data_graph.nodes_by_label(labels[0]), &labels[1..])
+    };
+
+    all_nodes
+        .chain(nodes_by_label.iter().copied())
+        .filter(move |&data_node| {
+            checked_labels
+                .iter()
+                .all(|&label| data_graph.has_label(data_node, label))
+        })
+}
+
+/// Returns `true` if a query node has a label that no data node has, in which
+/// case the query node has no candidates.
+pub(crate) fn has_absent_label(data_graph: &Graph, query_graph: &Graph) -> bool {
+    let labels_present = data_graph.labels_present();
+
+    (0..query_graph.node_count()).any(|node| {
+        query_graph
+            .labels(node)
+            .iter()
+            .any(|&label| label != ANY_LABEL && labels_present.binary_search(&label).is_err())
+    })
+}
+
+/// Returns `true` if the data node has all of the given query node labels.
+pub(crate) fn labels_match(data_graph: &Graph, data_node: usize, labels: &[usize]) -> bool {
+    labels
+        .iter()
+        .all(|&label| label == ANY_LABEL || data_graph.has_label(data_node, label))
+}
+
+/// Computes the candidates of each query node, e.g. to prune data nodes by
+/// domain-specific predicates, see [`crate::find_with_filter`].
+///
+/// Returns `None` if there is no candidate for at least one query node.
+/// The returned candidates do not need to be sorted.
+///
+/// The trait is implemented for the built-in filters, e.g. [`ldf_filter`],
+/// for closures with the same signature, for [`crate::Filter`], which
+/// uses the default parameters of each filter, and for [`crate::Config`],
+/// see [`filter_candidates`].
+pub trait CandidateFilter {
+    fn filter(&self, data_graph: &Graph, query_graph: &Graph) -> Option<Candidates>;
+}
+
+impl<F> CandidateFilter for F
+where
+    F: Fn(&Graph, &Graph) -> Option<Candidates>,
+{
+    fn filter(&self, data_graph: &Graph, query_graph: &Graph) -> Option<Candidates> {
+        self(data_graph, query_graph)
+    }
+}
+
+impl CandidateFilter for Filter {
+    fn filter(&self, data_graph: &Graph, query_graph: &Graph) -> Option<Candidates> {
+        filter_candidates(data_graph, query_graph, Config::from(*self))
+    }
+}
+
+impl CandidateFilter for Config {
+    fn filter(&self, data_graph: &Graph, query_graph: &Graph) -> Option<Candidates> {
+        filter_candidates(data_graph, query_graph, *self)
+    }
+}
+
+/// Runs the filter of the config with its parameters, e.g. the refinement
+/// threshold and candidate backend of [`Filter::Gql`].
+///
+/// Returns `None` if there is no candidate for at least one query node.
+/// The returned candidates are not sorted.
+pub fn filter_candidates(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    config: Config,
+) -> Option<Candidates> {
+    filter_candidates_with(data_graph, query_graph, config, None)
+}
+
+/// Runs the filter of the config like [`filter_candidates`], but uses the
+/// given coreness of the data graph instead of computing it, if present.
+pub(crate) fn filter_candidates_with(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    config: Config,
+    data_coreness: Option<&[usize]>,
+) -> Option<Candidates> {
+    match config.filter {
+        Filter::Ldf => ldf_filter(data_graph, query_graph),
+        Filter::Gql => gql_filter_with_backend(
+            data_graph,
+            query_graph,
+            config.gql_refinement_threshold,
+            config.gql_max_refinement_rounds,
+            config.gql_candidate_backend,
+        ),
+        Filter::Nlf => nlf_filter(data_graph, query_graph),
+        Filter::Nlf2 => nlf2_filter(data_graph, query_graph),
+        Filter::Cfl => cfl_filter(data_graph, query_graph),
+        Filter::LdfCore => {
+            ldf_filter(data_graph, query_graph).and_then(|candidates| match data_coreness {
+                Some(data_coreness) => core_prune_with(data_coreness, query_graph, candidates),
+                None => core_prune(data_graph, query_graph, candidates),
+            })
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
 pub struct Candidates {
     /// candidates for each query node
     candidates: Box<[Vec<usize>]>,
@@ -33,18 +167,47 @@ impl Candidates {
         self.candidates[query_node][idx] = data_node;
     }
 
-    pub fn candidates(&self, data_node: usize) -> &[usize] {
-        self.candidates[data_node].as_slice()
+    pub fn candidates(&self, query_node: usize) -> &[usize] {
+        self.candidates[query_node].as_slice()
+    }
+
+    pub fn candidates_mut(&mut self, query_node: usize) -> &mut [usize] {
+        self.candidates[query_node].as_mut_slice()
+    }
+
+    /// Returns the candidates of each query node as `(query_node, candidates)`.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &[usize])> {
+        self.candidates.iter().map(Vec::as_slice).enumerate()
     }
 
-    pub fn candidates_mut(&mut self, data_node: usize) -> &mut [usize] {
-        self.candidates[data_node].as_mut_slice()
+    /// Returns the number of query nodes.
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
     }
 
     pub fn candidate_count(&self, query_node: usize) -> usize {
         self.candidates[query_node].len()
     }
 
+    /// Returns the sum of the candidate counts of all query nodes.
+    pub fn total_candidates(&self) -> usize {
+        self.candidates.iter().map(Vec::len).sum()
+    }
+
+    /// Returns the product of the candidate counts of all query nodes,
+    /// which is an upper bound for the number of embeddings.
+    ///
+    /// Saturates at `u128::MAX`.
+    pub fn candidate_product(&self) -> u128 {
+        self.candidates
+            .iter()
+            .fold(1_u128, |product, c| product.saturating_mul(c.len() as u128))
+    }
+
     pub fn sort(&mut self) {
         for c in self.candidates.iter_mut() {
             c.sort_unstable()
@@ -65,6 +228,129 @@ impl Candidates {
         }
     }
 
+    /// Releases the capacity that is not needed for the current candidates,
+    /// e.g. after compacting pre-allocated candidate sets.
+    pub fn shrink_to_fit(&mut self) {
+        for node_candidates in self.candidates.iter_mut() {
+            node_candidates.shrink_to_fit();
+        }
+    }
+
+    /// Returns the sum of the allocated capacities of all query nodes.
+    pub fn capacity(&self) -> usize {
+        self.candidates.iter().map(Vec::capacity).sum()
+    }
+
+    /// Materializes the candidates as a dense `query_node_count x data_node_count`
+    /// matrix, where entry `[u][v]` is `true` iff `v` is a candidate of `u`.
+    pub fn to_dense_matrix(&self, data_node_count: usize) -> Vec<Vec<bool>> {
+        self.candidates
+            .iter()
+            .map(|node_candidates| {
+                let mut row = vec![false; data_node_count];
+                for &data_node in node_candidates {
+                    row[data_node] = true;
+                }
+                row
+            })
+            .collect()
+    }
+
+    /// Materializes the candidates of each query node as a [`CandidateSet`]
+    /// for the data nodes `0..data_node_count`.
+    pub fn to_candidate_sets<S: CandidateSet>(&self, data_node_count: usize) -> Vec<S> {
+        self.candidates
+            .iter()
+            .map(|node_candidates| {
+                let mut set = S::with_data_node_count(data_node_count);
+                for &data_node in node_candidates {
+                    set.insert(data_node);
+                }
+                set
+            })
+            .collect()
+    }
+
+    /// Retains only the candidates that are also candidates of the same query
+    /// node in `other`, e.g. to combine the results of two filters.
+    ///
+    /// Both need to be sorted, which the intersection preserves.
+    ///
+    /// # Panics
+    ///
+    /// Panics if both have a different number of query nodes.
+    pub fn intersect(&mut self, other: &Candidates) {
+        assert_eq!(
+            self.len(),
+            other.len(),
+            "candidates of different query graphs"
+        );
+
+        let mut intersection = Vec::new();
+        for (node_candidates, other_candidates) in
+            self.candidates.iter_mut().zip(other.candidates.iter())
+        {
+            crate::enumerate::intersect_sorted(
+                node_candidates,
+                other_candidates,
+                &mut intersection,
+            );
+            std::mem::swap(node_candidates, &mut intersection);
+        }
+    }
+
+    /// Returns the data nodes that are candidates in `before`, but not in
+    /// `self`, e.g. to inspect which candidates a filter prunes beyond
+    /// another one. Query nodes without pruned candidates are omitted.
+    ///
+    /// The pruned data nodes keep their order in `before`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if both have a different number of query nodes.
+    pub fn diff(&self, before: &Candidates) -> Vec<(usize, Vec<usize>)> {
+        assert_eq!(
+            self.len(),
+            before.len(),
+            "candidates of different query graphs"
+        );
+
+        let mut remaining = Vec::new();
+        self.candidates
+            .iter()
+            .zip(before.candidates.iter())
+            .enumerate()
+            .filter_map(|(query_node, (node_candidates, before_candidates))| {
+                remaining.clear();
+                remaining.extend_from_slice(node_candidates);
+                remaining.sort_unstable();
+
+                let pruned = before_candidates
+                    .iter()
+                    .copied()
+                    .filter(|data_node| remaining.binary_search(data_node).is_err())
+                    .collect::<Vec<_>>();
+
+                if pruned.is_empty() {
+                    None
+                } else {
+                    Some((query_node, pruned))
+                }
+            })
+            .collect()
+    }
+
+    /// Retains only the candidates for which `f(query_node, data_node)`
+    /// returns `true`, keeping their order.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: Fn(usize, usize) -> bool,
+    {
+        for (query_node, node_candidates) in self.candidates.iter_mut().enumerate() {
+            node_candidates.retain(|&data_node| f(query_node, data_node));
+        }
+    }
+
     pub fn is_valid(&self) -> bool {
         for node_candidates in self.candidates.iter() {
             if node_candidates.is_empty() {
@@ -78,11 +364,18 @@ impl Candidates {
 impl From<(&Graph, &Graph)> for Candidates {
     fn from((data_graph, query_graph): (&Graph, &Graph)) -> Self {
         let query_node_count = query_graph.node_count();
-        let max_candidates = data_graph.max_label_frequency();
 
         let mut candidates = Vec::with_capacity(query_node_count);
 
-        for _ in 0..query_node_count {
+        // The number of data nodes with the same label is an
+        // upper bound for the number of candidates of a query node.
+        for query_node in 0..query_node_count {
+            let label = query_graph.label(query_node);
+            let max_candidates = if label == ANY_LABEL {
+                data_graph.node_count()
+            } else {
+                data_graph.nodes_by_label(label).len()
+            };
             candidates.push(Vec::<usize>::with_capacity(max_candidates));
         }
 
@@ -106,6 +399,7 @@ impl Display for Candidates {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::graph::GdlGraph;
 
     #[test]
     fn test_candidates_sorting() {
@@ -118,4 +412,160 @@ mod tests {
         assert_eq!(candidates.candidates(1), &[1, 3, 3, 7]);
         assert_eq!(candidates.candidates(2), &[0]);
     }
+
+    #[test]
+    fn test_candidates_iter() {
+        let data_graph = "(n0:L0),(n1:L1),(n2:L1),(n0)-->(n1),(n0)-->(n2)"
+            .parse::<GdlGraph>()
+            .unwrap();
+        let query_graph = "(n0:L1),(n1:L0),(n0)-->(n1)".parse::<GdlGraph>().unwrap();
+
+        let candidates = ldf_filter(&data_graph, &query_graph).unwrap();
+
+        assert_eq!(candidates.len(), 2);
+        assert!(!candidates.is_empty());
+        assert_eq!(
+            candidates.iter().collect::<Vec<_>>(),
+            vec![(0, &[1, 2][..]), (1, &[0][..])]
+        );
+    }
+
+    #[test]
+    fn test_candidates_intersect() {
+        let mut candidates = Candidates::new(vec![vec![0, 2, 4, 6], vec![1, 3], vec![5]]);
+        let other = Candidates::new(vec![vec![1, 2, 3, 6, 8], vec![3], vec![5]]);
+
+        candidates.intersect(&other);
+
+        assert_eq!(candidates.candidates(0), &[2, 6]);
+        assert_eq!(candidates.candidates(1), &[3]);
+        assert_eq!(candidates.candidates(2), &[5]);
+        assert!(candidates.is_valid());
+
+        // No common candidates for the last query node.
+        candidates.intersect(&Candidates::new(vec![vec![2, 6], vec![3, 4], vec![4]]));
+
+        assert_eq!(candidates.candidates(0), &[2, 6]);
+        assert_eq!(candidates.candidate_count(2), 0);
+        assert!(!candidates.is_valid());
+    }
+
+    #[test]
+    fn test_candidates_retain() {
+        let mut candidates = Candidates::new(vec![vec![0, 1, 2, 3], vec![1, 3], vec![4, 5]]);
+
+        candidates.retain(|_, data_node| data_node % 2 == 0);
+
+        assert_eq!(candidates.candidates(0), &[0, 2]);
+        assert_eq!(candidates.candidate_count(1), 0);
+        assert_eq!(candidates.candidates(2), &[4]);
+        assert!(!candidates.is_valid());
+
+        let mut candidates = Candidates::new(vec![vec![0, 1], vec![0, 1]]);
+
+        candidates.retain(|query_node, data_node| query_node != data_node);
+
+        assert_eq!(candidates.candidates(0), &[1]);
+        assert_eq!(candidates.candidates(1), &[0]);
+    }
+
+    #[test]
+    fn test_candidates_capacity_by_label_frequency() {
+        // one huge label (L0) and two tiny ones (L1, L2)
+        let mut gdl = String::from("(n0:L1),(n1:L2),(n2:L2),(n0)-->(n1),(n0)-->(n2)");
+        for node in 3..103 {
+            gdl.push_str(&format!(",(n{}:L0),(n0)-->(n{})", node, node));
+        }
+        let data_graph = gdl.parse::<GdlGraph>().unwrap();
+        let query_graph = "(n0:L1),(n1:L2),(n0)-->(n1)".parse::<GdlGraph>().unwrap();
+
+        assert_eq!(data_graph.max_label_frequency(), 100);
+
+        let candidates = Candidates::from((&*data_graph, &*query_graph));
+
+        assert!(candidates.candidates[0].capacity() >= 1);
+        assert!(candidates.candidates[1].capacity() >= 2);
+
+        let candidates = ldf_filter(&data_graph, &query_graph).unwrap();
+
+        assert_eq!(candidates.candidates(0), &[0]);
+        assert_eq!(candidates.candidates(1), &[1, 2]);
+    }
+
+    #[test]
+    fn test_candidates_shrink_to_fit() {
+        let data_graph = "(n0:L0),(n1:L1),(n2:L1),(n3:L1),(n0)-->(n1),(n0)-->(n2),(n1)-->(n2)"
+            .parse::<GdlGraph>()
+            .unwrap();
+        let query_graph = "(n0:L0),(n1:L1),(n2:L1),(n0)-->(n1),(n0)-->(n2),(n1)-->(n2)"
+            .parse::<GdlGraph>()
+            .unwrap();
+
+        // The candidates of n1 and n2 are pre-allocated for all L1 nodes.
+        let mut candidates = Candidates::from((&*data_graph, &*query_graph));
+        candidates.add_candidate(0, 0);
+        candidates.add_candidate(1, 1);
+        candidates.add_candidate(2, 2);
+
+        assert!(candidates.candidates[1].capacity() >= 3);
+        let capacity = candidates.capacity();
+
+        candidates.shrink_to_fit();
+
+        // The allocator may keep more than requested, so the capacity is
+        // only guaranteed to stay within the previous one.
+        for node_candidates in candidates.candidates.iter() {
+            assert!(node_candidates.capacity() >= node_candidates.len());
+        }
+        assert!(candidates.capacity() <= capacity);
+
+        let filters: [fn(&Graph, &Graph) -> Option<Candidates>; 5] =
+            [ldf_filter, gql_filter, nlf_filter, nlf2_filter, cfl_filter];
+        for filter in filters.iter() {
+            let candidates = filter(&data_graph, &query_graph).unwrap();
+            assert_eq!(candidates.candidates(1), &[1, 2]);
+            assert!(candidates.capacity() >= candidates.total_candidates());
+        }
+    }
+
+    #[test]
+    fn test_filters_on_degenerate_data_graphs() {
+        let empty = "t 0 0\n".parse::<Graph>().unwrap();
+        let isolated = "t 1 0\nv 0 0 0\n".parse::<Graph>().unwrap();
+        let unique_labels = "t 3 2\nv 0 0 1\nv 1 1 2\nv 2 2 1\ne 0 1\ne 1 2\n"
+            .parse::<Graph>()
+            .unwrap();
+
+        let node_query = "(n0:L0)".parse::<GdlGraph>().unwrap();
+        let edge_query = "(n0:L0),(n1:L1),(n0)-->(n1)".parse::<GdlGraph>().unwrap();
+        let empty_query = "t 0 0\n".parse::<Graph>().unwrap();
+
+        let filters: [fn(&Graph, &Graph) -> Option<Candidates>; 5] =
+            [ldf_filter, gql_filter, nlf_filter, nlf2_filter, cfl_filter];
+
+        for filter in filters.iter() {
+            assert!(filter(&empty, &node_query).is_none());
+            assert!(filter(&empty, &edge_query).is_none());
+
+            let candidates = filter(&isolated, &node_query).unwrap();
+            assert_eq!(candidates.candidates(0), &[0]);
+            assert!(filter(&isolated, &edge_query).is_none());
+
+            let candidates = filter(&unique_labels, &edge_query).unwrap();
+            assert_eq!(candidates.candidates(0), &[0]);
+            assert_eq!(candidates.candidates(1), &[1]);
+
+            // Without query nodes, no query node lacks candidates.
+            assert_eq!(filter(&empty, &empty_query).unwrap().len(), 0);
+            assert_eq!(filter(&unique_labels, &empty_query).unwrap().len(), 0);
+        }
+
+        // The capacity is bounded by the label frequency in the data graph.
+        let candidates = Candidates::from((&empty, &*edge_query));
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates.capacity(), 0);
+
+        let candidates = Candidates::from((&unique_labels, &*edge_query));
+        assert!(candidates.capacity() >= 2);
+    }
 }
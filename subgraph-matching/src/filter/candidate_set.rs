@@ -0,0 +1,167 @@
+use crate::bitset::Bitset;
+
+/// A set of candidate data nodes of a single query node that supports
+/// constant time membership tests, e.g. during the global refinement of
+/// the GQL filter.
+pub trait CandidateSet {
+    /// Creates an empty set for the data nodes `0..data_node_count`.
+    fn with_data_node_count(data_node_count: usize) -> Self
+    where
+        Self: Sized;
+
+    fn contains(&self, data_node: usize) -> bool;
+
+    fn insert(&mut self, data_node: usize);
+
+    fn remove(&mut self, data_node: usize);
+
+    /// Returns the data nodes in the set in ascending order.
+    fn iter(&self) -> Box<dyn Iterator<Item = usize> + '_>;
+
+    /// Returns the number of data nodes in the set.
+    fn count(&self) -> usize;
+
+    /// Returns the number of bytes that the set allocates for its members.
+    fn memory_usage(&self) -> usize;
+}
+
+/// Stores one `bool` per data node.
+#[derive(Debug, Clone)]
+pub struct VecCandidates {
+    members: Box<[bool]>,
+}
+
+impl CandidateSet for VecCandidates {
+    fn with_data_node_count(data_node_count: usize) -> Self {
+        Self {
+            members: vec![false; data_node_count].into_boxed_slice(),
+        }
+    }
+
+    #[inline]
+    fn contains(&self, data_node: usize) -> bool {
+        self.members[data_node]
+    }
+
+    #[inline]
+    fn insert(&mut self, data_node: usize) {
+        self.members[data_node] = true;
+    }
+
+    #[inline]
+    fn remove(&mut self, data_node: usize) {
+        self.members[data_node] = false;
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(
+            self.members
+                .iter()
+                .enumerate()
+                .filter(|(_, &is_member)| is_member)
+                .map(|(data_node, _)| data_node),
+        )
+    }
+
+    fn count(&self) -> usize {
+        self.members.iter().filter(|&&is_member| is_member).count()
+    }
+
+    fn memory_usage(&self) -> usize {
+        std::mem::size_of_val(&*self.members)
+    }
+}
+
+/// Stores one bit per data node, which needs an eighth of the memory of
+/// [`VecCandidates`].
+#[derive(Debug, Clone)]
+pub struct BitsetCandidates {
+    members: Bitset,
+}
+
+impl CandidateSet for BitsetCandidates {
+    fn with_data_node_count(data_node_count: usize) -> Self {
+        Self {
+            members: Bitset::new(data_node_count),
+        }
+    }
+
+    #[inline]
+    fn contains(&self, data_node: usize) -> bool {
+        self.members.contains(data_node)
+    }
+
+    #[inline]
+    fn insert(&mut self, data_node: usize) {
+        self.members.insert(data_node);
+    }
+
+    #[inline]
+    fn remove(&mut self, data_node: usize) {
+        self.members.remove(data_node);
+    }
+
+    fn iter(&self) -> Box<dyn Iterator<Item = usize> + '_> {
+        Box::new(self.members.iter())
+    }
+
+    fn count(&self) -> usize {
+        self.members.count()
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.members.memory_usage()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_set_operations<S: CandidateSet>() {
+        let mut set = S::with_data_node_count(130);
+
+        assert_eq!(set.count(), 0);
+        assert_eq!(set.iter().next(), None);
+
+        for data_node in [129, 0, 64, 63, 7] {
+            set.insert(data_node);
+        }
+        set.insert(7);
+        set.remove(64);
+        set.remove(128);
+
+        assert!(set.contains(63));
+        assert!(!set.contains(64));
+        assert!(!set.contains(128));
+        assert_eq!(set.count(), 4);
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![0, 7, 63, 129]);
+    }
+
+    #[test]
+    fn test_vec_candidates() {
+        assert_set_operations::<VecCandidates>();
+        assert_eq!(VecCandidates::with_data_node_count(130).memory_usage(), 130);
+    }
+
+    #[test]
+    fn test_bitset_candidates() {
+        assert_set_operations::<BitsetCandidates>();
+        assert_eq!(
+            BitsetCandidates::with_data_node_count(130).memory_usage(),
+            24
+        );
+    }
+
+    #[test]
+    fn test_bitset_candidates_memory_usage() {
+        // The number of nodes of the HPRD data graph.
+        let data_node_count = 9460;
+        let vec = VecCandidates::with_data_node_count(data_node_count);
+        let bitset = BitsetCandidates::with_data_node_count(data_node_count);
+
+        assert_eq!(vec.memory_usage(), data_node_count);
+        assert_eq!(bitset.memory_usage(), data_node_count.div_ceil(64) * 8);
+        assert!(bitset.memory_usage() * 7 < vec.memory_usage());
+    }
+}
@@ -0,0 +1,382 @@
+use crate::graph::Graph;
+
+use super::{labels_match, Candidates};
+
+// CFL: core-forest-leaf filtering
+//
+// The query graph is decomposed into its 2-core, a forest attached to the
+// core and the leaves of that forest. The root of the BFS tree is picked
+// from the core, since its nodes are the most selective ones. Candidates
+// are then generated top-down along the BFS tree, where each candidate of
+// a node needs to be adjacent to a candidate of every already visited
+// neighbor, and refined bottom-up, where each candidate needs to be
+// adjacent to a candidate of every child.
+pub fn cfl_filter(data_graph: &Graph, query_graph: &Graph) -> Option<Candidates> {
+    let query_node_count = query_graph.node_count();
+
+    let ldf_candidates = super::ldf_filter(data_graph, query_graph)?;
+
+    // Without query nodes, there is no root to start the BFS from.
+    if query_node_count == 0 {
+        return Some(ldf_candidates);
+    }
+
+    let root = cfl_root(query_graph, &ldf_candidates);
+    let (levels, level_of) = bfs_levels(query_graph, root);
+
+    let mut candidates = vec![Vec::new(); query_node_count];
+    candidates[root] = ldf_candidates.candidates(root).to_vec();
+
+    let mut flags = vec![0_usize; data_graph.node_count()];
+    let mut updated = Vec::new();
+
+    // Top-down generation
+    for level in levels.iter().skip(1) {
+        for &query_node in level {
+            let parents = query_graph
+                .neighbors(query_node)
+                .iter()
+                .copied()
+                .filter(|&neighbor| level_of[neighbor] < level_of[query_node])
+                .collect::<Vec<_>>();
+
+            let count = count_adjacent(
+                data_graph,
+                query_graph.labels(query_node),
+                &parents,
+                &candidates,
+                &mut flags,
+                &mut updated,
+            );
+
+            let degree = query_graph.degree(query_node);
+            candidates[query_node] = updated
+                .iter()
+                .copied()
+                .filter(|&data_node| {
+                    flags[data_node] == count && data_graph.degree(data_node) >= degree
+                })
+                .collect();
+
+            reset(&mut flags, &mut updated);
+
+            if candidates[query_node].is_empty() {
+                return None;
+            }
+        }
+
+        // Prune along the non-tree edges within the same level.
+        for &query_node in level {
+            let siblings = query_graph
+                .neighbors(query_node)
+                .iter()
+                .copied()
+                .filter(|&neighbor| level_of[neighbor] == level_of[query_node])
+                .collect::<Vec<_>>();
+
+            if !prune(
+                data_graph,
+                query_graph,
+                query_node,
+                &siblings,
+                &mut candidates,
+                &mut flags,
+                &mut updated,
+            ) {
+                return None;
+            }
+        }
+    }
+
+    // Bottom-up refinement
+    for level in levels.iter().rev().skip(1) {
+        for &query_node in level {
+            let children = query_graph
+                .neighbors(query_node)
+                .iter()
+                .copied()
+                .filter(|&neighbor| level_of[neighbor] > level_of[query_node])
+                .collect::<Vec<_>>();
+
+            if !prune(
+                data_graph,
+                query_graph,
+                query_node,
+                &children,
+                &mut candidates,
+                &mut flags,
+                &mut updated,
+            ) {
+                return None;
+            }
+        }
+    }
+
+    let mut candidates = Candidates::new(candidates);
+    candidates.sort();
+    candidates.shrink_to_fit();
+
+    Some(candidates)
+}
+
+/// Selects the root of the BFS tree from the 2-core of the query graph by
+/// picking the node with the minimum ratio of candidates to degree.
+///
+/// If the query graph is a tree, the core is empty and all nodes qualify.
+fn cfl_root(query_graph: &Graph, candidates: &Candidates) -> usize {
+    let core = two_core(query_graph);
+    let in_core = core.iter().any(|&is_core| is_core);
+
+    let rank = |node: usize| {
+        candidates.candidate_count(node) as f64 / query_graph.degree(node).max(1) as f64
+    };
+
+    (0..query_graph.node_count())
+        .filter(|&node| !in_core || core[node])
+        .fold(None, |root: Option<usize>, node| match root {
+            Some(root) if rank(root) <= rank(node) => Some(root),
+            _ => Some(node),
+        })
+        .unwrap_or_default()
+}
+
+/// Computes the 2-core of the query graph by repeatedly removing nodes
+/// with a degree less than two. What remains outside the core forms the
+/// forest, including its leaves.
+fn two_core(query_graph: &Graph) -> Vec<bool> {
+    let node_count = query_graph.node_count();
+
+    let mut degrees = (0..node_count)
+        .map(|node| query_graph.degree(node))
+        .collect::<Vec<_>>();
+    let mut core = vec![true; node_count];
+    let mut stack = (0..node_count)
+        .filter(|&node| degrees[node] < 2)
+        .collect::<Vec<_>>();
+
+    while let Some(node) = stack.pop() {
+        if !core[node] {
+            continue;
+        }
+        core[node] = false;
+
+        for &neighbor in query_graph.neighbors(node) {
+            if core[neighbor] {
+                degrees[neighbor] -= 1;
+                if degrees[neighbor] < 2 {
+                    stack.push(neighbor);
+                }
+            }
+        }
+    }
+
+    core
+}
+
+/// Groups the query nodes into levels of a BFS starting at `root`.
+///
+/// Returns the levels and the level of each query node.
+fn bfs_levels(query_graph: &Graph, root: usize) -> (Vec<Vec<usize>>, Vec<usize>) {
+    let mut level_of = vec![usize::MAX; query_graph.node_count()];
+    let mut levels = vec![vec![root]];
+    level_of[root] = 0;
+
+    loop {
+        let mut next = Vec::new();
+
+        for &node in levels.last().unwrap() {
+            for &neighbor in query_graph.neighbors(node) {
+                if level_of[neighbor] == usize::MAX {
+                    level_of[neighbor] = levels.len();
+                    next.push(neighbor);
+                }
+            }
+        }
+
+        if next.is_empty() {
+            break;
+        }
+        levels.push(next);
+    }
+
+    (levels, level_of)
+}
+
+/// Counts for each data node with the given labels the number of pivot
+/// query nodes that have a candidate adjacent to it. A data node is only
+/// counted for a pivot if it has been counted for all previous pivots.
+///
+/// Touched data nodes are recorded in `updated`. Returns the number of pivots.
+fn count_adjacent(
+    data_graph: &Graph,
+    labels: &[usize],
+    pivots: &[usize],
+    candidates: &[Vec<usize>],
+    flags: &mut [usize],
+    updated: &mut Vec<usize>,
+) -> usize {
+    for (count, &pivot) in pivots.iter().enumerate() {
+        for &pivot_candidate in &candidates[pivot] {
+            for &data_node in data_graph.neighbors(pivot_candidate) {
+                if flags[data_node] == count && labels_match(data_graph, data_node, labels) {
+                    flags[data_node] += 1;
+                    if count == 0 {
+                        updated.push(data_node);
+                    }
+                }
+            }
+        }
+    }
+
+    pivots.len()
+}
+
+/// Removes all candidates of `query_node` that are not adjacent to a
+/// candidate of each pivot. Returns `false` if no candidate remains.
+fn prune(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    query_node: usize,
+    pivots: &[usize],
+    candidates: &mut [Vec<usize>],
+    flags: &mut [usize],
+    updated: &mut Vec<usize>,
+) -> bool {
+    if pivots.is_empty() {
+        return true;
+    }
+
+    let count = count_adjacent(
+        data_graph,
+        query_graph.labels(query_node),
+        pivots,
+        candidates,
+        flags,
+        updated,
+    );
+
+    candidates[query_node].retain(|&data_node| flags[data_node] == count);
+    reset(flags, updated);
+
+    !candidates[query_node].is_empty()
+}
+
+fn reset(flags: &mut [usize], updated: &mut Vec<usize>) {
+    for data_node in updated.drain(..) {
+        flags[data_node] = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{filter::gql_filter_with, graph::GdlGraph};
+    use trim_margin::MarginTrimmable;
+
+    fn graph(gdl: &str) -> GdlGraph {
+        gdl.trim_margin().unwrap().parse::<GdlGraph>().unwrap()
+    }
+
+    const DATA_GRAPH_2: &str = "
+        |(n0:L0)
+        |(n1:L2)
+        |(n2:L1)
+        |(n3:L2)
+        |(n4:L1)
+        |(n5:L2)
+        |(n6:L1)
+        |(n7:L2)
+        |(n8:L3)
+        |(n9:L3)
+        |(n10:L3)
+        |(n11:L3)
+        |(n12:L3)
+        |(n13:L2)
+        |(n14:L3)
+        |(n0)-->(n1)
+        |(n0)-->(n2)
+        |(n0)-->(n3)
+        |(n0)-->(n4)
+        |(n0)-->(n5)
+        |(n0)-->(n6)
+        |(n0)-->(n7)
+        |(n1)-->(n2)
+        |(n1)-->(n8)
+        |(n2)-->(n9)
+        |(n2)-->(n10)
+        |(n3)-->(n4)
+        |(n3)-->(n10)
+        |(n4)-->(n5)
+        |(n4)-->(n10)
+        |(n4)-->(n11)
+        |(n4)-->(n12)
+        |(n5)-->(n12)
+        |(n6)-->(n12)
+        |(n6)-->(n13)
+        |(n7)-->(n14)
+        |(n9)-->(n10)
+        |";
+
+    #[test]
+    fn test_cfl_filter() {
+        let data_graph = graph(DATA_GRAPH_2);
+        let query_graph = graph(
+            "
+            |(n0:L1)
+            |(n1:L3)
+            |(n2:L3)
+            |(n3:L2)
+            |(n4:L0)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |(n2)-->(n3)
+            |(n3)-->(n4)
+            |",
+        );
+
+        let candidates = cfl_filter(&data_graph, &query_graph).unwrap();
+
+        assert_eq!(candidates.candidates(0), &[2]);
+        assert_eq!(candidates.candidates(1), &[9]);
+        assert_eq!(candidates.candidates(2), &[10]);
+        assert_eq!(candidates.candidates(3), &[3]);
+        assert_eq!(candidates.candidates(4), &[0]);
+
+        // Two rounds of GQL refinement keep n4 as candidate for n0.
+        let gql_candidates = gql_filter_with(&data_graph, &query_graph, 0.0, 2).unwrap();
+        assert_eq!(gql_candidates.candidates(0), &[2, 4]);
+    }
+
+    #[test]
+    fn test_cfl_filter_cycle() {
+        let data_graph = graph(DATA_GRAPH_2);
+        let query_graph = graph(
+            "
+            |(n0:L0)
+            |(n1:L1)
+            |(n2:L2)
+            |(n3:L3)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |",
+        );
+
+        let candidates = cfl_filter(&data_graph, &query_graph).unwrap();
+
+        assert_eq!(candidates.candidates(0), &[0]);
+        assert_eq!(candidates.candidates(1), &[2, 4]);
+        assert_eq!(candidates.candidates(2), &[3, 5]);
+        assert_eq!(candidates.candidates(3), &[10, 12]);
+    }
+
+    #[test]
+    fn test_two_core() {
+        let query_graph = graph(
+            "(n0:L0), (n1:L0), (n2:L0), (n3:L0), (n4:L0), (n0)-->(n1), (n1)-->(n2), (n2)-->(n0), (n2)-->(n3), (n3)-->(n4)",
+        );
+        assert_eq!(two_core(&query_graph), &[true, true, true, false, false]);
+    }
+}
@@ -1,44 +1,215 @@
-use crate::graph::Graph;
+use crate::{config::CandidateBackend, graph::Graph};
 
-use super::Candidates;
 use super::INVALID_NODE_ID;
+use super::{BitsetCandidates, CandidateSet, Candidates, VecCandidates};
 
 // The C++ impl uses 100_000_000 :shrug:
 const UNMAPPED: usize = usize::MAX;
 
+/// The default fraction of candidates that a refinement round needs to
+/// remove in order to run another round.
+pub const GQL_REFINEMENT_THRESHOLD: f64 = 0.01;
+/// The default maximum number of refinement rounds.
+pub const GQL_MAX_REFINEMENT_ROUNDS: usize = 4;
+
 pub fn gql_filter(data_graph: &Graph, query_graph: &Graph) -> Option<Candidates> {
-    // Local refinement
-    let mut candidates = super::ldf_filter(data_graph, query_graph)?;
+    gql_filter_with(
+        data_graph,
+        query_graph,
+        GQL_REFINEMENT_THRESHOLD,
+        GQL_MAX_REFINEMENT_ROUNDS,
+    )
+}
 
-    let query_node_count = query_graph.node_count();
-    let data_node_count = data_graph.node_count();
+/// Runs the GQL filter with an adaptive number of global refinement rounds.
+///
+/// Refinement continues as long as a round removes more than `threshold`
+/// of the candidates that remained before that round, but stops after
+/// `max_rounds` rounds. With `max_rounds = 0`, there is no global refinement
+/// and the result equals the [`super::ldf_filter`] candidates.
+pub fn gql_filter_with(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    threshold: f64,
+    max_rounds: usize,
+) -> Option<Candidates> {
+    gql_filter_with_backend(
+        data_graph,
+        query_graph,
+        threshold,
+        max_rounds,
+        CandidateBackend::Vec,
+    )
+}
 
-    // Record valid candidate vertices for each query vertex
-    // TODO: bitset
-    let mut valid_candidates = Vec::with_capacity(query_node_count);
-    for query_node in 0..query_node_count {
-        let mut node_candidates = vec![false; data_node_count];
-        for data_node in candidates.candidates(query_node) {
-            node_candidates[*data_node] = true;
+/// Runs the GQL filter like [`gql_filter_with`], but tracks the valid
+/// candidates during refinement using the given backend.
+///
+/// [`CandidateBackend::Bitset`] needs one bit instead of one byte per pair
+/// of query node and data node, the resulting candidates are the same.
+pub fn gql_filter_with_backend(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    threshold: f64,
+    max_rounds: usize,
+    backend: CandidateBackend,
+) -> Option<Candidates> {
+    match backend {
+        CandidateBackend::Vec => {
+            gql_refine::<VecCandidates>(data_graph, query_graph, threshold, max_rounds)
+        }
+        CandidateBackend::Bitset => {
+            gql_refine::<BitsetCandidates>(data_graph, query_graph, threshold, max_rounds)
         }
-        valid_candidates.push(node_candidates);
     }
+    .map(|(candidates, _)| candidates)
+}
 
-    let query_graph_max_degree = query_graph.max_degree();
-    let data_graph_max_degree = data_graph.max_degree();
+/// Runs the GQL filter like [`gql_filter`], but reuses the buffers of the
+/// given scratch instead of allocating them, e.g. to filter many queries
+/// against the same data graph. The candidates are the same.
+pub fn gql_filter_with_scratch(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    scratch: &mut GqlFilterScratch,
+) -> Option<Candidates> {
+    gql_refine_with(
+        data_graph,
+        query_graph,
+        GQL_REFINEMENT_THRESHOLD,
+        GQL_MAX_REFINEMENT_ROUNDS,
+        scratch,
+    )
+    .map(|(candidates, _)| candidates)
+}
 
+/// The buffers of the global refinement of the GQL filter, which can be
+/// reused across calls of [`gql_filter_with_scratch`].
+///
+/// The buffers are sized for the data graph the scratch has been created
+/// for and grow if a query or data graph needs larger ones.
+pub struct GqlFilterScratch<S = VecCandidates> {
     // CSR datastructures to represent the bi-partite graph
-    let mut offsets = vec![0_usize; query_graph_max_degree + 1];
-    let mut targets = vec![0_usize; query_graph_max_degree * data_graph_max_degree];
-    let mut left_mapping = vec![0_usize; query_graph_max_degree];
-    let mut right_mapping = vec![0_usize; data_graph_max_degree];
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+    left_mapping: Vec<usize>,
+    right_mapping: Vec<usize>,
     // Buffers for BFS in Hopcroft and Karp
-    let mut queue = vec![0_usize; query_node_count];
-    let mut visited = vec![0_usize; data_graph_max_degree + 1];
-    let mut predecessors = vec![0_usize; data_graph_max_degree + 1];
+    queue: Vec<usize>,
+    visited: Vec<usize>,
+    predecessors: Vec<usize>,
+    /// The valid candidates of each query node, which are empty between
+    /// refinements.
+    valid_candidates: Vec<S>,
+    data_node_count: usize,
+}
+
+impl<S: CandidateSet> GqlFilterScratch<S> {
+    pub fn new(data_graph: &Graph) -> Self {
+        let data_graph_max_degree = data_graph.max_degree();
+
+        Self {
+            offsets: Vec::new(),
+            targets: Vec::new(),
+            left_mapping: Vec::new(),
+            right_mapping: vec![0; data_graph_max_degree],
+            queue: Vec::new(),
+            visited: vec![0; data_graph_max_degree + 1],
+            predecessors: vec![0; data_graph_max_degree + 1],
+            valid_candidates: Vec::new(),
+            data_node_count: data_graph.node_count(),
+        }
+    }
+
+    /// Resets the buffers to the sizes needed for the given graphs.
+    fn prepare(&mut self, data_graph: &Graph, query_graph: &Graph) {
+        let query_graph_max_degree = query_graph.max_degree();
+        let data_graph_max_degree = data_graph.max_degree();
+
+        let reset = |buffer: &mut Vec<usize>, len: usize| {
+            buffer.clear();
+            buffer.resize(len, 0);
+        };
+
+        reset(&mut self.offsets, query_graph_max_degree + 1);
+        reset(
+            &mut self.targets,
+            query_graph_max_degree * data_graph_max_degree,
+        );
+        reset(&mut self.left_mapping, query_graph_max_degree);
+        reset(&mut self.right_mapping, data_graph_max_degree);
+        reset(&mut self.queue, query_graph.node_count());
+        reset(&mut self.visited, data_graph_max_degree + 1);
+        reset(&mut self.predecessors, data_graph_max_degree + 1);
+
+        if self.data_node_count != data_graph.node_count() {
+            self.data_node_count = data_graph.node_count();
+            self.valid_candidates.clear();
+        }
+        while self.valid_candidates.len() < query_graph.node_count() {
+            self.valid_candidates
+                .push(S::with_data_node_count(self.data_node_count));
+        }
+    }
+}
+
+/// Returns the refined candidates and the number of refinement rounds.
+fn gql_refine<S: CandidateSet>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    threshold: f64,
+    max_rounds: usize,
+) -> Option<(Candidates, usize)> {
+    let mut scratch = GqlFilterScratch::<S>::new(data_graph);
+    gql_refine_with(data_graph, query_graph, threshold, max_rounds, &mut scratch)
+}
+
+/// Refines the candidates like [`gql_refine`] using the buffers of the
+/// given scratch.
+fn gql_refine_with<S: CandidateSet>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    threshold: f64,
+    max_rounds: usize,
+    scratch: &mut GqlFilterScratch<S>,
+) -> Option<(Candidates, usize)> {
+    // Local refinement, which is shared between query nodes of the same
+    // orbit. The global refinement updates the candidates in place, so its
+    // result for a node depends on the nodes refined before and is not shared.
+    let mut candidates = super::ldf_filter(data_graph, query_graph)?;
+
+    let query_node_count = query_graph.node_count();
+
+    scratch.prepare(data_graph, query_graph);
+    let GqlFilterScratch {
+        offsets,
+        targets,
+        left_mapping,
+        right_mapping,
+        queue,
+        visited,
+        predecessors,
+        valid_candidates,
+        ..
+    } = scratch;
+
+    // Record valid candidate vertices for each query vertex
+    for (query_node, node_candidates) in candidates.iter() {
+        for &data_node in node_candidates {
+            valid_candidates[query_node].insert(data_node);
+        }
+    }
+
+    let mut remaining = (0..query_node_count)
+        .map(|query_node| candidates.candidate_count(query_node))
+        .sum::<usize>();
+    let mut rounds = 0;
 
     // Global refinement
-    for _ in 0..2 {
+    while rounds < max_rounds {
+        let mut removed = 0;
+        rounds += 1;
+
         for query_node in 0..query_node_count {
             for data_node in candidates.candidates_mut(query_node) {
                 if *data_node == INVALID_NODE_ID {
@@ -53,9 +224,9 @@ pub fn gql_filter(data_graph: &Graph, query_graph: &Graph) -> Option<Candidates>
                 compute_bipartite_graph(
                     query_node_neighbors,
                     data_node_neighbors,
-                    &valid_candidates,
-                    &mut offsets,
-                    &mut targets,
+                    valid_candidates,
+                    offsets,
+                    targets,
                 );
 
                 left_mapping.fill(UNMAPPED);
@@ -63,48 +234,64 @@ pub fn gql_filter(data_graph: &Graph, query_graph: &Graph) -> Option<Candidates>
 
                 // A cheap match to reduce overhead for Hopcroft and Karp.
                 match_cheap(
-                    &offsets,
-                    &targets,
-                    &mut left_mapping,
-                    &mut right_mapping,
+                    offsets,
+                    targets,
+                    left_mapping,
+                    right_mapping,
                     left_partition_size,
                 );
 
                 // Run Hopcroft and Karp to find maximal matching.
                 match_bfs(
-                    &offsets,
-                    &targets,
-                    &mut left_mapping,
-                    &mut right_mapping,
-                    &mut visited,
-                    &mut queue,
-                    &mut predecessors,
+                    offsets,
+                    targets,
+                    left_mapping,
+                    right_mapping,
+                    visited,
+                    queue,
+                    predecessors,
                     left_partition_size,
                 );
 
                 // Check if each neighbor has a match.
-                if !is_semi_perfect_matching(&left_mapping, left_partition_size) {
-                    valid_candidates[query_node][*data_node] = false;
+                if !is_semi_perfect_matching(left_mapping, left_partition_size) {
+                    valid_candidates[query_node].remove(*data_node);
                     *data_node = INVALID_NODE_ID;
+                    removed += 1;
                 }
             }
         }
+
+        let removed_fraction = removed as f64 / remaining.max(1) as f64;
+        remaining -= removed;
+
+        if removed_fraction <= threshold {
+            break;
+        }
     }
 
     candidates.compact();
+    candidates.shrink_to_fit();
+
+    // Leave the candidate sets empty for the next refinement.
+    for (query_node, node_candidates) in candidates.iter() {
+        for &data_node in node_candidates {
+            valid_candidates[query_node].remove(data_node);
+        }
+    }
 
     if candidates.is_valid() {
-        Some(candidates)
+        Some((candidates, rounds))
     } else {
         None
     }
 }
 
 // Constructs a bi-partite graph between the N(query_node) and N(data_node)
-fn compute_bipartite_graph(
+fn compute_bipartite_graph<S: CandidateSet>(
     query_node_neighbors: &[usize],
     data_node_neighbors: &[usize],
-    valid_candidates: &[Vec<bool>],
+    valid_candidates: &[S],
     offsets: &mut [usize],
     targets: &mut [usize],
 ) {
@@ -114,7 +301,7 @@ fn compute_bipartite_graph(
         offsets[i] = rel_count;
 
         for (j, data_node_neighbor) in data_node_neighbors.iter().enumerate() {
-            if valid_candidates[*query_node_neighbor][*data_node_neighbor] {
+            if valid_candidates[*query_node_neighbor].contains(*data_node_neighbor) {
                 targets[rel_count] = j;
                 rel_count += 1;
             }
@@ -144,6 +331,7 @@ fn match_cheap(
 
 /// An implementation of "Hopcroft and Karp" to find
 /// the maximum matching in a bi-partite graph.
+#[allow(clippy::too_many_arguments)]
 fn match_bfs(
     offsets: &[usize],
     targets: &[usize],
@@ -307,6 +495,270 @@ mod tests {
         assert_eq!(candidates.candidate_count(1), 1);
         assert_eq!(candidates.candidate_count(2), 2);
         assert_eq!(candidates.candidate_count(3), 2);
+
+        assert_eq!(candidates.total_candidates(), 6);
+        assert_eq!(candidates.candidate_product(), 4);
+    }
+
+    #[test]
+    fn test_gql_filter_diff() {
+        let data_graph = graph(DATA_GRAPH_2);
+        let query_graph = graph(
+            "
+            |(n0:L0)
+            |(n1:L1)
+            |(n2:L2)
+            |(n3:L3)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |",
+        );
+
+        let ldf = crate::filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let gql = gql_filter(&data_graph, &query_graph).unwrap();
+
+        // LDF keeps these nodes, since their labels and degrees fit, but
+        // their neighbors cannot be matched to the query neighbors.
+        assert_eq!(
+            gql.diff(&ldf),
+            vec![(1, vec![2, 6]), (2, vec![1]), (3, vec![9])]
+        );
+        assert!(ldf.diff(&gql).is_empty());
+        assert!(gql.diff(&gql.clone()).is_empty());
+    }
+
+    #[test]
+    fn test_gql_filter_with_config() {
+        use crate::{filter::CandidateFilter, Config, Filter};
+
+        let data_graph = graph(DATA_GRAPH_2);
+        let query_graph = graph(
+            "
+            |(n0:L0)
+            |(n1:L1)
+            |(n2:L2)
+            |(n3:L3)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |",
+        );
+
+        let ldf = crate::filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let gql = gql_filter(&data_graph, &query_graph).unwrap();
+        assert!(!gql.diff(&ldf).is_empty());
+
+        assert!(Filter::Gql
+            .filter(&data_graph, &query_graph)
+            .unwrap()
+            .diff(&gql)
+            .is_empty());
+
+        // Without global refinement, only the local LDF pruning remains.
+        let config = Config::builder()
+            .filter(Filter::Gql)
+            .gql_max_refinement_rounds(0)
+            .build();
+        let candidates = config.filter(&data_graph, &query_graph).unwrap();
+        assert!(candidates.diff(&ldf).is_empty());
+        assert!(ldf.diff(&candidates).is_empty());
+    }
+
+    #[test]
+    fn test_gql_filter_with_scratch() {
+        let data_graph = graph(DATA_GRAPH_2);
+        let query_graphs = [
+            "(n0:L0),(n1:L1),(n2:L2),(n3:L3),(n0)-->(n1),(n0)-->(n2),(n1)-->(n2),(n1)-->(n3),(n2)-->(n3)",
+            // no L1 node has two L3 neighbors that are adjacent
+            "(n0:L1),(n1:L3),(n2:L3),(n0)-->(n1),(n0)-->(n2),(n1)-->(n2)",
+            "(n0:L1),(n1:L3),(n2:L3),(n0)-->(n1),(n0)-->(n2)",
+            "(n0:L2),(n1:L0),(n0)-->(n1)",
+        ];
+
+        let mut scratch = GqlFilterScratch::new(&data_graph);
+
+        for gdl in query_graphs.iter() {
+            let query_graph = graph(gdl);
+
+            let expected = gql_filter(&data_graph, &query_graph);
+            let actual = gql_filter_with_scratch(&data_graph, &query_graph, &mut scratch);
+
+            assert_eq!(
+                actual.map(|c| c.iter().map(|(_, c)| c.to_vec()).collect::<Vec<_>>()),
+                expected.map(|c| c.iter().map(|(_, c)| c.to_vec()).collect::<Vec<_>>()),
+                "query: {}",
+                gdl
+            );
+        }
+
+        // The candidate sets are empty after each refinement.
+        assert!(scratch.valid_candidates.iter().all(|set| set.count() == 0));
+    }
+
+    #[test]
+    fn test_gql_filter_adaptive_rounds() {
+        let data_graph = graph(DATA_GRAPH_2);
+        let query_graph = graph(
+            "
+            |(n0:L1)
+            |(n1:L3)
+            |(n2:L3)
+            |(n3:L2)
+            |(n4:L0)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |(n2)-->(n3)
+            |(n3)-->(n4)
+            |",
+        );
+
+        let (candidates, _) =
+            gql_refine::<VecCandidates>(&data_graph, &query_graph, 0.0, 2).unwrap();
+        assert_eq!(candidates.candidates(0), &[2, 4]);
+
+        // The second round still removes candidates, so a third one follows.
+        let (candidates, rounds) = gql_refine::<VecCandidates>(
+            &data_graph,
+            &query_graph,
+            GQL_REFINEMENT_THRESHOLD,
+            GQL_MAX_REFINEMENT_ROUNDS,
+        )
+        .unwrap();
+        assert!(rounds > 2);
+        assert_eq!(candidates.candidates(0), &[2]);
+    }
+
+    #[test]
+    fn test_gql_filter_rounds() {
+        let data_graph = graph(DATA_GRAPH_2);
+        let query_graph = graph(
+            "
+            |(n0:L1)
+            |(n1:L3)
+            |(n2:L3)
+            |(n3:L2)
+            |(n4:L0)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |(n2)-->(n3)
+            |(n3)-->(n4)
+            |",
+        );
+
+        let mut ldf_candidates = crate::filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        ldf_candidates.sort();
+
+        // Without global refinement, only the local LDF pruning remains.
+        let (candidates, rounds) =
+            gql_refine::<VecCandidates>(&data_graph, &query_graph, 0.0, 0).unwrap();
+        assert_eq!(rounds, 0);
+        assert_eq!(
+            candidates.iter().collect::<Vec<_>>(),
+            ldf_candidates.iter().collect::<Vec<_>>()
+        );
+
+        let mut previous = ldf_candidates;
+        for max_rounds in 1..=3 {
+            let (candidates, _) =
+                gql_refine::<VecCandidates>(&data_graph, &query_graph, 0.0, max_rounds).unwrap();
+
+            for (query_node, node_candidates) in candidates.iter() {
+                assert!(node_candidates
+                    .iter()
+                    .all(|candidate| previous.candidates(query_node).contains(candidate)));
+            }
+            assert!(candidates.total_candidates() <= previous.total_candidates());
+
+            previous = candidates;
+        }
+        assert!(previous.total_candidates() < candidates.total_candidates());
+    }
+
+    #[test]
+    fn test_gql_filter_adaptive_rounds_converged() {
+        let data_graph = graph(DATA_GRAPH_2);
+        let query_graph = graph("(n0:L0), (n1:L1), (n0)-->(n1)");
+
+        // The first round removes nothing, so there is no need for a second one.
+        let (candidates, rounds) = gql_refine::<VecCandidates>(
+            &data_graph,
+            &query_graph,
+            GQL_REFINEMENT_THRESHOLD,
+            GQL_MAX_REFINEMENT_ROUNDS,
+        )
+        .unwrap();
+        assert_eq!(rounds, 1);
+        assert_eq!(candidates.candidates(1), &[2, 4, 6]);
+    }
+
+    #[test]
+    fn test_gql_filter_bitset_backend() {
+        let data_graph = graph(DATA_GRAPH_2);
+        let query_graph = graph(
+            "
+            |(n0:L1)
+            |(n1:L3)
+            |(n2:L3)
+            |(n3:L2)
+            |(n4:L0)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |(n2)-->(n3)
+            |(n3)-->(n4)
+            |",
+        );
+
+        let (vec_candidates, vec_rounds) =
+            gql_refine::<VecCandidates>(&data_graph, &query_graph, 0.0, 3).unwrap();
+        let (bitset_candidates, bitset_rounds) =
+            gql_refine::<BitsetCandidates>(&data_graph, &query_graph, 0.0, 3).unwrap();
+
+        assert_eq!(vec_rounds, bitset_rounds);
+        assert_eq!(
+            vec_candidates.iter().collect::<Vec<_>>(),
+            bitset_candidates.iter().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_candidates_to_dense_matrix() {
+        let data_graph = graph(DATA_GRAPH_2);
+        let query_graph = graph(
+            "
+            |(n0:L0)
+            |(n1:L1)
+            |(n2:L2)
+            |(n3:L3)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |",
+        );
+
+        let candidates = gql_filter(&data_graph, &query_graph).unwrap();
+        let matrix = candidates.to_dense_matrix(data_graph.node_count());
+
+        assert_eq!(matrix.len(), query_graph.node_count());
+
+        for (query_node, row) in matrix.iter().enumerate() {
+            assert_eq!(row.len(), data_graph.node_count());
+
+            let set_bits = row
+                .iter()
+                .enumerate()
+                .filter(|(_, &is_candidate)| is_candidate)
+                .map(|(data_node, _)| data_node)
+                .collect::<Vec<_>>();
+
+            assert_eq!(set_bits, candidates.candidates(query_node));
+        }
     }
 
     #[test]
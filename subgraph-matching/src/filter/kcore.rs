@@ -0,0 +1,106 @@
+use crate::{graph::Graph, graph_ops::coreness};
+
+use super::{Candidates, INVALID_NODE_ID};
+
+/// Removes the candidates whose coreness is smaller than the coreness of
+/// their query node.
+///
+/// An embedding maps the k-core of the query graph to a subgraph in which
+/// each node has at least degree k, which is therefore part of the k-core
+/// of the data graph. Returns `None` if a query node has no candidates left.
+pub fn core_prune(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: Candidates,
+) -> Option<Candidates> {
+    core_prune_with(&coreness(data_graph), query_graph, candidates)
+}
+
+/// Prunes the candidates like [`core_prune`], but uses the given, already
+/// computed coreness of the data graph.
+pub(crate) fn core_prune_with(
+    data_coreness: &[usize],
+    query_graph: &Graph,
+    mut candidates: Candidates,
+) -> Option<Candidates> {
+    let query_coreness = coreness(query_graph);
+
+    for (query_node, &query_core) in query_coreness.iter().enumerate() {
+        for data_node in candidates.candidates_mut(query_node) {
+            if data_coreness[*data_node] < query_core {
+                *data_node = INVALID_NODE_ID;
+            }
+        }
+    }
+
+    candidates.compact();
+    candidates.shrink_to_fit();
+
+    if candidates.is_valid() {
+        Some(candidates)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{filter::ldf_filter, graph::GdlGraph};
+    use trim_margin::MarginTrimmable;
+
+    fn graph(gdl: &str) -> GdlGraph {
+        gdl.trim_margin().unwrap().parse::<GdlGraph>().unwrap()
+    }
+
+    #[test]
+    fn test_core_prune() {
+        // n4 has the degree of a clique node, but only coreness 1.
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0)
+            |(n4:L0),(n5:L0),(n6:L0),(n7:L0)
+            |(n0)-->(n1),(n0)-->(n2),(n0)-->(n3)
+            |(n1)-->(n2),(n1)-->(n3),(n2)-->(n3)
+            |(n4)-->(n0),(n4)-->(n5),(n4)-->(n6),(n4)-->(n7)
+            |",
+        );
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0)
+            |(n0)-->(n1),(n0)-->(n2),(n0)-->(n3)
+            |(n1)-->(n2),(n1)-->(n3),(n2)-->(n3)
+            |",
+        );
+
+        let candidates = ldf_filter(&data_graph, &query_graph).unwrap();
+        assert_eq!(candidates.candidates(0), &[0, 1, 2, 3, 4]);
+
+        let candidates = core_prune(&data_graph, &query_graph, candidates).unwrap();
+
+        for query_node in 0..query_graph.node_count() {
+            assert_eq!(candidates.candidates(query_node), &[0, 1, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn test_core_prune_no_candidates() {
+        // A 4-cycle is a 2-core, but the data graph is a tree.
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0),(n4:L0)
+            |(n0)-->(n1),(n0)-->(n2),(n1)-->(n3),(n1)-->(n4)
+            |",
+        );
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0)
+            |(n0)-->(n1),(n1)-->(n2),(n2)-->(n3),(n3)-->(n0)
+            |",
+        );
+
+        let candidates = ldf_filter(&data_graph, &query_graph).unwrap();
+
+        assert!(core_prune(&data_graph, &query_graph, candidates).is_none());
+    }
+}
@@ -1,22 +1,72 @@
+use std::collections::HashMap;
+
 use crate::graph::Graph;
 
-use super::Candidates;
+use super::{has_absent_label, nodes_with_labels, Candidates};
 
 // LDF: label-and-degree filtering
 //
 // C(u) = { v ∈ V(G) | L(v) = L(u) ∧ d(v) >= d(u) }
+//
+// A query node with the wildcard label matches data nodes of any label.
+// A multi-labeled data node matches query nodes with any subset of its labels.
+//
+// Query nodes in the same orbit, see `order::query_orbits`, have the same
+// labels and degree. Their candidates are computed once and copied, unless
+// their directed degrees or edge label frequencies differ.
 pub fn ldf_filter(data_graph: &Graph, query_graph: &Graph) -> Option<Candidates> {
+    // Rejects the query before allocating candidates for any query node.
+    if has_absent_label(data_graph, query_graph) {
+        return None;
+    }
+
     let mut candidates = Candidates::from((data_graph, query_graph));
 
-    for query_node in 0..query_graph.node_count() {
-        let label = query_graph.label(query_node);
+    let orbits = crate::order::query_orbits(query_graph);
+    let mut representatives = HashMap::new();
+
+    for (query_node, &orbit) in orbits.iter().enumerate() {
+        let labels = query_graph.labels(query_node);
         let degree = query_graph.degree(query_node);
 
-        let nodes_by_label = data_graph.nodes_by_label(label);
+        let directed_degrees = directed_degrees(data_graph, query_graph, query_node);
+
+        // If both graphs are edge labeled, a candidate needs at least as many
+        // incident edges per edge label as the query node.
+        let edge_label_frequency = (data_graph.has_edge_labels() && query_graph.has_edge_labels())
+            .then(|| edge_label_frequency(query_graph, query_node));
+
+        match representatives.get(&orbit) {
+            Some((representative, representative_degrees, representative_frequency))
+                if *representative_degrees == directed_degrees
+                    && *representative_frequency == edge_label_frequency =>
+            {
+                for idx in 0..candidates.candidate_count(*representative) {
+                    let data_node = candidates.candidates(*representative)[idx];
+                    candidates.add_candidate(query_node, data_node);
+                }
+                continue;
+            }
+            Some(_) => {}
+            None => {
+                representatives.insert(
+                    orbit,
+                    (query_node, directed_degrees, edge_label_frequency.clone()),
+                );
+            }
+        }
+
+        for data_node in nodes_with_labels(data_graph, labels) {
+            let directed_degrees_match = directed_degrees.is_none_or(|(out_degree, in_degree)| {
+                data_graph.out_degree(data_node) >= out_degree
+                    && data_graph.in_degree(data_node) >= in_degree
+            });
 
-        for data_node in nodes_by_label {
-            if data_graph.degree(*data_node) >= degree {
-                candidates.add_candidate(query_node, *data_node);
+            if data_graph.degree(data_node) >= degree
+                && directed_degrees_match
+                && edge_labels_match(data_graph, data_node, edge_label_frequency.as_ref())
+            {
+                candidates.add_candidate(query_node, data_node);
             }
         }
 
@@ -26,9 +76,62 @@ pub fn ldf_filter(data_graph: &Graph, query_graph: &Graph) -> Option<Candidates>
         }
     }
 
+    candidates.shrink_to_fit();
+
     Some(candidates)
 }
 
+// If both graphs are directed, a candidate also needs a sufficient in-
+// and out-degree. Edges that are marked as undirected are not counted.
+fn directed_degrees(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    query_node: usize,
+) -> Option<(usize, usize)> {
+    if !(data_graph.is_directed() && query_graph.is_directed()) {
+        return None;
+    }
+
+    let directed = |nodes: &[usize]| {
+        nodes
+            .iter()
+            .filter(|&&node| !query_graph.is_undirected_edge(query_node, node))
+            .count()
+    };
+
+    Some((
+        directed(query_graph.out_neighbors(query_node)),
+        directed(query_graph.in_neighbors(query_node)),
+    ))
+}
+
+fn edge_label_frequency(graph: &Graph, node: usize) -> HashMap<usize, usize> {
+    let mut frequency = HashMap::new();
+
+    for label in graph.incident_edge_labels(node) {
+        *frequency.entry(label).or_insert(0) += 1;
+    }
+
+    frequency
+}
+
+fn edge_labels_match(
+    data_graph: &Graph,
+    data_node: usize,
+    query_frequency: Option<&HashMap<usize, usize>>,
+) -> bool {
+    let query_frequency = match query_frequency {
+        Some(query_frequency) if !query_frequency.is_empty() => query_frequency,
+        _ => return true,
+    };
+
+    let data_frequency = edge_label_frequency(data_graph, data_node);
+
+    query_frequency
+        .iter()
+        .all(|(label, count)| data_frequency.get(label).is_some_and(|c| c >= count))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -74,6 +177,29 @@ mod tests {
         assert_eq!(candidates.candidate_count(2), 1);
     }
 
+    #[test]
+    fn test_ldf_filter_orbits() {
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0),(n4:L0)
+            |(n0)-->(n1),(n0)-->(n2),(n0)-->(n3),(n1)-->(n2),(n2)-->(n3),(n3)-->(n4)
+            |",
+        );
+        let diamond = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0)
+            |(n0)-->(n1),(n1)-->(n2),(n2)-->(n3),(n3)-->(n0),(n1)-->(n3)
+            |",
+        );
+
+        let candidates = ldf_filter(&data_graph, &diamond).unwrap();
+
+        assert_eq!(candidates.candidates(0), &[0, 1, 2, 3]);
+        assert_eq!(candidates.candidates(0), candidates.candidates(2));
+        assert_eq!(candidates.candidates(1), &[0, 2, 3]);
+        assert_eq!(candidates.candidates(1), candidates.candidates(3));
+    }
+
     #[test]
     fn test_ldf_filter_invalid_label() {
         let data_graph = graph(DATA_GRAPH_1);
@@ -82,6 +208,30 @@ mod tests {
         assert!(candidates.is_none())
     }
 
+    #[test]
+    fn test_ldf_filter_label_exceeds_max_label() {
+        let data_graph = graph(DATA_GRAPH_1);
+        let query_graph = graph("(n0:L9), (n1:L1), (n0)-->(n1)");
+        let candidates = ldf_filter(&data_graph, &query_graph);
+        assert!(candidates.is_none())
+    }
+
+    #[test]
+    fn test_ldf_filter_absent_label() {
+        let data_graph = graph(DATA_GRAPH_1);
+        // The first query node has candidates, the absent label rejects the
+        // query before they are computed.
+        let query_graph = graph("(n0:L1), (n1:L1000000), (n0)-->(n1)");
+
+        assert_eq!(data_graph.labels_present(), &[0, 1, 2, 4]);
+        assert!(ldf_filter(&data_graph, &query_graph).is_none());
+        assert!(crate::filter::has_absent_label(&data_graph, &query_graph));
+        assert_eq!(
+            crate::find(&data_graph, &query_graph, crate::Config::default()),
+            0
+        );
+    }
+
     #[test]
     fn test_ldf_filter_invalid_degree() {
         let data_graph = graph(DATA_GRAPH_1);
@@ -97,4 +247,47 @@ mod tests {
         let candidates = ldf_filter(&data_graph, &query_graph);
         assert!(candidates.is_none())
     }
+
+    #[test]
+    fn test_ldf_filter_edge_labels() {
+        fn labeled_graph(gdl: &str) -> Graph {
+            Graph::from_gdl(
+                &gdl.trim_margin().unwrap(),
+                crate::graph::LoadConfig::default().edge_labeled(),
+            )
+            .unwrap()
+        }
+
+        let data_graph = labeled_graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L0),(n3:L1)
+            |(n0)-[:E0]->(n1)
+            |(n2)-[:E1]->(n3)
+            |",
+        );
+        let query_graph = labeled_graph("(a:L0),(b:L1),(a)-[:E1]->(b)");
+
+        let candidates = ldf_filter(&data_graph, &query_graph).unwrap();
+
+        assert_eq!(candidates.candidates(0), &[2]);
+        assert_eq!(candidates.candidates(1), &[3]);
+
+        // Each of the reciprocal edges counts with its own label.
+        let load_config = crate::graph::LoadConfig::default()
+            .directed()
+            .edge_labeled();
+        let data_graph =
+            Graph::from_gdl("(a:L0),(b:L1),(a)-[:E1]->(b),(b)-[:E2]->(a)", load_config).unwrap();
+
+        for gdl in [
+            "(x:L0),(y:L1),(x)-[:E1]->(y)",
+            "(x:L0),(y:L1),(y)-[:E2]->(x)",
+        ] {
+            let query_graph = Graph::from_gdl(gdl, load_config).unwrap();
+            let candidates = ldf_filter(&data_graph, &query_graph).unwrap();
+
+            assert_eq!(candidates.candidates(0), &[0], "query: {}", gdl);
+            assert_eq!(candidates.candidates(1), &[1], "query: {}", gdl);
+        }
+    }
 }
@@ -1,30 +1,68 @@
+use std::{borrow::Cow, collections::HashMap};
+
 use crate::Graph;
 
-use super::Candidates;
+use super::{nodes_with_labels, Candidates};
 
+/// Keeps the data nodes whose neighbors contain at least as many nodes of
+/// each label as the neighbors of the query node.
+///
+/// Uses the neighbor label frequencies of graphs that have been loaded with
+/// [`LoadConfig::with_neighbor_label_frequency`](crate::graph::LoadConfig::with_neighbor_label_frequency).
+/// For other graphs, the frequencies are computed on demand, which results
+/// in the same candidates, but is slower. Wildcard neighbors of the query
+/// node are not counted, since they match neighbors of any label.
 pub fn nlf_filter(data_graph: &Graph, query_graph: &Graph) -> Option<Candidates> {
     let mut candidates = Candidates::from((data_graph, query_graph));
 
     for query_node in 0..query_graph.node_count() {
-        let label = query_graph.label(query_node);
+        let labels = query_graph.labels(query_node);
         let degree = query_graph.degree(query_node);
-        let query_nlf = query_graph.neighbor_label_frequency(query_node);
+        let query_nlf = neighbor_label_frequency(query_graph, query_node);
 
-        for &data_node in data_graph.nodes_by_label(label) {
+        for data_node in nodes_with_labels(data_graph, labels) {
             if data_graph.degree(data_node) >= degree {
-                let data_nlf = data_graph.neighbor_label_frequency(data_node);
+                let data_nlf = neighbor_label_frequency(data_graph, data_node);
+
+                if dominates(&data_nlf, &query_nlf) {
+                    candidates.add_candidate(query_node, data_node);
+                }
+            }
+        }
+
+        if candidates.candidate_count(query_node) == 0 {
+            return None;
+        }
+    }
 
-                if data_nlf.len() >= query_nlf.len() {
-                    let mut is_valid = true;
+    candidates.shrink_to_fit();
 
-                    for (query_label, query_label_count) in query_nlf.iter() {
-                        is_valid = matches!(data_nlf.get(query_label), Some(data_label_count) if data_label_count >= query_label_count);
-                    }
+    Some(candidates)
+}
 
-                    if is_valid {
-                        candidates.add_candidate(query_node, data_node);
-                    }
-                }
+/// Keeps the data nodes that pass the [`nlf_filter`] and whose two-hop
+/// neighborhood contains at least as many nodes of each label as the two-hop
+/// neighborhood of the query node.
+///
+/// The two-hop neighborhood of a node contains the distinct nodes within
+/// distance two, excluding the node itself. Since an embedding maps paths to
+/// paths, it maps the two-hop neighborhood of a query node injectively into
+/// the two-hop neighborhood of its data node. The frequencies of a data node
+/// are computed when it is first checked and reused for other query nodes.
+pub fn nlf2_filter(data_graph: &Graph, query_graph: &Graph) -> Option<Candidates> {
+    let one_hop_candidates = nlf_filter(data_graph, query_graph)?;
+    let mut candidates = Candidates::from((data_graph, query_graph));
+    let mut data_nlfs = vec![None; data_graph.node_count()];
+
+    for (query_node, one_hop) in one_hop_candidates.iter() {
+        let query_nlf = two_hop_label_frequency(query_graph, query_node);
+
+        for &data_node in one_hop {
+            let data_nlf = data_nlfs[data_node]
+                .get_or_insert_with(|| two_hop_label_frequency(data_graph, data_node));
+
+            if dominates(data_nlf, &query_nlf) {
+                candidates.add_candidate(query_node, data_node);
             }
         }
 
@@ -33,5 +71,201 @@ pub fn nlf_filter(data_graph: &Graph, query_graph: &Graph) -> Option<Candidates>
         }
     }
 
+    candidates.shrink_to_fit();
+
     Some(candidates)
 }
+
+/// Returns `true` if `data_nlf` contains each label of `query_nlf` at
+/// least as often.
+fn dominates(data_nlf: &HashMap<usize, usize>, query_nlf: &HashMap<usize, usize>) -> bool {
+    data_nlf.len() >= query_nlf.len()
+        && query_nlf.iter().all(|(query_label, query_label_count)| {
+            matches!(data_nlf.get(query_label), Some(data_label_count) if data_label_count >= query_label_count)
+        })
+}
+
+/// Counts the labels of the distinct nodes within distance two of the given
+/// node, excluding the node itself and wildcard nodes.
+fn two_hop_label_frequency(graph: &Graph, node: usize) -> HashMap<usize, usize> {
+    let mut two_hop_neighbors = graph
+        .neighbors(node)
+        .iter()
+        .flat_map(|&neighbor| {
+            std::iter::once(neighbor).chain(graph.neighbors(neighbor).iter().copied())
+        })
+        .filter(|&other| other != node && !graph.is_wildcard(other))
+        .collect::<Vec<_>>();
+    two_hop_neighbors.sort_unstable();
+    two_hop_neighbors.dedup();
+
+    let mut nlf = HashMap::new();
+    for other in two_hop_neighbors {
+        for &label in graph.labels(other) {
+            *nlf.entry(label).or_insert(0) += 1;
+        }
+    }
+    nlf
+}
+
+fn neighbor_label_frequency(graph: &Graph, node: usize) -> Cow<'_, HashMap<usize, usize>> {
+    if graph.has_neighbor_label_frequencies() {
+        return Cow::Borrowed(graph.neighbor_label_frequency(node));
+    }
+
+    let mut nlf = HashMap::new();
+    for &neighbor in graph.neighbors(node) {
+        if !graph.is_wildcard(neighbor) {
+            for &label in graph.labels(neighbor) {
+                *nlf.entry(label).or_insert(0) += 1;
+            }
+        }
+    }
+    Cow::Owned(nlf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::{GdlGraph, LoadConfig};
+    use trim_margin::MarginTrimmable;
+
+    fn graph(gdl: &str) -> GdlGraph {
+        gdl.trim_margin().unwrap().parse::<GdlGraph>().unwrap()
+    }
+
+    #[test]
+    fn test_nlf_filter() {
+        // n0 has enough neighbors, but none of them has label L1.
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L2),(n2:L2),(n3:L3)
+            |(n4:L0),(n5:L1),(n6:L2),(n7:L3)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n0)-->(n3)
+            |(n4)-->(n5)
+            |(n4)-->(n6)
+            |(n4)-->(n7)
+            |",
+        );
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L2),(n3:L3)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n0)-->(n3)
+            |",
+        );
+
+        let candidates = nlf_filter(&data_graph, &query_graph).unwrap();
+
+        assert_eq!(candidates.candidates(0), &[4]);
+        assert_eq!(candidates.candidates(1), &[5]);
+        assert_eq!(candidates.candidates(2), &[1, 2, 6]);
+        assert_eq!(candidates.candidates(3), &[3, 7]);
+
+        // Each of n0, n4 and n8 lacks a different neighbor label of the query
+        // node. Whichever label is checked last, the others must already
+        // rule out the remaining two nodes.
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L4),(n2:L2),(n3:L3)
+            |(n4:L0),(n5:L1),(n6:L4),(n7:L3)
+            |(n8:L0),(n9:L1),(n10:L2),(n11:L4)
+            |(n12:L0),(n13:L1),(n14:L2),(n15:L3)
+            |(n0)-->(n1),(n0)-->(n2),(n0)-->(n3)
+            |(n4)-->(n5),(n4)-->(n6),(n4)-->(n7)
+            |(n8)-->(n9),(n8)-->(n10),(n8)-->(n11)
+            |(n12)-->(n13),(n12)-->(n14),(n12)-->(n15)
+            |",
+        );
+
+        let candidates = nlf_filter(&data_graph, &query_graph).unwrap();
+
+        assert_eq!(candidates.candidates(0), &[12]);
+    }
+
+    #[test]
+    fn test_nlf2_filter() {
+        // n0 and n3 both have a neighbor with label L1, but only the one of n0
+        // is adjacent to a node with label L2.
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L2)
+            |(n3:L0),(n4:L1),(n5:L3)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |(n3)-->(n4)
+            |(n4)-->(n5)
+            |",
+        );
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L2)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |",
+        );
+
+        let one_hop = nlf_filter(&data_graph, &query_graph).unwrap();
+        let two_hop = nlf2_filter(&data_graph, &query_graph).unwrap();
+
+        assert_eq!(one_hop.candidates(0), &[0, 3]);
+        assert_eq!(two_hop.candidates(0), &[0]);
+        assert_eq!(two_hop.candidates(1), &[1]);
+        assert_eq!(two_hop.candidates(2), &[2]);
+
+        assert_eq!(
+            crate::find(&data_graph, &query_graph, crate::Filter::Nlf2),
+            crate::find(&data_graph, &query_graph, crate::Filter::Nlf)
+        );
+    }
+
+    #[test]
+    fn test_nlf_filter_without_loaded_frequencies() {
+        let data_gdl = "
+            |(n0:L0),(n1:L2),(n2:L2),(n3:L3)
+            |(n4:L0),(n5:L1),(n6:L2),(n7:L3)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n0)-->(n3)
+            |(n4)-->(n5)
+            |(n4)-->(n6)
+            |(n4)-->(n7)
+            |"
+        .trim_margin()
+        .unwrap();
+        let query_gdl = "
+            |(n0:L0),(n1:L1),(n2:L2),(n3:L3)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n0)-->(n3)
+            |"
+        .trim_margin()
+        .unwrap();
+
+        let data_graph = Graph::from_gdl(&data_gdl, LoadConfig::default()).unwrap();
+        let query_graph = Graph::from_gdl(&query_gdl, LoadConfig::default()).unwrap();
+        assert!(!data_graph.has_neighbor_label_frequencies());
+
+        let candidates = nlf_filter(&data_graph, &query_graph).unwrap();
+
+        assert_eq!(candidates.candidates(0), &[4]);
+        assert_eq!(candidates.candidates(1), &[5]);
+        assert_eq!(candidates.candidates(2), &[1, 2, 6]);
+        assert_eq!(candidates.candidates(3), &[3, 7]);
+
+        let data_graph_with_nlf = data_gdl.parse::<GdlGraph>().unwrap();
+        let query_graph_with_nlf = query_gdl.parse::<GdlGraph>().unwrap();
+
+        assert_eq!(
+            crate::find(&data_graph, &query_graph, crate::Filter::Nlf),
+            crate::find(
+                &data_graph_with_nlf,
+                &query_graph_with_nlf,
+                crate::Filter::Nlf
+            )
+        );
+    }
+}
@@ -1,43 +1,1546 @@
 use core::panic;
-use graph::input::dotgraph::DotGraph;
+use flate2::bufread::GzDecoder;
 use graph::prelude::{Graph as OtherGraph, *};
-use graph::UndirectedNodeLabeledCsrGraph;
+use graph::{DirectedCsrGraph, UndirectedCsrGraph};
+use std::io::{BufRead, Read};
 use std::path::Path;
 use std::{
-    collections::HashMap, convert::TryFrom, fmt::Display, ops::Deref, str::FromStr, time::Instant,
+    collections::HashMap,
+    fmt::Display,
+    ops::{Deref, Range},
+    str::FromStr,
+    sync::OnceLock,
+    time::{Duration, Instant},
 };
 
-use crate::{Config, Error, Filter};
+use crate::{Config, Enumeration, Error, Filter, Order};
 
-use linereader::LineReader;
+mod binary;
+mod builder;
+mod graphml;
 
-type CsrGraph = UndirectedNodeLabeledCsrGraph<usize, usize>;
+pub use builder::GraphBuilderMut;
+
+type UndirectedGraph = UndirectedCsrGraph<usize>;
+type DirectedGraph = DirectedCsrGraph<usize>;
+
+/// The relationship property that marks a relationship
+/// in a directed GDL graph as undirected.
+const UNDIRECTED_PROPERTY: &str = "undirected";
+
+/// The relationship property that marks a relationship
+/// in a GDL graph as anti-edge.
+const ANTI_EDGE_PROPERTY: &str = "anti";
+
+/// The line marker of anti-edges in .graph files, e.g. `!e 0 2`.
+const ANTI_EDGE_MARKER: &str = "!e";
+
+/// The relationship property that stores the weight of a relationship
+/// in a GDL graph.
+const EDGE_WEIGHT_PROPERTY: &str = "weight";
+
+/// Marks a relationship without label in an edge labeled graph.
+const NO_EDGE_LABEL: usize = usize::MAX;
+/// Marks an edge without weight in a weighted graph.
+const NO_EDGE_WEIGHT: f64 = f64::NAN;
+
+/// The label of a query node that matches data nodes of any label.
+///
+/// In .graph files, wildcard nodes are declared using `*` as label, e.g.
+/// `v 0 * 2`. In GDL, nodes without label are wildcard nodes.
+pub const ANY_LABEL: usize = usize::MAX;
+
+/// The label marker of wildcard nodes in .graph files, which also marks
+/// edges without label or weight.
+const ANY_LABEL_MARKER: &str = "*";
+
+/// Separates the labels of a multi-labeled node in .graph files.
+const LABEL_SEPARATOR: char = ',';
+
+/// The first two bytes of a gzip compressed file.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// The direction of the edges between two nodes, as seen from the first
+/// node, e.g. how a query edge between the query node at the current depth
+/// and one of its visited neighbors needs to be present in the data graph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EdgeDirection {
+    /// An edge in any direction.
+    Any,
+    /// An edge from the first node to the second one.
+    Outgoing,
+    /// An edge from the second node to the first one.
+    Incoming,
+    /// Edges in both directions.
+    Both,
+}
 
 pub struct Graph {
-    graph: CsrGraph,
+    graph: UndirectedGraph,
+    /// The number of labeled nodes, which includes isolated nodes
+    /// beyond the largest node id that the CSR graph knows about.
+    node_count: usize,
+    max_degree: usize,
+    /// The label of each node as index into `label_values`, or
+    /// [`ANY_LABEL`] for wildcard nodes. For multi-labeled nodes, this is
+    /// the smallest label.
+    labels: Box<[usize]>,
+    /// The nodes of each label, indexed like `label_values`, which lists
+    /// multi-labeled nodes under each of their labels. Wildcard nodes are
+    /// not indexed. `None`, if the graph has been loaded without node label
+    /// index, see [`LoadConfig::without_node_label_index`].
+    label_index: Option<LabelIndex>,
+    /// The number of nodes of each label, indexed like `label_values`.
+    label_frequencies: Box<[usize]>,
+    directed: Option<DirectedGraph>,
+    undirected_edges: Box<[(usize, usize)]>,
+    /// The sorted node pairs, with `source <= target`, that must not be
+    /// adjacent in the data graph.
+    anti_edges: Box<[(usize, usize)]>,
+    /// The edge labels for each node, aligned with its neighbors.
+    edge_labels: Option<Box<[Box<[usize]>]>>,
+    /// The edge weights for each node, aligned with its neighbors.
+    edge_weights: Option<Box<[Box<[f64]>]>>,
     neighbor_label_frequencies: Option<Box<[HashMap<usize, usize>]>>,
+    /// The labels of all nodes, if at least one node has multiple labels.
+    multi_labels: Option<MultiLabels>,
+    /// The sorted distinct labels of all nodes, i.e. the original value of
+    /// each label in `labels` and `label_index`, such that both are sized by
+    /// the number of labels instead of the max label. Also allows returning
+    /// the single label of a node as a slice.
+    label_values: Box<[usize]>,
+    /// The contiguous CSR arrays, which are materialized on first access,
+    /// see [`Graph::csr_offsets`].
+    raw_csr: OnceLock<RawCsr>,
+}
+
+/// The nodes of each label as contiguous array.
+struct LabelIndex {
+    /// The start of the nodes of each label in `nodes`, plus the total
+    /// number of nodes.
+    offsets: Box<[usize]>,
+    nodes: Box<[usize]>,
+}
+
+impl LabelIndex {
+    /// Groups the given `(node, label)` pairs, which need to be ordered by
+    /// node, by their label, given the number of pairs of each label.
+    fn new<I>(frequencies: &[usize], node_labels: I) -> Self
+    where
+        I: Iterator<Item = (usize, usize)>,
+    {
+        let label_count = frequencies.len();
+        let mut offsets = vec![0; label_count + 1];
+        for label in 0..label_count {
+            offsets[label + 1] = offsets[label] + frequencies[label];
+        }
+
+        let mut next = offsets.clone();
+        let mut nodes = vec![0; offsets[label_count]];
+        for (node, label) in node_labels {
+            nodes[next[label]] = node;
+            next[label] += 1;
+        }
+
+        Self {
+            offsets: offsets.into_boxed_slice(),
+            nodes: nodes.into_boxed_slice(),
+        }
+    }
+
+    /// Returns the sorted nodes of the given label, which are empty if the
+    /// label is out of range.
+    fn nodes(&self, label: usize) -> &[usize] {
+        match self.offsets.get(label..label.saturating_add(2)) {
+            Some(&[start, end]) => &self.nodes[start..end],
+            _ => &[],
+        }
+    }
+}
+
+/// The adjacency and labels of a graph as contiguous arrays.
+struct RawCsr {
+    offsets: Box<[usize]>,
+    targets: Box<[usize]>,
+    labels: Box<[usize]>,
+}
+
+/// The label sets of a graph with multi-labeled nodes.
+///
+/// The graph stores the smallest label of each node as its label.
+struct MultiLabels {
+    /// The sorted labels of each node.
+    labels: Box<[Box<[usize]>]>,
 }
 
 impl Graph {
     delegate::delegate! {
         to self.graph {
-            pub fn node_count(&self) -> usize;
             pub fn edge_count(&self) -> usize;
-            pub fn degree(&self, node: usize) -> usize;
-            pub fn max_degree(&self) -> usize;
-            pub fn label(&self, node: usize) -> usize;
-            pub fn neighbors(&self, node: usize) -> &[usize];
-            pub fn nodes_by_label(&self, label: usize) -> &[usize];
-            pub fn label_count(&self) -> usize;
-            pub fn max_label(&self) -> usize;
-            pub fn max_label_frequency(&self) -> usize;
         }
     }
 
+    pub fn max_degree(&self) -> usize {
+        self.max_degree
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.node_count
+    }
+
+    /// Returns the number of distinct labels, which is `0` for an empty
+    /// graph. Wildcard nodes do not count towards any label.
+    pub fn label_count(&self) -> usize {
+        self.label_values.len()
+    }
+
+    /// Returns the largest label, which is `0` for an empty graph.
+    pub fn max_label(&self) -> usize {
+        self.label_values.last().copied().unwrap_or_default()
+    }
+
+    /// Returns the number of nodes of the most frequent label, which is `0`
+    /// for an empty graph. Wildcard nodes do not count towards any label.
+    pub fn max_label_frequency(&self) -> usize {
+        self.label_frequencies
+            .iter()
+            .copied()
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Returns the label of the given node, which is [`ANY_LABEL`] for
+    /// wildcard nodes. For multi-labeled nodes, this is the smallest label.
+    pub fn label(&self, node: usize) -> usize {
+        match self.labels[node] {
+            ANY_LABEL => ANY_LABEL,
+            label => self.label_values[label],
+        }
+    }
+
+    /// Returns the index of the given label in `label_values`, or `None` if
+    /// no node has the label.
+    fn compact_label(&self, label: usize) -> Option<usize> {
+        self.label_values.binary_search(&label).ok()
+    }
+
+    /// Returns the sorted labels of the given node, which contain a single
+    /// label unless the node is multi-labeled.
+    pub fn labels(&self, node: usize) -> &[usize] {
+        if self.is_wildcard(node) {
+            return &[ANY_LABEL];
+        }
+        match &self.multi_labels {
+            Some(multi_labels) => &multi_labels.labels[node],
+            None => std::slice::from_ref(&self.label_values[self.labels[node]]),
+        }
+    }
+
+    /// Returns `true` if the given label is one of the labels of the node.
+    pub fn has_label(&self, node: usize, label: usize) -> bool {
+        self.labels(node).binary_search(&label).is_ok()
+    }
+
+    /// Returns `true` if at least one node has more than one label.
+    pub fn is_multi_labeled(&self) -> bool {
+        self.multi_labels.is_some()
+    }
+
+    /// Returns `true` if the given node matches data nodes of any label.
+    pub fn is_wildcard(&self, node: usize) -> bool {
+        self.labels.get(node) == Some(&ANY_LABEL)
+    }
+
+    pub fn degree(&self, node: usize) -> usize {
+        if node < self.graph.node_count() {
+            self.graph.degree(node)
+        } else {
+            0
+        }
+    }
+
+    pub fn neighbors(&self, node: usize) -> &[usize] {
+        if node < self.graph.node_count() {
+            self.graph.neighbors(node)
+        } else {
+            &[]
+        }
+    }
+
+    /// Returns the CSR offsets, which contain `node_count + 1` entries, such
+    /// that the neighbors of node `n` are
+    /// `&csr_targets()[csr_offsets()[n]..csr_offsets()[n + 1]]`.
+    ///
+    /// The arrays are materialized on the first call to one of
+    /// [`Graph::csr_offsets`], [`Graph::csr_targets`] or
+    /// [`Graph::node_labels`] and borrowed afterwards. The adjacency ignores
+    /// edge direction, see [`Graph::neighbors`].
+    pub fn csr_offsets(&self) -> &[usize] {
+        &self.raw_csr().offsets
+    }
+
+    /// Returns the concatenated, sorted neighbors of all nodes, see
+    /// [`Graph::csr_offsets`].
+    pub fn csr_targets(&self) -> &[usize] {
+        &self.raw_csr().targets
+    }
+
+    /// Returns the label of each node, see [`Graph::label`] and
+    /// [`Graph::csr_offsets`].
+    pub fn node_labels(&self) -> &[usize] {
+        &self.raw_csr().labels
+    }
+
+    fn raw_csr(&self) -> &RawCsr {
+        self.raw_csr.get_or_init(|| {
+            let mut offsets = Vec::with_capacity(self.node_count + 1);
+            let mut targets = Vec::with_capacity(2 * self.edge_count());
+            offsets.push(0);
+            for node in 0..self.node_count {
+                targets.extend_from_slice(self.neighbors(node));
+                offsets.push(targets.len());
+            }
+
+            RawCsr {
+                offsets: offsets.into_boxed_slice(),
+                targets: targets.into_boxed_slice(),
+                labels: (0..self.node_count).map(|node| self.label(node)).collect(),
+            }
+        })
+    }
+
+    /// Returns the sorted neighbors of the node that have the given label,
+    /// which may be one of multiple labels of a neighbor.
+    ///
+    /// Filters the neighbors, i.e. takes time linear in the degree.
+    pub fn neighbors_by_label(
+        &self,
+        node: usize,
+        label: usize,
+    ) -> impl Iterator<Item = usize> + '_ {
+        self.neighbors(node)
+            .iter()
+            .copied()
+            .filter(move |&neighbor| self.has_label(neighbor, label))
+    }
+
+    /// Returns the number of nodes for each degree, i.e. the value at
+    /// index `d` is the number of nodes with degree `d`.
+    pub fn degree_histogram(&self) -> Vec<usize> {
+        let mut histogram = vec![0; self.max_degree() + 1];
+        for node in 0..self.node_count() {
+            histogram[self.degree(node)] += 1;
+        }
+        histogram
+    }
+
+    /// Returns the average number of neighbors per node.
+    pub fn average_degree(&self) -> f64 {
+        if self.node_count() == 0 {
+            return 0.0;
+        }
+        (2 * self.edge_count()) as f64 / self.node_count() as f64
+    }
+
+    /// Returns the fraction of node pairs that are connected by an edge,
+    /// treating the graph as undirected.
+    pub fn density(&self) -> f64 {
+        let node_count = self.node_count();
+        if node_count < 2 {
+            return 0.0;
+        }
+        (2 * self.edge_count()) as f64 / (node_count * (node_count - 1)) as f64
+    }
+
+    /// Returns all nodes with the given label.
+    ///
+    /// Query graphs often contain labels that are absent from the data graph,
+    /// in which case the returned slice is empty. Wildcard nodes are not
+    /// listed. If the graph has been loaded without node label index,
+    /// the returned slice is always empty, see [`Graph::has_node_label_index`].
+    pub fn nodes_by_label(&self, label: usize) -> &[usize] {
+        match (&self.label_index, self.compact_label(label)) {
+            (Some(label_index), Some(label)) => label_index.nodes(label),
+            _ => &[],
+        }
+    }
+
+    /// Returns `true` unless the graph has been loaded without node label
+    /// index, see [`LoadConfig::without_node_label_index`].
+    pub fn has_node_label_index(&self) -> bool {
+        self.label_index.is_some()
+    }
+
+    /// Returns the number of nodes with the given label, which is `0` for
+    /// labels that are absent from the graph. Wildcard nodes are not counted.
+    ///
+    /// The frequencies are also available without node label index.
+    pub fn label_frequency(&self, label: usize) -> usize {
+        self.compact_label(label)
+            .map_or(0, |label| self.label_frequencies[label])
+    }
+
+    /// Returns the sorted distinct labels that at least one node has, i.e.
+    /// the labels with non-zero [`Graph::label_frequency`], which also works
+    /// for graphs without node label index.
+    pub fn labels_present(&self) -> &[usize] {
+        &self.label_values
+    }
+
+    /// Returns each label that at least one node has with its frequency, see
+    /// [`Graph::label_frequency`].
+    pub fn labels_iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.label_values
+            .iter()
+            .map(move |&label| (label, self.label_frequency(label)))
+    }
+
+    /// Returns `true` if there is an edge between `source` and `target`,
+    /// ignoring edge direction.
+    ///
+    /// `exists(x, x)` returns whether `x` has a self-loop, which is always
+    /// `false` for loaded graphs, since they reject self-loops, see
+    /// [`Error::SelfLoop`]. Nodes without neighbors, including nodes that are
+    /// out of range, have no edges.
     pub fn exists(&self, source: usize, target: usize) -> bool {
         self.neighbors(source).binary_search(&target).is_ok()
     }
 
+    /// Alias for [`Graph::exists`].
+    pub fn has_edge(&self, source: usize, target: usize) -> bool {
+        self.exists(source, target)
+    }
+
+    /// Returns the number of parallel edges between `source` and `target`,
+    /// i.e. how often `target` occurs in the neighbors of `source`.
+    ///
+    /// This is at most `1`, unless the input contains duplicate edges.
+    pub fn edge_count_between(&self, source: usize, target: usize) -> usize {
+        let neighbors = self.neighbors(source);
+        let start = neighbors.partition_point(|&neighbor| neighbor < target);
+        let end = neighbors.partition_point(|&neighbor| neighbor <= target);
+        end - start
+    }
+
+    /// Returns `true` if the graph has been loaded including edge direction.
+    pub fn is_directed(&self) -> bool {
+        self.directed.is_some()
+    }
+
+    /// Returns the nodes the given node points to.
+    ///
+    /// If the graph is undirected, this is equivalent to `neighbors`.
+    pub fn out_neighbors(&self, node: usize) -> &[usize] {
+        match &self.directed {
+            Some(directed) if node < directed.node_count() => directed.out_neighbors(node),
+            Some(_) => &[],
+            None => self.neighbors(node),
+        }
+    }
+
+    /// Returns the nodes that point to the given node.
+    ///
+    /// If the graph is undirected, this is equivalent to `neighbors`.
+    pub fn in_neighbors(&self, node: usize) -> &[usize] {
+        match &self.directed {
+            Some(directed) if node < directed.node_count() => directed.in_neighbors(node),
+            Some(_) => &[],
+            None => self.neighbors(node),
+        }
+    }
+
+    /// Returns the number of edges starting at the given node.
+    ///
+    /// If the graph is undirected, this is equivalent to `degree`.
+    pub fn out_degree(&self, node: usize) -> usize {
+        match &self.directed {
+            Some(directed) if node < directed.node_count() => directed.out_degree(node),
+            Some(_) => 0,
+            None => self.degree(node),
+        }
+    }
+
+    /// Returns the number of edges ending at the given node.
+    ///
+    /// If the graph is undirected, this is equivalent to `degree`.
+    pub fn in_degree(&self, node: usize) -> usize {
+        match &self.directed {
+            Some(directed) if node < directed.node_count() => directed.in_degree(node),
+            Some(_) => 0,
+            None => self.degree(node),
+        }
+    }
+
+    /// Checks if there is an edge pointing from `source` to `target`.
+    ///
+    /// If the graph is undirected, this is equivalent to `exists`.
+    pub fn exists_directed(&self, source: usize, target: usize) -> bool {
+        self.out_neighbors(source).binary_search(&target).is_ok()
+    }
+
+    /// Returns `true` if the edge between `source` and `target` has been
+    /// explicitly marked as undirected within a directed graph.
+    ///
+    /// This allows a query graph to mix directed and undirected edges.
+    pub fn is_undirected_edge(&self, source: usize, target: usize) -> bool {
+        let edge = (usize::min(source, target), usize::max(source, target));
+        self.undirected_edges.binary_search(&edge).is_ok()
+    }
+
+    /// Returns the anti-edges of a query graph, each once and with
+    /// `source <= target`.
+    ///
+    /// An anti-edge requires that the data nodes of its end nodes are not
+    /// adjacent. Unlike induced matching, this only constrains specific
+    /// pairs of query nodes.
+    pub fn anti_edges(&self) -> &[(usize, usize)] {
+        &self.anti_edges
+    }
+
+    /// Returns `true` if there is an anti-edge between `source` and `target`.
+    pub fn is_anti_edge(&self, source: usize, target: usize) -> bool {
+        let edge = (usize::min(source, target), usize::max(source, target));
+        self.anti_edges.binary_search(&edge).is_ok()
+    }
+
+    /// Returns `true` if the graph has been loaded including edge labels.
+    pub fn has_edge_labels(&self) -> bool {
+        self.edge_labels.is_some()
+    }
+
+    /// Returns the label of the edge between `source` and `target`.
+    ///
+    /// Returns `None` if there is no such edge, the edge has no label or
+    /// the graph has been loaded without edge labels.
+    ///
+    /// If there are parallel edges, e.g. in both directions, the label of
+    /// the first edge from `source` to `target` is returned, see
+    /// [`Graph::edge_labels_between`] for the labels of all of them.
+    pub fn edge_label(&self, source: usize, target: usize) -> Option<usize> {
+        let edge_labels = self.edge_labels.as_ref()?;
+        let idx = self.edge_slot(source, target)?;
+
+        match edge_labels[source][idx] {
+            NO_EDGE_LABEL => None,
+            label => Some(label),
+        }
+    }
+
+    /// Returns the labels of the parallel edges between `source` and
+    /// `target` in the given direction, skipping edges without label.
+    ///
+    /// [`EdgeDirection::Outgoing`] only considers the edges from `source` to
+    /// `target`, [`EdgeDirection::Incoming`] only the edges back. All edges
+    /// are considered for the other directions or if the graph has been
+    /// loaded without edge direction.
+    pub fn edge_labels_between(
+        &self,
+        source: usize,
+        target: usize,
+        direction: EdgeDirection,
+    ) -> impl Iterator<Item = usize> + '_ {
+        let labels = match &self.edge_labels {
+            Some(edge_labels) if source < edge_labels.len() => {
+                &edge_labels[source][self.edge_slots(source, target, direction)]
+            }
+            _ => &[],
+        };
+
+        labels
+            .iter()
+            .copied()
+            .filter(|&label| label != NO_EDGE_LABEL)
+    }
+
+    /// Returns the labels of the edges of the given node, one for each of
+    /// its neighbors, including parallel edges, skipping edges without label.
+    pub fn incident_edge_labels(&self, node: usize) -> impl Iterator<Item = usize> + '_ {
+        let labels = match &self.edge_labels {
+            Some(edge_labels) if node < edge_labels.len() => &edge_labels[node][..],
+            _ => &[],
+        };
+
+        labels
+            .iter()
+            .copied()
+            .filter(|&label| label != NO_EDGE_LABEL)
+    }
+
+    /// Returns `true` if the graph has been loaded including edge weights.
+    pub fn has_edge_weights(&self) -> bool {
+        self.edge_weights.is_some()
+    }
+
+    /// Returns the weight of the edge between `source` and `target`.
+    ///
+    /// Returns `None` if there is no such edge, the edge has no weight or
+    /// the graph has been loaded without edge weights.
+    ///
+    /// If there are parallel edges, the weight of the first one is returned,
+    /// see [`Graph::edge_weights_between`] for the weights of all of them.
+    pub fn edge_weight(&self, source: usize, target: usize) -> Option<f64> {
+        let edge_weights = self.edge_weights.as_ref()?;
+        let idx = self.edge_slot(source, target)?;
+
+        Some(edge_weights[source][idx]).filter(|weight| !weight.is_nan())
+    }
+
+    /// Returns the weights of the parallel edges between `source` and
+    /// `target` in the given direction, skipping edges without weight, see
+    /// [`Graph::edge_labels_between`].
+    pub fn edge_weights_between(
+        &self,
+        source: usize,
+        target: usize,
+        direction: EdgeDirection,
+    ) -> impl Iterator<Item = f64> + '_ {
+        let weights = match &self.edge_weights {
+            Some(edge_weights) if source < edge_weights.len() => {
+                &edge_weights[source][self.edge_slots(source, target, direction)]
+            }
+            _ => &[],
+        };
+
+        weights.iter().copied().filter(|weight| !weight.is_nan())
+    }
+
+    /// Returns the index of the first `target` in the neighbors of `source`.
+    fn edge_slot(&self, source: usize, target: usize) -> Option<usize> {
+        Some(self.edge_slots(source, target, EdgeDirection::Any).start)
+            .filter(|&idx| self.neighbors(source).get(idx) == Some(&target))
+    }
+
+    /// Returns the indices of the parallel edges between `source` and
+    /// `target` in the given direction in the neighbors of `source`.
+    ///
+    /// The values of the edges from `source` to `target` precede the values
+    /// of the edges back, see [`Graph::align_edge_values`], so the first
+    /// `out_neighbors` occurrences of `target` are the outgoing edges.
+    fn edge_slots(&self, source: usize, target: usize, direction: EdgeDirection) -> Range<usize> {
+        let neighbors = self.neighbors(source);
+        let start = neighbors.partition_point(|&neighbor| neighbor < target);
+        let end = neighbors.partition_point(|&neighbor| neighbor <= target);
+
+        if self.directed.is_none() {
+            return start..end;
+        }
+
+        let out_neighbors = self.out_neighbors(source);
+        let outgoing = out_neighbors.partition_point(|&neighbor| neighbor <= target)
+            - out_neighbors.partition_point(|&neighbor| neighbor < target);
+        let split = usize::min(start + outgoing, end);
+
+        match direction {
+            EdgeDirection::Outgoing => start..split,
+            EdgeDirection::Incoming => split..end,
+            EdgeDirection::Any | EdgeDirection::Both => start..end,
+        }
+    }
+
+    /// Stores the weights of the given `(source, target, weight)` edges
+    /// aligned with the neighbors of both end nodes.
+    fn set_edge_weights(&mut self, edges: &[(usize, usize, f64)]) {
+        self.edge_weights = Some(self.align_edge_values(edges, NO_EDGE_WEIGHT));
+    }
+
+    /// Stores the labels of the given `(source, target, label)` edges
+    /// aligned with the neighbors of both end nodes.
+    fn set_edge_labels(&mut self, edges: &[(usize, usize, usize)]) {
+        self.edge_labels = Some(self.align_edge_values(edges, NO_EDGE_LABEL));
+    }
+
+    /// Aligns the values of the given edges with the neighbors of both end
+    /// nodes, using `missing` for all other neighbors.
+    ///
+    /// Each edge takes the next free slot among the parallel edges of its
+    /// end nodes, such that parallel edges keep their own values. All edges
+    /// take their slot at the source first, such that e.g. edges in both
+    /// directions keep the value of their direction at their source.
+    fn align_edge_values<T: Copy>(
+        &self,
+        edges: &[(usize, usize, T)],
+        missing: T,
+    ) -> Box<[Box<[T]>]> {
+        let mut values = (0..self.node_count())
+            .map(|node| vec![missing; self.degree(node)])
+            .collect::<Vec<_>>();
+        let mut taken = (0..self.node_count())
+            .map(|node| vec![false; self.degree(node)])
+            .collect::<Vec<_>>();
+
+        let at_source = edges
+            .iter()
+            .map(|&(source, target, value)| (source, target, value));
+        let at_target = edges
+            .iter()
+            .map(|&(source, target, value)| (target, source, value));
+
+        for (node, other, value) in at_source.chain(at_target) {
+            let neighbors = self.neighbors(node);
+            let start = neighbors.partition_point(|&neighbor| neighbor < other);
+            if let Some(idx) = (start..neighbors.len())
+                .take_while(|&idx| neighbors[idx] == other)
+                .find(|&idx| !taken[node][idx])
+            {
+                taken[node][idx] = true;
+                values[node][idx] = value;
+            }
+        }
+
+        values.into_iter().map(Vec::into_boxed_slice).collect()
+    }
+
+    /// Copies the values of `graph`, which are aligned with its neighbors,
+    /// to this graph, which contains node `new_id(node)` for each node of
+    /// `graph` that is kept, together with the edges between kept nodes.
+    ///
+    /// Parallel edges keep their order, so each edge keeps its value.
+    fn copy_edge_values<T: Copy, F>(
+        &self,
+        graph: &Graph,
+        values: &[Box<[T]>],
+        new_id: F,
+        missing: T,
+    ) -> Box<[Box<[T]>]>
+    where
+        F: Fn(usize) -> Option<usize>,
+    {
+        let mut copied = (0..self.node_count())
+            .map(|node| vec![missing; self.degree(node)])
+            .collect::<Vec<_>>();
+
+        for (node, values) in values.iter().enumerate() {
+            let new_node = match new_id(node) {
+                Some(new_node) => new_node,
+                None => continue,
+            };
+            let neighbors = graph.neighbors(node);
+            let new_neighbors = self.neighbors(new_node);
+
+            for (idx, &neighbor) in neighbors.iter().enumerate() {
+                if let Some(new_neighbor) = new_id(neighbor) {
+                    let offset = idx - neighbors.partition_point(|&other| other < neighbor);
+                    let start = new_neighbors.partition_point(|&other| other < new_neighbor);
+                    copied[new_node][start + offset] = values[idx];
+                }
+            }
+        }
+
+        copied.into_iter().map(Vec::into_boxed_slice).collect()
+    }
+
+    /// Stores the given node pairs as anti-edges.
+    fn set_anti_edges(&mut self, anti_edges: Vec<(usize, usize)>) {
+        let mut anti_edges = anti_edges
+            .into_iter()
+            .map(|(source, target)| (usize::min(source, target), usize::max(source, target)))
+            .collect::<Vec<_>>();
+        anti_edges.sort_unstable();
+        anti_edges.dedup();
+        self.anti_edges = anti_edges.into_boxed_slice();
+    }
+
+    /// Assigns multiple labels to the given nodes. The graph needs to store
+    /// the smallest of these labels for each node.
+    ///
+    /// The neighbor label frequencies only count the smallest label, so they
+    /// are rebuilt to count each label.
+    fn set_multi_labels(&mut self, node_labels: Vec<(usize, Vec<usize>)>) {
+        if node_labels.iter().all(|(_, labels)| labels.len() < 2) {
+            return;
+        }
+
+        let mut labels = (0..self.node_count())
+            .map(|node| self.labels(node).to_vec())
+            .collect::<Vec<_>>();
+
+        for (node, mut node_labels) in node_labels {
+            node_labels.sort_unstable();
+            node_labels.dedup();
+            labels[node] = node_labels;
+        }
+
+        self.set_node_labels(labels);
+    }
+
+    /// Replaces the labels of all nodes, which are `[ANY_LABEL]` for
+    /// wildcard nodes, and rebuilds the label index and, if loaded, the
+    /// neighbor label frequencies.
+    fn set_node_labels(&mut self, labels: Vec<Vec<usize>>) {
+        let mut label_values = labels
+            .iter()
+            .flatten()
+            .copied()
+            .filter(|&label| label != ANY_LABEL)
+            .collect::<Vec<_>>();
+        label_values.sort_unstable();
+        label_values.dedup();
+
+        self.labels = labels
+            .iter()
+            .map(|labels| match labels.first() {
+                Some(&label) if label != ANY_LABEL => {
+                    label_values.partition_point(|&value| value < label)
+                }
+                _ => ANY_LABEL,
+            })
+            .collect();
+        self.multi_labels = labels
+            .iter()
+            .any(|labels| labels.len() > 1)
+            .then(|| MultiLabels {
+                labels: labels.into_iter().map(Vec::into_boxed_slice).collect(),
+            });
+        self.label_values = label_values.into_boxed_slice();
+
+        self.index_labels(self.has_node_label_index());
+        self.rebuild_neighbor_label_frequencies();
+    }
+
+    /// Counts the nodes of each label and, if requested, builds the label
+    /// index, see [`Graph::nodes_by_label`].
+    fn index_labels(&mut self, node_label_index: bool) {
+        let mut frequencies = vec![0; self.label_count()];
+        for (_, label) in self.indexed_labels() {
+            frequencies[label] += 1;
+        }
+
+        self.label_index =
+            node_label_index.then(|| LabelIndex::new(&frequencies, self.indexed_labels()));
+        self.label_frequencies = frequencies.into_boxed_slice();
+    }
+
+    /// Returns the `(node, label)` pairs of each label of each node, ordered
+    /// by node and skipping wildcard nodes, where the label is its index in
+    /// `label_values`.
+    fn indexed_labels(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        (0..self.node_count).flat_map(move |node| {
+            self.labels(node)
+                .iter()
+                .filter(|&&label| label != ANY_LABEL)
+                .map(move |&label| {
+                    let label = self.label_values.partition_point(|&value| value < label);
+                    (node, label)
+                })
+        })
+    }
+
+    /// Recomputes the neighbor label frequencies, if they have been loaded,
+    /// see [`Graph::compute_neighbor_label_frequencies`].
+    fn rebuild_neighbor_label_frequencies(&mut self) {
+        if self.has_neighbor_label_frequencies() {
+            self.compute_neighbor_label_frequencies();
+        }
+    }
+
+    /// Computes the neighbor label frequencies, counting each label of a
+    /// neighbor and skipping wildcard neighbors.
+    fn compute_neighbor_label_frequencies(&mut self) {
+        let nlfs = (0..self.node_count())
+            .map(|node| {
+                let mut nlf = HashMap::new();
+                for &neighbor in self.neighbors(node) {
+                    if !self.is_wildcard(neighbor) {
+                        for &label in self.labels(neighbor) {
+                            *nlf.entry(label).or_insert(0) += 1;
+                        }
+                    }
+                }
+                nlf
+            })
+            .collect();
+        self.neighbor_label_frequencies = Some(nlfs);
+    }
+
+    /// Creates a graph from the given GDL string.
+    ///
+    /// Node labels need to be prefixed with a single character, e.g.
+    /// `(n0:L0)` declares label `0`. Relationships are undirected, i.e.
+    /// `(n0)-->(n1)` and `(n1)-->(n0)` declare the same edge and matching
+    /// ignores their direction. If the load config requests a directed
+    /// graph, a relationship can be marked as undirected using a property,
+    /// e.g. `(n0)-[{undirected: true}]->(n1)`. If the load config requests
+    /// edge labels, relationship types are prefixed like node labels, e.g.
+    /// `(n0)-[:E1]->(n1)` declares edge label `1`. Nodes without label are
+    /// wildcard nodes, see [`ANY_LABEL`]. Nodes may have multiple labels,
+    /// e.g. `(n0:L0:L1)`. A relationship can be marked as anti-edge using a
+    /// property, e.g. `(n0)-[{anti: true}]->(n2)`, see [`Graph::anti_edges`].
+    /// Edge weights are read from the `weight` property, if the load config
+    /// requests them, see [`LoadConfig::weighted`].
+    pub fn from_gdl(gdl: &str, load_config: LoadConfig) -> Result<Self, Error> {
+        let gdl_graph = gdl.parse::<gdl::Graph>()?;
+        let anti_edges = gdl_anti_edges(&gdl_graph);
+
+        // Unlabeled nodes are wildcard nodes and the graph stores the
+        // smallest of multiple labels.
+        let (labels, edges) = gdl_labels_and_edges(&gdl_graph)?;
+        let mut graph = Graph::from_parts(
+            &labels
+                .iter()
+                .map(|labels| labels.first().copied().unwrap_or(ANY_LABEL))
+                .collect::<Vec<_>>(),
+            &edges,
+            load_config,
+        )?;
+        graph.set_multi_labels(
+            labels
+                .into_iter()
+                .enumerate()
+                .filter(|(_, labels)| labels.len() > 1)
+                .collect(),
+        );
+
+        if load_config.edge_labels {
+            graph.set_edge_labels(&gdl_edge_labels(&gdl_graph)?);
+        }
+
+        if load_config.edge_weights {
+            graph.set_edge_weights(&gdl_edge_weights(&gdl_graph)?);
+        }
+
+        if load_config.directed {
+            graph.directed = Some(if anti_edges.is_empty() {
+                DirectedGraph::from((&gdl_graph, CsrLayout::Sorted))
+            } else {
+                let (_, edges) = gdl_labels_and_edges(&gdl_graph)?;
+                GraphBuilder::new()
+                    .csr_layout(CsrLayout::Sorted)
+                    .edges(edges)
+                    .build()
+            });
+            graph.undirected_edges = undirected_edges(&gdl_graph).into_boxed_slice();
+        }
+
+        graph.set_anti_edges(anti_edges);
+
+        Ok(graph)
+    }
+
+    /// Creates a graph from the given node labels and undirected edges,
+    /// e.g. to generate query graphs programmatically.
+    ///
+    /// Node `i` has label `node_labels[i]`, which may be [`ANY_LABEL`].
+    /// Neighbor label frequencies are computed, like for graphs parsed via
+    /// [`FromStr`].
+    pub fn from_edges(node_labels: &[usize], edges: &[(usize, usize)]) -> Result<Self, Error> {
+        let node_count = node_labels.len();
+
+        if let Some(&node) = edges
+            .iter()
+            .flat_map(|(source, target)| [source, target])
+            .find(|&&node| node >= node_count)
+        {
+            return Err(Error::InvalidNode { node, node_count });
+        }
+
+        Graph::from_parts(
+            node_labels,
+            edges,
+            LoadConfig::with_neighbor_label_frequency(),
+        )
+    }
+
+    /// Creates a graph from the given GraphML document.
+    ///
+    /// Node labels are read from the `<data>` elements of the node key
+    /// whose `id` or `attr.name` equals `label_key` and must be integers.
+    /// Node ids are remapped to `0..n` in document order. If the load
+    /// config requests a directed graph, each edge is directed from its
+    /// `source` to its `target`.
+    pub fn from_graphml(
+        graphml: &str,
+        label_key: &str,
+        load_config: LoadConfig,
+    ) -> Result<Self, Error> {
+        let graphml::GraphMl { labels, edges } = graphml::parse(graphml, label_key)?;
+        let mut graph = Graph::from_parts(&labels, &edges, load_config)?;
+
+        if load_config.edge_labels {
+            graph.set_edge_labels(&[]);
+        }
+
+        if load_config.edge_weights {
+            graph.set_edge_weights(&[]);
+        }
+
+        if load_config.directed {
+            graph.directed = Some(GraphBuilder::new().edges(edges).build());
+        }
+
+        Ok(graph)
+    }
+
+    /// Creates a graph from an edge list with one `source<delimiter>target`
+    /// pair per line and a label list with one `node<delimiter>label` pair
+    /// per line.
+    ///
+    /// Edges are treated as undirected, i.e. duplicates and reverse edges
+    /// are merged. Nodes may appear only in the label list, in which case
+    /// they have no neighbors, but every node up to the largest node id
+    /// needs a label. Empty lines, lines starting with `#` and a header
+    /// line that does not start with a number are skipped.
+    pub fn from_edge_list(
+        edges: &str,
+        labels: &str,
+        delimiter: char,
+        load_config: LoadConfig,
+    ) -> Result<Self, Error> {
+        let mut directed_edges = parse_pairs(edges, delimiter)?;
+        let node_labels = parse_pairs(labels, delimiter)?;
+
+        let node_count = directed_edges
+            .iter()
+            .map(|&(source, target)| usize::max(source, target) + 1)
+            .chain(node_labels.iter().map(|&(node, _)| node + 1))
+            .max()
+            .unwrap_or(0);
+
+        let mut labels = vec![None; node_count];
+        for &(node, label) in &node_labels {
+            labels[node] = Some(label);
+        }
+        let labels = labels
+            .into_iter()
+            .enumerate()
+            .map(|(node, label)| {
+                label.ok_or_else(|| invalid_data(format!("node {} has no label", node)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut edges = directed_edges
+            .iter()
+            .map(|&(source, target)| (source.min(target), source.max(target)))
+            .collect::<Vec<_>>();
+        edges.sort_unstable();
+        edges.dedup();
+
+        let mut graph = Graph::from_parts(&labels, &edges, load_config)?;
+
+        if load_config.directed {
+            directed_edges.sort_unstable();
+            directed_edges.dedup();
+            graph.directed = Some(GraphBuilder::new().edges(directed_edges).build());
+        }
+
+        Ok(graph)
+    }
+
+    /// Creates a copy of this graph with the same topology but with the
+    /// given node labels attached, e.g. to label a purely structural graph.
+    ///
+    /// The label index and, if loaded, the neighbor label frequencies are
+    /// rebuilt from the new labels. Edge direction is preserved.
+    pub fn clone_with_labels(&self, labels: &[usize]) -> Result<Graph, Error> {
+        if labels.len() != self.node_count() {
+            return Err(Error::InvalidLabelCount {
+                expected: self.node_count(),
+                actual: labels.len(),
+            });
+        }
+
+        let edges = match &self.directed {
+            Some(directed) => (0..directed.node_count())
+                .flat_map(|source| {
+                    directed
+                        .out_neighbors(source)
+                        .iter()
+                        .map(move |&target| (source, target))
+                })
+                .collect::<Vec<_>>(),
+            None => self.edges(),
+        };
+
+        let load_config = LoadConfig {
+            neighbor_label_frequency: self.neighbor_label_frequencies.is_some(),
+            directed: self.is_directed(),
+            edge_labels: self.has_edge_labels(),
+            edge_weights: self.has_edge_weights(),
+            node_label_index: self.has_node_label_index(),
+            ..LoadConfig::default()
+        };
+
+        let mut graph = Graph::from_parts(labels, &edges, load_config)?;
+
+        if self.is_directed() {
+            graph.directed = Some(GraphBuilder::new().edges(edges).build());
+            graph.undirected_edges = self.undirected_edges.clone();
+        }
+
+        // The topology is unchanged, so the labels are still aligned.
+        graph.edge_labels = self.edge_labels.clone();
+        graph.edge_weights = self.edge_weights.clone();
+        graph.anti_edges = self.anti_edges.clone();
+
+        Ok(graph)
+    }
+
+    /// Extracts the subgraph induced by the given nodes, i.e. it contains all
+    /// edges whose end nodes are both in `nodes`.
+    ///
+    /// Duplicate nodes are ignored. The nodes are renumbered to `0..k` in
+    /// ascending order of their id in this graph and keep their labels.
+    /// Returns [`Error::InvalidNode`] if a node is out of range. Edge
+    /// direction, edge labels, edge weights and anti-edges are preserved.
+    pub fn subgraph(&self, nodes: &[usize]) -> Result<Graph, Error> {
+        let node_count = self.node_count();
+        if let Some(&node) = nodes.iter().find(|&&node| node >= node_count) {
+            return Err(Error::InvalidNode { node, node_count });
+        }
+
+        let mut nodes = nodes.to_vec();
+        nodes.sort_unstable();
+        nodes.dedup();
+
+        let new_id = |node: usize| nodes.binary_search(&node).ok();
+        let remap = |pairs: &mut dyn Iterator<Item = (usize, usize)>| {
+            pairs
+                .filter_map(|(source, target)| Some((new_id(source)?, new_id(target)?)))
+                .collect::<Vec<_>>()
+        };
+
+        let neighbors = nodes
+            .iter()
+            .map(|&node| {
+                self.neighbors(node)
+                    .iter()
+                    .filter_map(|&neighbor| new_id(neighbor))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let edges = adjacency_edges(nodes.len(), |node| &neighbors[node]);
+
+        let load_config = LoadConfig {
+            neighbor_label_frequency: self.has_neighbor_label_frequencies(),
+            directed: self.is_directed(),
+            edge_labels: self.has_edge_labels(),
+            edge_weights: self.has_edge_weights(),
+            node_label_index: self.has_node_label_index(),
+            ..LoadConfig::default()
+        };
+
+        let labels = nodes
+            .iter()
+            .map(|&node| self.label(node))
+            .collect::<Vec<_>>();
+        let mut graph = Graph::from_parts(&labels, &edges, load_config)?;
+
+        graph.set_multi_labels(
+            nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, &node)| !self.is_wildcard(node))
+                .map(|(new_node, &node)| (new_node, self.labels(node).to_vec()))
+                .collect(),
+        );
+
+        if let Some(directed) = &self.directed {
+            let directed_edges = remap(&mut (0..directed.node_count()).flat_map(|source| {
+                directed
+                    .out_neighbors(source)
+                    .iter()
+                    .map(move |&target| (source, target))
+            }));
+            graph.directed = Some(GraphBuilder::new().edges(directed_edges).build());
+            graph.undirected_edges =
+                remap(&mut self.undirected_edges.iter().copied()).into_boxed_slice();
+        }
+
+        if let Some(edge_labels) = &self.edge_labels {
+            graph.edge_labels =
+                Some(graph.copy_edge_values(self, edge_labels, new_id, NO_EDGE_LABEL));
+        }
+
+        if let Some(edge_weights) = &self.edge_weights {
+            graph.edge_weights =
+                Some(graph.copy_edge_values(self, edge_weights, new_id, NO_EDGE_WEIGHT));
+        }
+
+        graph.set_anti_edges(remap(&mut self.anti_edges.iter().copied()));
+
+        Ok(graph)
+    }
+
+    /// Creates a copy of this graph in which node `n` becomes node
+    /// `permutation[n]` and the `i`-th label of [`Graph::labels_present`]
+    /// becomes label `label_map[i]`, see
+    /// [`crate::graph_ops::relabel_by_frequency`].
+    ///
+    /// The permutation needs to be a bijection on the nodes and the label map
+    /// needs to cover all labels. Wildcard nodes keep their label. Edge
+    /// direction, edge labels, edge weights and anti-edges are preserved.
+    pub(crate) fn relabel(
+        &self,
+        permutation: &[usize],
+        label_map: &[usize],
+    ) -> Result<Graph, Error> {
+        debug_assert_eq!(permutation.len(), self.node_count());
+        debug_assert!(label_map.len() >= self.label_count());
+
+        let new_label = |label: usize| match self.compact_label(label) {
+            Some(label) => label_map[label],
+            None => ANY_LABEL,
+        };
+        let remap = |pairs: &mut dyn Iterator<Item = (usize, usize)>| {
+            pairs
+                .map(|(source, target)| (permutation[source], permutation[target]))
+                .collect::<Vec<_>>()
+        };
+
+        let mut labels = vec![0; self.node_count()];
+        for (node, &new_node) in permutation.iter().enumerate() {
+            labels[new_node] = new_label(self.label(node));
+        }
+
+        let old_edges = self.edges();
+        let edges = remap(&mut old_edges.iter().copied());
+
+        let load_config = LoadConfig {
+            neighbor_label_frequency: self.has_neighbor_label_frequencies(),
+            directed: self.is_directed(),
+            edge_labels: self.has_edge_labels(),
+            edge_weights: self.has_edge_weights(),
+            node_label_index: self.has_node_label_index(),
+            ..LoadConfig::default()
+        };
+
+        let mut graph = Graph::from_parts(&labels, &edges, load_config)?;
+
+        graph.set_multi_labels(
+            (0..self.node_count())
+                .filter(|&node| !self.is_wildcard(node))
+                .map(|node| {
+                    let labels = self.labels(node).iter().map(|&label| new_label(label));
+                    (permutation[node], labels.collect())
+                })
+                .collect(),
+        );
+
+        if let Some(directed) = &self.directed {
+            let directed_edges = remap(&mut (0..directed.node_count()).flat_map(|source| {
+                directed
+                    .out_neighbors(source)
+                    .iter()
+                    .map(move |&target| (source, target))
+            }));
+            graph.directed = Some(GraphBuilder::new().edges(directed_edges).build());
+            graph.undirected_edges =
+                remap(&mut self.undirected_edges.iter().copied()).into_boxed_slice();
+        }
+
+        let new_id = |node: usize| Some(permutation[node]);
+
+        if let Some(edge_labels) = &self.edge_labels {
+            graph.edge_labels =
+                Some(graph.copy_edge_values(self, edge_labels, new_id, NO_EDGE_LABEL));
+        }
+
+        if let Some(edge_weights) = &self.edge_weights {
+            graph.edge_weights =
+                Some(graph.copy_edge_values(self, edge_weights, new_id, NO_EDGE_WEIGHT));
+        }
+
+        graph.set_anti_edges(remap(&mut self.anti_edges.iter().copied()));
+
+        Ok(graph)
+    }
+
+    /// Creates a graph from the given node labels and undirected edges,
+    /// which need to refer to nodes within `labels`.
+    fn from_parts(
+        labels: &[usize],
+        edges: &[(usize, usize)],
+        load_config: LoadConfig,
+    ) -> Result<Graph, Error> {
+        let graph = GraphBuilder::new()
+            .csr_layout(CsrLayout::Sorted)
+            .edges(edges.iter().copied())
+            .build();
+
+        Graph::from_csr(graph, labels, load_config).reject_self_loops()
+    }
+
+    /// Creates a graph from the given CSR graph and the label of each node,
+    /// which may be [`ANY_LABEL`], and builds the label index.
+    ///
+    /// The labels are mapped to `0..n`, such that the label index is sized by
+    /// the number of distinct labels, see [`Graph::labels_present`].
+    fn from_csr(graph: UndirectedGraph, labels: &[usize], load_config: LoadConfig) -> Graph {
+        let mut label_values = labels
+            .iter()
+            .copied()
+            .filter(|&label| label != ANY_LABEL)
+            .collect::<Vec<_>>();
+        label_values.sort_unstable();
+        label_values.dedup();
+
+        let labels = labels
+            .iter()
+            .map(|&label| match label {
+                ANY_LABEL => ANY_LABEL,
+                label => label_values.partition_point(|&value| value < label),
+            })
+            .collect::<Box<[_]>>();
+
+        let max_degree = (0..graph.node_count())
+            .map(|node| graph.degree(node))
+            .max()
+            .unwrap_or_default();
+
+        let mut graph = Self {
+            graph,
+            node_count: labels.len(),
+            max_degree,
+            labels,
+            label_index: None,
+            label_frequencies: Box::new([]),
+            directed: None,
+            undirected_edges: Box::new([]),
+            anti_edges: Box::new([]),
+            edge_labels: None,
+            edge_weights: None,
+            neighbor_label_frequencies: None,
+            multi_labels: None,
+            label_values: label_values.into_boxed_slice(),
+            raw_csr: OnceLock::new(),
+        };
+        graph.index_labels(load_config.node_label_index);
+
+        if load_config.neighbor_label_frequency {
+            graph.compute_neighbor_label_frequencies();
+        }
+
+        graph
+    }
+
+    /// Returns [`Error::SelfLoop`] if a node is adjacent to itself.
+    ///
+    /// The CSR graph stores a self-loop twice in the adjacency list of its
+    /// node, which would count it twice in the degree, so graphs with
+    /// self-loops are rejected while loading instead.
+    fn reject_self_loops(self) -> Result<Self, Error> {
+        match (0..self.node_count()).find(|&node| self.exists(node, node)) {
+            Some(node) => Err(Error::SelfLoop { node }),
+            None => Ok(self),
+        }
+    }
+
+    /// Checks the invariants that the matching relies on, e.g. the binary
+    /// search in [`Graph::exists`], and returns [`Error::InvalidGraph`] for
+    /// the first violation.
+    ///
+    /// The CSR must not have neighbors for nodes beyond the graph and its
+    /// offsets need to cover exactly its targets, i.e. two per edge. Each adjacency list
+    /// needs to be sorted, within `0..node_count` and be mirrored by its
+    /// neighbors. Edge labels and weights need to be aligned with the
+    /// adjacency lists, the maximum degree needs to match the adjacency lists
+    /// and each node needs to be in the label index of exactly its labels.
+    pub fn validate(&self) -> Result<(), Error> {
+        let invalid = |reason: String| Err(Error::InvalidGraph { reason });
+        let node_count = self.node_count();
+
+        if let Some(node) =
+            (node_count..self.graph.node_count()).find(|&node| self.graph.degree(node) > 0)
+        {
+            return invalid(format!(
+                "node {} has neighbors, but the graph has {} nodes",
+                node, node_count
+            ));
+        }
+
+        let mut offset = 0;
+        let mut max_degree = 0;
+
+        for node in 0..node_count {
+            let neighbors = self.neighbors(node);
+            offset += neighbors.len();
+            max_degree = max_degree.max(neighbors.len());
+
+            if neighbors.windows(2).any(|pair| pair[0] > pair[1]) {
+                return invalid(format!("neighbors of node {} are not sorted", node));
+            }
+            if let Some(&neighbor) = neighbors.iter().find(|&&neighbor| neighbor >= node_count) {
+                return invalid(format!(
+                    "neighbor {} of node {} is out of range",
+                    neighbor, node
+                ));
+            }
+            if let Some(&neighbor) = neighbors
+                .iter()
+                .find(|&&neighbor| !self.exists(neighbor, node))
+            {
+                return invalid(format!("edge ({}, {}) has no reverse edge", node, neighbor));
+            }
+            if let Some(edge_labels) = &self.edge_labels {
+                if edge_labels[node].len() != neighbors.len() {
+                    return invalid(format!("edge labels of node {} are not aligned", node));
+                }
+            }
+            if let Some(edge_weights) = &self.edge_weights {
+                if edge_weights[node].len() != neighbors.len() {
+                    return invalid(format!("edge weights of node {} are not aligned", node));
+                }
+            }
+        }
+
+        if offset != 2 * self.edge_count() {
+            return invalid(format!(
+                "the adjacency lists have {} targets, but the graph has {} edges",
+                offset,
+                self.edge_count()
+            ));
+        }
+        if max_degree != self.max_degree {
+            return invalid(format!(
+                "the maximum degree is {}, but {} is stored",
+                max_degree, self.max_degree
+            ));
+        }
+
+        if !self.has_node_label_index() {
+            return Ok(());
+        }
+
+        for &label in self.labels_present() {
+            let nodes = self.nodes_by_label(label);
+
+            if nodes.windows(2).any(|pair| pair[0] >= pair[1]) {
+                return invalid(format!("nodes of label {} are not sorted", label));
+            }
+            if let Some(&node) = nodes
+                .iter()
+                .find(|&&node| node >= node_count || !self.has_label(node, label))
+            {
+                return invalid(format!("node {} is indexed by label {}", node, label));
+            }
+        }
+
+        for node in (0..node_count).filter(|&node| !self.is_wildcard(node)) {
+            if let Some(&label) = self
+                .labels(node)
+                .iter()
+                .find(|&&label| self.nodes_by_label(label).binary_search(&node).is_err())
+            {
+                return invalid(format!("node {} is not indexed by label {}", node, label));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns each undirected edge once, with `source < target`.
+    fn edges(&self) -> Vec<(usize, usize)> {
+        adjacency_edges(self.node_count(), |node| self.neighbors(node))
+    }
+
+    /// Renders the graph as an undirected Graphviz DOT document, e.g. to
+    /// visualize small query graphs via `dot -Tpng`.
+    ///
+    /// Each node `n{id}` is labeled like in GDL, i.e. `L{label}` for each of
+    /// its labels and `*` for wildcard nodes. Each edge is emitted once,
+    /// anti-edges are drawn dashed.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("graph {\n");
+
+        for node in 0..self.node_count() {
+            let label = if self.is_wildcard(node) {
+                String::from(ANY_LABEL_MARKER)
+            } else {
+                self.labels(node)
+                    .iter()
+                    .map(|label| format!("L{}", label))
+                    .collect::<Vec<_>>()
+                    .join(":")
+            };
+            dot.push_str(&format!("  n{} [label=\"{}\"];\n", node, label));
+        }
+
+        for (source, target) in self.edges() {
+            dot.push_str(&format!("  n{} -- n{};\n", source, target));
+        }
+
+        for (source, target) in self.anti_edges() {
+            dot.push_str(&format!("  n{} -- n{} [style=dashed];\n", source, target));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Writes the graph to the given path in a compact binary format that
+    /// can be loaded via [`Graph::read_binary`] without parsing text.
+    ///
+    /// The file starts with a magic number and a format version, followed
+    /// by the labels, the label index and the CSR of the graph. Edge
+    /// direction, edge labels, edge weights and neighbor label frequencies
+    /// are included if they have been loaded, the label sets of
+    /// multi-labeled graphs and anti-edges are always included.
+    pub fn write_binary(&self, path: &Path) -> Result<(), Error> {
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        binary::write(self, &mut writer)?;
+        std::io::Write::flush(&mut writer)?;
+
+        Ok(())
+    }
+
+    /// Reads a graph in the .graph format from the given reader, e.g. from
+    /// stdin, like [`load`] reads it from a file.
+    ///
+    /// The input is parsed while it is read. Gzip compressed input is
+    /// detected by its magic header.
+    pub fn from_reader<R: Read>(reader: R, load_config: LoadConfig) -> Result<Graph, Error> {
+        let reader = std::io::BufReader::new(reader);
+        read_input(reader, false, load_config, |_, _| {})
+    }
+
+    /// Reads a graph that has been written via [`Graph::write_binary`].
+    ///
+    /// The stored arrays are restored as they are, i.e. neither the CSR nor
+    /// the label index or any frequencies are rebuilt. Returns
+    /// [`Error::InvalidBinaryGraph`] if the file has not been written by
+    /// this version of the library or refers to nodes or labels that do not
+    /// exist. Use [`Graph::validate`] to fully check a file from an
+    /// untrusted source.
+    pub fn read_binary(path: &Path) -> Result<Graph, Error> {
+        let mut reader = std::io::BufReader::new(std::fs::File::open(path)?);
+        binary::read(&mut reader)
+    }
+
+    /// Checks if both graphs are isomorphic, i.e. if there is a bijection
+    /// between their nodes that maps edges onto edges.
+    ///
+    /// The other graph is matched as query graph against this graph. Since
+    /// both have the same number of nodes and edges, any embedding is an
+    /// isomorphism. If `label_sensitive` is `false`, node labels are ignored.
+    pub fn is_isomorphic_to(&self, other: &Graph, label_sensitive: bool) -> bool {
+        if self.node_count() != other.node_count() || self.edge_count() != other.edge_count() {
+            return false;
+        }
+        if self.node_count() == 0 {
+            return true;
+        }
+
+        // The RI order does not require the query graph to be connected.
+        let config = Config::new(Filter::Ldf, Order::Ri, Enumeration::Gql);
+
+        if label_sensitive {
+            crate::contains_match(self, other, config)
+        } else {
+            let unlabeled = |graph: &Graph| graph.clone_with_labels(&vec![0; graph.node_count()]);
+            match (unlabeled(self), unlabeled(other)) {
+                (Ok(data_graph), Ok(query_graph)) => {
+                    crate::contains_match(&data_graph, &query_graph, config)
+                }
+                _ => false,
+            }
+        }
+    }
+
+    /// Returns `true` if the graph has been loaded including neighbor label
+    /// frequencies, see [`LoadConfig::with_neighbor_label_frequency`].
+    pub fn has_neighbor_label_frequencies(&self) -> bool {
+        self.neighbor_label_frequencies.is_some()
+    }
+
     pub fn neighbor_label_frequency(&self, node: usize) -> &HashMap<usize, usize> {
         match &self.neighbor_label_frequencies {
             Some(nlfs) => &nlfs[node],
@@ -60,54 +1563,428 @@ impl Display for Graph {
     }
 }
 
+/// Two graphs are equal if they have the same nodes with the same labels and
+/// the same adjacency, i.e. node ids need to match. Use
+/// [`Graph::is_isomorphic_to`] to ignore node ids.
+impl PartialEq for Graph {
+    fn eq(&self, other: &Self) -> bool {
+        self.node_count() == other.node_count()
+            && self.edge_count() == other.edge_count()
+            && (0..self.node_count()).all(|node| {
+                self.labels(node) == other.labels(node)
+                    && self.neighbors(node) == other.neighbors(node)
+            })
+    }
+}
+
+/// Parses a .graph input including neighbor label frequencies. Extensions
+/// of the format, e.g. wildcard nodes, need to be requested via the load
+/// config of [`Graph::from_reader`].
 impl FromStr for Graph {
     type Err = Error;
 
     fn from_str(input: &str) -> Result<Self, Error> {
-        let reader = LineReader::new(input.as_bytes());
-        let dot_graph: DotGraph<usize, usize> = DotGraph::try_from(reader)?;
-        let csr_graph: CsrGraph = CsrGraph::from((dot_graph, CsrLayout::Sorted));
+        let load_config = LoadConfig::with_neighbor_label_frequency();
+        read_graph(input.as_bytes(), load_config, |_, _| {})
+    }
+}
 
-        Ok(Graph::from((
-            csr_graph,
-            LoadConfig::with_neighbor_label_frequency(),
-        )))
+fn undirected_edges(gdl_graph: &gdl::Graph) -> Vec<(usize, usize)> {
+    let node_id = |variable| gdl_graph.get_node(variable).expect("Node expected").id();
+
+    let mut edges = gdl_graph
+        .relationships()
+        .filter(|rel| {
+            matches!(
+                rel.property_value(UNDIRECTED_PROPERTY),
+                Some(gdl::CypherValue::Boolean(true))
+            )
+        })
+        .map(|rel| {
+            let source = node_id(rel.source());
+            let target = node_id(rel.target());
+            (usize::min(source, target), usize::max(source, target))
+        })
+        .collect::<Vec<_>>();
+
+    edges.sort_unstable();
+    edges
+}
+
+fn gdl_anti_edges(gdl_graph: &gdl::Graph) -> Vec<(usize, usize)> {
+    let node_id = |variable| gdl_graph.get_node(variable).expect("Node expected").id();
+
+    gdl_graph
+        .relationships()
+        .filter(|rel| is_anti_edge(rel))
+        .map(|rel| (node_id(rel.source()), node_id(rel.target())))
+        .collect()
+}
+
+fn is_anti_edge(rel: &gdl::Relationship) -> bool {
+    matches!(
+        rel.property_value(ANTI_EDGE_PROPERTY),
+        Some(gdl::CypherValue::Boolean(true))
+    )
+}
+
+/// Returns the label of each relationship except anti-edges, see
+/// [`Graph::from_gdl`].
+fn gdl_edge_labels(gdl_graph: &gdl::Graph) -> Result<Vec<(usize, usize, usize)>, Error> {
+    let node_id = |variable| gdl_graph.get_node(variable).expect("Node expected").id();
+
+    gdl_graph
+        .relationships()
+        .filter(|rel| !is_anti_edge(rel))
+        .map(|rel| {
+            let label = match rel.rel_type() {
+                Some(rel_type) => rel_type
+                    .get(1..)
+                    .and_then(|label| label.parse().ok())
+                    .ok_or_else(|| Error::InvalidGdlGraph {
+                        reason: format!("expected a numeric edge label, got '{}'", rel_type),
+                    })?,
+                None => NO_EDGE_LABEL,
+            };
+            Ok((node_id(rel.source()), node_id(rel.target()), label))
+        })
+        .collect()
+}
+
+/// Returns the weight of each relationship except anti-edges, see
+/// [`Graph::from_gdl`].
+fn gdl_edge_weights(gdl_graph: &gdl::Graph) -> Result<Vec<(usize, usize, f64)>, Error> {
+    let node_id = |variable| gdl_graph.get_node(variable).expect("Node expected").id();
+
+    gdl_graph
+        .relationships()
+        .filter(|rel| !is_anti_edge(rel))
+        .map(|rel| {
+            let weight = match rel.property_value(EDGE_WEIGHT_PROPERTY) {
+                Some(gdl::CypherValue::Float(weight)) => *weight,
+                Some(gdl::CypherValue::Integer(weight)) => *weight as f64,
+                Some(weight) => {
+                    return Err(Error::InvalidGdlGraph {
+                        reason: format!("expected a numeric edge weight, got {:?}", weight),
+                    })
+                }
+                None => NO_EDGE_WEIGHT,
+            };
+            Ok((node_id(rel.source()), node_id(rel.target()), weight))
+        })
+        .collect()
+}
+
+/// The sorted labels of all nodes and the edges of a GDL graph.
+type GdlLabelsAndEdges = (Vec<Vec<usize>>, Vec<(usize, usize)>);
+
+/// Returns the sorted labels of all nodes, which are empty for unlabeled
+/// nodes, and all relationships of the given GDL graph except anti-edges.
+fn gdl_labels_and_edges(gdl_graph: &gdl::Graph) -> Result<GdlLabelsAndEdges, Error> {
+    let node_id = |variable| gdl_graph.get_node(variable).expect("Node expected").id();
+
+    let mut labels = vec![Vec::new(); gdl_graph.node_count()];
+    for node in gdl_graph.nodes() {
+        labels[node.id()] = node
+            .labels()
+            .map(|label| {
+                label
+                    .get(1..)
+                    .and_then(|label| label.parse::<usize>().ok())
+                    .ok_or_else(|| Error::InvalidGdlGraph {
+                        reason: format!("expected a numeric node label, got '{}'", label),
+                    })
+            })
+            .collect::<Result<_, _>>()?;
+        labels[node.id()].sort_unstable();
     }
+
+    let edges = gdl_graph
+        .relationships()
+        .filter(|rel| !is_anti_edge(rel))
+        .map(|rel| (node_id(rel.source()), node_id(rel.target())))
+        .collect();
+
+    Ok((labels, edges))
 }
 
-impl From<(CsrGraph, LoadConfig)> for Graph {
-    fn from((graph, load_config): (CsrGraph, LoadConfig)) -> Self {
-        let neighbor_label_frequencies = if load_config.neighbor_label_frequency {
-            Some(neighbor_label_frequencies(&graph).into_boxed_slice())
-        } else {
-            None
-        };
+/// The node labels and edges of a .graph input, see [`parse_graph_file`].
+struct GraphFile {
+    /// The smallest label of each node or [`ANY_LABEL`] for wildcard nodes.
+    labels: Vec<usize>,
+    /// The sorted labels of nodes with multiple labels.
+    multi_labels: Vec<(usize, Vec<usize>)>,
+    edges: Vec<(usize, usize)>,
+    /// The label of each edge, if the load config requests edge labels.
+    edge_labels: Vec<usize>,
+    /// The weight of each edge, if the load config requests edge weights.
+    edge_weights: Vec<f64>,
+    anti_edges: Vec<(usize, usize)>,
+}
+
+/// Parses a .graph input in a single pass and checks it against its header
+/// `t node_count edge_count` and the declared node degrees.
+///
+/// Returns [`Error::InvalidGraphFile`] if a line is malformed, if the node
+/// lines are not ordered by node id, unless the load config accepts unsorted
+/// input, if a node is declared twice, if the number of node or edge lines
+/// differs from the header, if an edge refers to an undeclared node or if a
+/// node has more edges than its declared degree, e.g. due to a duplicate
+/// edge line. Parallel edges within the declared degrees are accepted, see
+/// [`Graph::edge_count_between`].
+///
+/// Node lines `v node label degree` may use the wildcard marker or a list of
+/// labels, edge lines `e source target` have a label and a weight column and
+/// anti-edge lines `!e source target` are accepted if the load config
+/// requests it, see [`LoadConfig::with_wildcards`],
+/// [`LoadConfig::multi_labeled`], [`LoadConfig::edge_labeled`],
+/// [`LoadConfig::weighted`] and [`LoadConfig::with_anti_edges`].
+fn parse_graph_file<R: BufRead>(
+    mut reader: R,
+    load_config: LoadConfig,
+) -> Result<GraphFile, Error> {
+    let invalid = |reason: String| Err(Error::InvalidGraphFile { reason });
+    let number = |token: Option<&str>| token.and_then(|token| token.parse::<usize>().ok());
+
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return invalid(String::from("missing header line"));
+    }
+    let mut tokens = line.split_whitespace();
+    let (node_count, edge_count) = match (
+        tokens.next(),
+        number(tokens.next()),
+        number(tokens.next()),
+        tokens.next(),
+    ) {
+        (Some("t"), Some(node_count), Some(edge_count), None) => (node_count, edge_count),
+        _ => return invalid(format!("invalid header line '{}'", line.trim_end())),
+    };
+
+    // Node lines are placed by their id, since unsorted inputs may declare
+    // them in any order.
+    let mut labels = vec![0; node_count];
+    let mut degrees = vec![0; node_count];
+    let mut declared = vec![false; node_count];
+    let mut node_lines = 0;
+    let mut multi_labels = Vec::new();
+    let mut edges = Vec::new();
+    let mut edge_labels = Vec::new();
+    let mut edge_weights = Vec::new();
+    let mut anti_edges = Vec::new();
+    let mut edge_degrees = vec![0_usize; node_count];
 
-        Self {
-            graph,
-            neighbor_label_frequencies,
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        let mut tokens = line.split_whitespace();
+
+        match tokens.next() {
+            Some("v") => {
+                let node = number(tokens.next());
+                let label = match tokens.next() {
+                    Some(ANY_LABEL_MARKER) if load_config.wildcards => Some(ANY_LABEL),
+                    Some(labels)
+                        if load_config.multi_labels && labels.contains(LABEL_SEPARATOR) =>
+                    {
+                        let labels = labels
+                            .split(LABEL_SEPARATOR)
+                            .map(|label| number(Some(label)))
+                            .collect::<Option<Vec<_>>>();
+                        match (node, labels) {
+                            (Some(node), Some(mut labels)) => {
+                                labels.sort_unstable();
+                                labels.dedup();
+                                let label = labels[0];
+                                multi_labels.push((node, labels));
+                                Some(label)
+                            }
+                            _ => None,
+                        }
+                    }
+                    label => number(label),
+                };
+                let (node, label, degree) =
+                    match (node, label, number(tokens.next()), tokens.next()) {
+                        (Some(node), Some(label), Some(degree), None) => (node, label, degree),
+                        _ => return invalid(format!("invalid node line '{}'", line.trim_end())),
+                    };
+                if node >= node_count {
+                    return invalid(format!(
+                        "node {} is out of range, the header declares {} nodes",
+                        node, node_count
+                    ));
+                }
+                if load_config.sorted && node != node_lines {
+                    return invalid(format!(
+                        "expected node {}, but found node {}",
+                        node_lines, node
+                    ));
+                }
+                if declared[node] {
+                    return invalid(format!("node {} is declared twice", node));
+                }
+                declared[node] = true;
+                node_lines += 1;
+                labels[node] = label;
+                degrees[node] = degree;
+            }
+            Some("e") => {
+                let (source, target) = match (number(tokens.next()), number(tokens.next())) {
+                    (Some(source), Some(target)) => (source, target),
+                    _ => return invalid(format!("invalid edge line '{}'", line.trim_end())),
+                };
+                let label = if load_config.edge_labels {
+                    match tokens.next() {
+                        Some(ANY_LABEL_MARKER) => Some(NO_EDGE_LABEL),
+                        label => number(label),
+                    }
+                } else {
+                    Some(NO_EDGE_LABEL)
+                };
+                let weight = if load_config.edge_weights {
+                    match tokens.next() {
+                        Some(ANY_LABEL_MARKER) => Some(NO_EDGE_WEIGHT),
+                        weight => weight.and_then(|weight| weight.parse::<f64>().ok()),
+                    }
+                } else {
+                    Some(NO_EDGE_WEIGHT)
+                };
+                let (label, weight) = match (label, weight, tokens.next()) {
+                    (Some(label), Some(weight), None) => (label, weight),
+                    _ => return invalid(format!("invalid edge line '{}'", line.trim_end())),
+                };
+                for &node in [source, target].iter() {
+                    if node >= node_count {
+                        return invalid(format!(
+                            "edge ({}, {}) refers to node {}, but the header declares {} nodes",
+                            source, target, node, node_count
+                        ));
+                    }
+                    edge_degrees[node] += 1;
+                }
+                edges.push((source, target));
+                if load_config.edge_labels {
+                    edge_labels.push(label);
+                }
+                if load_config.edge_weights {
+                    edge_weights.push(weight);
+                }
+            }
+            Some(ANTI_EDGE_MARKER) if load_config.anti_edges => {
+                let (source, target) =
+                    match (number(tokens.next()), number(tokens.next()), tokens.next()) {
+                        (Some(source), Some(target), None) => (source, target),
+                        _ => {
+                            return invalid(format!("invalid anti-edge line '{}'", line.trim_end()))
+                        }
+                    };
+                if let Some(&node) = [source, target].iter().find(|&&node| node >= node_count) {
+                    return invalid(format!(
+                        "anti-edge ({}, {}) refers to node {}, but the header declares {} nodes",
+                        source, target, node, node_count
+                    ));
+                }
+                anti_edges.push((source, target));
+            }
+            Some(_) => return invalid(format!("invalid line '{}'", line.trim_end())),
+            None => {}
         }
     }
+
+    if node_lines != node_count {
+        return invalid(format!(
+            "the header declares {} nodes, but there are {} node lines",
+            node_count, node_lines
+        ));
+    }
+    if edges.len() != edge_count {
+        return invalid(format!(
+            "the header declares {} edges, but there are {} edge lines",
+            edge_count,
+            edges.len()
+        ));
+    }
+    if let Some(node) = (0..node_count).find(|&node| edge_degrees[node] > degrees[node]) {
+        return invalid(format!(
+            "node {} declares degree {}, but has {} edges",
+            node, degrees[node], edge_degrees[node]
+        ));
+    }
+
+    Ok(GraphFile {
+        labels,
+        multi_labels,
+        edges,
+        edge_labels,
+        edge_weights,
+        anti_edges,
+    })
 }
 
-fn neighbor_label_frequencies(graph: &CsrGraph) -> Vec<HashMap<usize, usize>> {
-    let mut nlfs = Vec::with_capacity(graph.node_count());
+/// Returns each undirected edge once, with `source <= target`, given the
+/// adjacency lists of all nodes, which contain each self-loop twice.
+fn adjacency_edges<'a, F>(node_count: usize, neighbors: F) -> Vec<(usize, usize)>
+where
+    F: Fn(usize) -> &'a [usize],
+{
+    (0..node_count)
+        .flat_map(|source| {
+            let neighbors = neighbors(source);
+            let self_loops = neighbors.iter().filter(|&&target| target == source).count() / 2;
+            neighbors
+                .iter()
+                .filter(move |&&target| source < target)
+                .map(move |&target| (source, target))
+                .chain((0..self_loops).map(move |_| (source, source)))
+        })
+        .collect()
+}
 
-    for node in 0..graph.node_count() {
-        let mut nlf = HashMap::<usize, usize>::new();
+/// Parses lines of two delimited integers, e.g. edges or node labels.
+fn parse_pairs(input: &str, delimiter: char) -> Result<Vec<(usize, usize)>, Error> {
+    let mut pairs = Vec::new();
 
-        for &target in graph.neighbors(node) {
-            let target_label = graph.label(target);
-            let count = nlf.entry(target_label).or_insert(0);
-            *count += 1;
+    for (idx, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
         }
 
-        nlfs.push(nlf);
+        let mut values = line
+            .split(delimiter)
+            .map(|value| value.trim().parse::<usize>());
+        match (values.next(), values.next(), values.next()) {
+            (Some(Ok(first)), Some(Ok(second)), None) => pairs.push((first, second)),
+            // Tolerate a header line, e.g. `source,target`.
+            (Some(Err(_)), _, _) if idx == 0 => {}
+            _ => {
+                return Err(invalid_data(format!(
+                    "expected two values separated by {:?} in line {}: {}",
+                    delimiter,
+                    idx + 1,
+                    line
+                )))
+            }
+        }
     }
 
-    nlfs
+    Ok(pairs)
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::from(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        message,
+    ))
 }
 
+/// An undirected graph parsed from GDL, see [`Graph::from_gdl`].
+///
+/// The direction of relationships is dropped, [`LoadConfig::directed`]
+/// keeps it for matching directed queries.
 pub struct GdlGraph(Graph);
 
 impl Deref for GdlGraph {
@@ -122,51 +1999,321 @@ impl FromStr for GdlGraph {
     type Err = Error;
 
     fn from_str(gdl: &str) -> Result<Self, Error> {
-        let csr_graph: CsrGraph = GraphBuilder::new().gdl_str::<usize, _>(gdl).build()?;
-        let graph = Graph::from((csr_graph, LoadConfig::with_neighbor_label_frequency()));
+        let graph = Graph::from_gdl(gdl, LoadConfig::with_neighbor_label_frequency())?;
         Ok(GdlGraph(graph))
     }
 }
 
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy)]
 pub struct LoadConfig {
     neighbor_label_frequency: bool,
+    directed: bool,
+    edge_labels: bool,
+    edge_weights: bool,
+    wildcards: bool,
+    multi_labels: bool,
+    anti_edges: bool,
+    node_label_index: bool,
+    sorted: bool,
+}
+
+impl Default for LoadConfig {
+    fn default() -> Self {
+        Self {
+            neighbor_label_frequency: false,
+            directed: false,
+            edge_labels: false,
+            edge_weights: false,
+            wildcards: false,
+            multi_labels: false,
+            anti_edges: false,
+            node_label_index: true,
+            sorted: true,
+        }
+    }
 }
 
 impl LoadConfig {
     pub fn with_neighbor_label_frequency() -> Self {
         Self {
             neighbor_label_frequency: true,
+            ..Self::default()
+        }
+    }
+
+    /// Additionally loads the edge direction of the graph.
+    pub fn directed(self) -> Self {
+        Self {
+            directed: true,
+            ..self
+        }
+    }
+
+    /// Additionally loads edge labels, i.e. edge lines in .graph files
+    /// contain a third value `e source target label`, which is `*` for
+    /// unlabeled edges.
+    pub fn edge_labeled(self) -> Self {
+        Self {
+            edge_labels: true,
+            ..self
+        }
+    }
+
+    /// Additionally loads edge weights, i.e. edge lines in .graph files end
+    /// with a weight `e source target weight`, or `e source target label
+    /// weight` if edge labels are loaded as well, which is `*` for
+    /// unweighted edges. In GDL, the weight is a relationship property, e.g.
+    /// `(n0)-[{weight: 0.5}]->(n1)`.
+    pub fn weighted(self) -> Self {
+        Self {
+            edge_weights: true,
+            ..self
+        }
+    }
+
+    /// Additionally accepts wildcard nodes in .graph files, i.e. node lines
+    /// may use `*` as label, see [`ANY_LABEL`].
+    pub fn with_wildcards(self) -> Self {
+        Self {
+            wildcards: true,
+            ..self
+        }
+    }
+
+    /// Additionally accepts multi-labeled nodes in .graph files, i.e. node
+    /// lines may contain a comma-separated list of labels `v node l1,l2 degree`.
+    pub fn multi_labeled(self) -> Self {
+        Self {
+            multi_labels: true,
+            ..self
+        }
+    }
+
+    /// Additionally accepts anti-edges in .graph files, i.e. lines
+    /// `!e source target`, see [`Graph::anti_edges`].
+    pub fn with_anti_edges(self) -> Self {
+        Self {
+            anti_edges: true,
+            ..self
+        }
+    }
+
+    /// Skips building the label index for [`Graph::nodes_by_label`], e.g. for
+    /// graphs that are only used for [`crate::graph_ops`]. The filters fall
+    /// back to scanning all nodes, see [`Graph::has_node_label_index`].
+    pub fn without_node_label_index(self) -> Self {
+        Self {
+            node_label_index: false,
+            ..self
+        }
+    }
+
+    /// Accepts .graph files whose node lines are not sorted by node id or
+    /// are interleaved with edge lines, as produced by some tools of the C++
+    /// reference implementation. Each node is placed by its id while parsing.
+    pub fn unsorted(self) -> Self {
+        Self {
+            sorted: false,
+            ..self
         }
     }
 }
 
 impl From<Config> for LoadConfig {
     fn from(config: Config) -> Self {
-        let neighbor_label_frequency = config.filter == Filter::Nlf;
+        let neighbor_label_frequency = config.filter == Filter::Nlf || config.order == Order::Nlf;
 
         LoadConfig {
             neighbor_label_frequency,
+            ..LoadConfig::default()
         }
     }
 }
 
+/// Loads a graph from a .graph file.
+///
+/// Gzip compressed files are decompressed on the fly. They are detected by
+/// their `.gz` extension or their magic header.
 pub fn load(path: &Path, load_config: LoadConfig) -> Result<Graph, Error> {
-    println!("Reading from: {:?}", path);
-    let start = Instant::now();
-    println!("Preparing input: {:?}", start.elapsed());
+    load_with_stages(path, load_config, |_, _| {})
+}
+
+/// A stage of loading a graph, see [`load_with_stages`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadStage {
+    /// Reading and parsing the input.
+    Parse,
+    /// Building the graph from the parsed input.
+    Build,
+    /// Computing the neighbor label frequencies, if the load config requests
+    /// them, see [`LoadConfig::with_neighbor_label_frequency`].
+    NeighborLabelFrequencies,
+    /// Building the directed graph, if the load config requests it.
+    BuildDirected,
+}
+
+impl Display for LoadStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stage = match self {
+            LoadStage::Parse => "Parsing graph",
+            LoadStage::Build => "Building graph",
+            LoadStage::NeighborLabelFrequencies => "Computing neighbor label frequencies",
+            LoadStage::BuildDirected => "Building directed graph",
+        };
+        write!(f, "{}", stage)
+    }
+}
+
+/// The durations of the stages of [`load_with_metrics`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct LoadMetrics {
+    /// The time to read and parse the input.
+    pub parse: Duration,
+    /// The time to build the graph, including the directed graph if the load
+    /// config requests it.
+    pub build: Duration,
+    /// The time to compute the neighbor label frequencies, if the load config
+    /// requests them, see [`LoadConfig::with_neighbor_label_frequency`].
+    pub frequencies: Option<Duration>,
+}
+
+/// Loads a graph from a .graph file like [`load`] and returns the durations
+/// of the loading stages, e.g. to report them as metrics.
+pub fn load_with_metrics(
+    path: &Path,
+    load_config: LoadConfig,
+) -> Result<(Graph, LoadMetrics), Error> {
+    let mut metrics = LoadMetrics::default();
+    let graph = load_with_stages(path, load_config, |stage, elapsed| match stage {
+        LoadStage::Parse => metrics.parse += elapsed,
+        LoadStage::Build | LoadStage::BuildDirected => metrics.build += elapsed,
+        LoadStage::NeighborLabelFrequencies => metrics.frequencies = Some(elapsed),
+    })?;
+
+    Ok((graph, metrics))
+}
 
+/// Loads a graph from a .graph file like [`load`] and reports the duration
+/// of each loading stage to `on_stage`, e.g. to print timings.
+///
+/// The stages are reported in the order of [`LoadStage`], stages that the
+/// load config does not request are skipped.
+pub fn load_with_stages<S>(
+    path: &Path,
+    load_config: LoadConfig,
+    on_stage: S,
+) -> Result<Graph, Error>
+where
+    S: FnMut(LoadStage, Duration),
+{
+    let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    read_input(reader, has_gzip_extension(path), load_config, on_stage)
+}
+
+/// Loads a graph from a GraphML file, see [`Graph::from_graphml`].
+pub fn load_graphml(path: &Path, label_key: &str, load_config: LoadConfig) -> Result<Graph, Error> {
+    let input = std::fs::read_to_string(path)?;
+    Graph::from_graphml(&input, label_key, load_config)
+}
+
+/// Loads a graph from an edge list file and a label file, see
+/// [`Graph::from_edge_list`].
+pub fn load_edge_list(
+    edges_path: &Path,
+    labels_path: &Path,
+    delimiter: char,
+    load_config: LoadConfig,
+) -> Result<Graph, Error> {
+    let edges = std::fs::read_to_string(edges_path)?;
+    let labels = std::fs::read_to_string(labels_path)?;
+    Graph::from_edge_list(&edges, &labels, delimiter, load_config)
+}
+
+fn has_gzip_extension(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "gz")
+}
+
+/// Parses a .graph input like [`read_graph`] and decompresses it on the fly
+/// if it is gzip compressed, either as indicated by the caller or by its
+/// magic header.
+fn read_input<R, S>(
+    mut reader: R,
+    gzip: bool,
+    load_config: LoadConfig,
+    on_stage: S,
+) -> Result<Graph, Error>
+where
+    R: BufRead,
+    S: FnMut(LoadStage, Duration),
+{
+    if gzip || reader.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        let reader = std::io::BufReader::new(GzDecoder::new(reader));
+        read_graph(reader, load_config, on_stage)
+    } else {
+        read_graph(reader, load_config, on_stage)
+    }
+}
+
+/// Parses a .graph input, builds the graph and reports the duration of
+/// each stage to `on_stage`, see [`load_with_stages`].
+fn read_graph<R, S>(reader: R, load_config: LoadConfig, mut on_stage: S) -> Result<Graph, Error>
+where
+    R: BufRead,
+    S: FnMut(LoadStage, Duration),
+{
     let start = Instant::now();
-    let csr_graph: CsrGraph = GraphBuilder::new()
-        .csr_layout(CsrLayout::Sorted)
-        .file_format(graph::input::dotgraph::DotGraphInput::default())
-        .path(path)
-        .build()?;
-    println!("Parsing graph: {:?}", start.elapsed());
+    let GraphFile {
+        labels,
+        multi_labels,
+        edges,
+        edge_labels,
+        edge_weights,
+        anti_edges,
+    } = parse_graph_file(reader, load_config)?;
+    on_stage(LoadStage::Parse, start.elapsed());
 
     let start = Instant::now();
-    let graph = Graph::from((csr_graph, load_config));
-    println!("Building graph: {:?}", start.elapsed());
+    // The neighbor label frequencies are computed in a separate stage.
+    let build_config = LoadConfig {
+        neighbor_label_frequency: false,
+        ..load_config
+    };
+    let mut graph = Graph::from_parts(&labels, &edges, build_config)?;
+    if load_config.edge_labels {
+        let edge_labels = edges
+            .iter()
+            .zip(edge_labels)
+            .map(|(&(source, target), label)| (source, target, label))
+            .collect::<Vec<_>>();
+        graph.set_edge_labels(&edge_labels);
+    }
+    if load_config.edge_weights {
+        let edge_weights = edges
+            .iter()
+            .zip(edge_weights)
+            .map(|(&(source, target), weight)| (source, target, weight))
+            .collect::<Vec<_>>();
+        graph.set_edge_weights(&edge_weights);
+    }
+    graph.set_multi_labels(multi_labels);
+    graph.set_anti_edges(anti_edges);
+    on_stage(LoadStage::Build, start.elapsed());
+
+    if load_config.neighbor_label_frequency {
+        let start = Instant::now();
+        graph.compute_neighbor_label_frequencies();
+        on_stage(LoadStage::NeighborLabelFrequencies, start.elapsed());
+    }
+
+    if load_config.directed {
+        let start = Instant::now();
+        let directed: DirectedGraph = GraphBuilder::new()
+            .csr_layout(CsrLayout::Sorted)
+            .edges(edges)
+            .build();
+        graph.directed = Some(directed);
+        on_stage(LoadStage::BuildDirected, start.elapsed());
+    }
 
     Ok(graph)
 }
@@ -204,6 +2351,12 @@ mod tests {
         assert_eq!(graph.max_label(), 2);
         assert_eq!(graph.max_degree(), 3);
         assert_eq!(graph.max_label_frequency(), 2);
+        assert_eq!(graph.label_frequency(1), 2);
+        assert_eq!(graph.label_frequency(99), 0);
+        assert_eq!(
+            graph.labels_iter().collect::<Vec<_>>(),
+            vec![(0, 1), (1, 2), (2, 2)]
+        );
 
         assert_eq!(graph.label(0), 0);
         assert_eq!(graph.label(1), 1);
@@ -223,6 +2376,12 @@ mod tests {
         assert_eq!(graph.neighbors(3), &[1, 4]);
         assert_eq!(graph.neighbors(4), &[2, 3]);
 
+        assert_eq!(graph.neighbors_by_label(1, 2).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(graph.neighbors_by_label(1, 1).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(graph.neighbors_by_label(0, 99).count(), 0);
+
+        assert!(graph.validate().is_ok());
+
         assert!(graph.exists(0, 1));
         assert!(graph.exists(0, 2));
         assert!(!graph.exists(0, 3));
@@ -232,6 +2391,440 @@ mod tests {
         assert_eq!(graph.nodes_by_label(0), &[0]);
         assert_eq!(graph.nodes_by_label(1), &[1, 3]);
         assert_eq!(graph.nodes_by_label(2), &[2, 4]);
+        assert!(graph.nodes_by_label(graph.max_label() + 5).is_empty());
+
+        let histogram = graph.degree_histogram();
+        assert_eq!(histogram, vec![0, 0, 3, 2]);
+        assert_eq!(histogram.iter().sum::<usize>(), graph.node_count());
+
+        assert_eq!(
+            graph.average_degree(),
+            (2 * graph.edge_count()) as f64 / graph.node_count() as f64
+        );
+        assert_eq!(graph.density(), 0.6);
+
+        let dot = graph.to_dot();
+        assert!(dot.starts_with("graph {\n"));
+        assert!(dot.contains("  n0 [label=\"L0\"];\n"));
+        assert!(dot.contains("  n3 [label=\"L1\"];\n"));
+        assert!(dot.contains("  n0 -- n1;\n"));
+        assert!(dot.contains("  n3 -- n4;\n"));
+        assert!(!dot.contains("n1 -- n0"));
+        assert_eq!(dot.matches(" -- ").count(), graph.edge_count());
+    }
+
+    #[test]
+    fn read_gzip() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+
+        let input = "
+        |t 5 6
+        |v 0 0 2
+        |v 1 1 3
+        |v 2 2 3
+        |v 3 1 2
+        |v 4 2 2
+        |e 0 1
+        |e 0 2
+        |e 1 2
+        |e 1 3
+        |e 2 4
+        |e 3 4
+        |"
+        .trim_margin()
+        .unwrap();
+        let expected = input.parse::<Graph>().unwrap();
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(input.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        // Detected by extension and by magic header respectively.
+        let dir = std::env::temp_dir();
+        let paths = [
+            dir.join(format!("suma-gzip-{}.graph.gz", std::process::id())),
+            dir.join(format!("suma-gzip-{}.graph", std::process::id())),
+        ];
+
+        for path in paths.iter() {
+            std::fs::write(path, &compressed).unwrap();
+            let graph = load(path, LoadConfig::default());
+            std::fs::remove_file(path).unwrap();
+
+            let graph = graph.unwrap();
+
+            assert_eq!(graph.node_count(), expected.node_count());
+            assert_eq!(graph.edge_count(), expected.edge_count());
+            for node in 0..expected.node_count() {
+                assert_eq!(graph.label(node), expected.label(node));
+                assert_eq!(graph.neighbors(node), expected.neighbors(node));
+            }
+        }
+    }
+
+    #[test]
+    fn read_from_reader() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::{Cursor, Write};
+
+        let input = "
+        |t 5 6
+        |v 0 0 2
+        |v 1 1 3
+        |v 2 2 3
+        |v 3 1 2
+        |v 4 2 2
+        |e 0 1
+        |e 0 2
+        |e 1 2
+        |e 1 3
+        |e 2 4
+        |e 3 4
+        |"
+        .trim_margin()
+        .unwrap();
+        let expected = input.parse::<Graph>().unwrap();
+
+        let graph = Graph::from_reader(Cursor::new(input.as_bytes()), LoadConfig::default());
+
+        assert!(graph.unwrap() == expected);
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(input.as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let graph = Graph::from_reader(Cursor::new(compressed), LoadConfig::default());
+
+        assert!(graph.unwrap() == expected);
+
+        assert!(Graph::from_reader(Cursor::new(vec![0xff, 0xfe]), LoadConfig::default()).is_err());
+    }
+
+    #[test]
+    fn read_unsorted() {
+        let sorted = "
+        |t 5 6
+        |v 0 0 2
+        |v 1 1 3
+        |v 2 2 3
+        |v 3 1 2
+        |v 4 2 2
+        |e 0 1
+        |e 0 2
+        |e 1 2
+        |e 1 3
+        |e 2 4
+        |e 3 4
+        |"
+        .trim_margin()
+        .unwrap();
+        let expected = sorted.parse::<Graph>().unwrap();
+
+        let shuffled = "
+        |t 5 6
+        |v 3 1 2
+        |e 2 4
+        |v 0 0 2
+        |e 1 3
+        |e 0 2
+        |v 4 2 2
+        |v 2 2 3
+        |e 3 4
+        |e 0 1
+        |v 1 1 3
+        |e 1 2
+        |"
+        .trim_margin()
+        .unwrap();
+
+        let path = std::env::temp_dir().join(format!("suma-unsorted-{}.graph", std::process::id()));
+        std::fs::write(&path, &shuffled).unwrap();
+        let loaded = load(&path, LoadConfig::default().unsorted());
+        std::fs::remove_file(&path).unwrap();
+
+        let read = Graph::from_reader(shuffled.as_bytes(), LoadConfig::default().unsorted());
+
+        for graph in [loaded.unwrap(), read.unwrap()].iter() {
+            assert!(graph.validate().is_ok());
+            assert_eq!(graph.node_count(), expected.node_count());
+            assert_eq!(graph.edge_count(), expected.edge_count());
+            for node in 0..expected.node_count() {
+                assert_eq!(graph.label(node), expected.label(node));
+                assert_eq!(graph.neighbors(node), expected.neighbors(node));
+            }
+        }
+
+        let invalid = |input: String| match Graph::from_reader(
+            input.as_bytes(),
+            LoadConfig::default().unsorted(),
+        ) {
+            Err(Error::InvalidGraphFile { reason }) => Some(reason),
+            _ => None,
+        };
+
+        assert_eq!(
+            invalid(shuffled.replace("v 4 2 2", "v x 2 2")).as_deref(),
+            Some("invalid node line 'v x 2 2'")
+        );
+        assert_eq!(
+            invalid(shuffled.replace("v 4 2 2", "v 0 2 2")).as_deref(),
+            Some("node 0 is declared twice")
+        );
+        assert_eq!(
+            invalid(shuffled.replace("v 4 2 2", "v 5 2 2")).as_deref(),
+            Some("node 5 is out of range, the header declares 5 nodes")
+        );
+        assert!(matches!(
+            shuffled.parse::<Graph>(),
+            Err(Error::InvalidGraphFile { reason }) if reason == "expected node 0, but found node 3"
+        ));
+    }
+
+    #[test]
+    fn degenerate_graphs() {
+        let empty = "t 0 0\n".parse::<Graph>().unwrap();
+
+        assert_eq!(empty.node_count(), 0);
+        assert_eq!(empty.edge_count(), 0);
+        assert_eq!(empty.label_count(), 0);
+        assert_eq!(empty.max_label(), 0);
+        assert_eq!(empty.max_label_frequency(), 0);
+        assert_eq!(empty.max_degree(), 0);
+        assert_eq!(empty.labels_iter().count(), 0);
+        assert!(empty.nodes_by_label(0).is_empty());
+        assert_eq!(empty.label_frequency(0), 0);
+        assert!(empty.validate().is_ok());
+
+        let path = std::env::temp_dir().join(format!("suma-empty-{}.graph", std::process::id()));
+        std::fs::write(&path, "t 0 0\n").unwrap();
+        let loaded = load(&path, LoadConfig::default());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(loaded.unwrap() == empty);
+        assert!(*builder::GraphBuilderMut::new().finalize().unwrap() == empty);
+
+        let isolated = "t 1 0\nv 0 3 0\n".parse::<Graph>().unwrap();
+
+        assert_eq!(isolated.node_count(), 1);
+        assert_eq!(isolated.edge_count(), 0);
+        assert_eq!(isolated.label_count(), 1);
+        assert_eq!(isolated.max_label(), 3);
+        assert_eq!(isolated.max_label_frequency(), 1);
+        assert_eq!(isolated.max_degree(), 0);
+        assert_eq!(isolated.label_frequency(0), 0);
+        assert_eq!(isolated.nodes_by_label(3), &[0]);
+        assert!(isolated.neighbors(0).is_empty());
+        assert!(isolated.validate().is_ok());
+
+        let unique_labels = "t 3 2\nv 0 0 1\nv 1 1 2\nv 2 2 1\ne 0 1\ne 1 2\n"
+            .parse::<Graph>()
+            .unwrap();
+
+        assert_eq!(unique_labels.label_count(), 3);
+        assert_eq!(unique_labels.max_label_frequency(), 1);
+        assert_eq!(
+            unique_labels.labels_iter().collect::<Vec<_>>(),
+            vec![(0, 1), (1, 1), (2, 1)]
+        );
+
+        // Wildcard nodes do not count towards label `0`.
+        let wildcards = Graph::from_reader(
+            "t 3 2\nv 0 * 1\nv 1 * 2\nv 2 1 1\ne 0 1\ne 1 2\n".as_bytes(),
+            LoadConfig::default().with_wildcards(),
+        )
+        .unwrap();
+
+        assert_eq!(wildcards.label_frequency(0), 0);
+        assert_eq!(wildcards.max_label_frequency(), 1);
+        assert_eq!(wildcards.labels_present(), &[1]);
+        assert!(empty.labels_present().is_empty());
+    }
+
+    #[test]
+    fn compact_labels() {
+        let input = "
+        |t 4 3
+        |v 0 1000000 1
+        |v 1 0 2
+        |v 2 1000000 2
+        |v 3 42 1
+        |e 0 1
+        |e 1 2
+        |e 2 3
+        |"
+        .trim_margin()
+        .unwrap();
+
+        let load_config = LoadConfig::with_neighbor_label_frequency();
+        let graph = Graph::from_reader(input.as_bytes(), load_config).unwrap();
+
+        // The labels are indexed by their rank among the distinct labels.
+        assert_eq!(graph.label_frequencies.len(), 3);
+
+        assert!(graph.validate().is_ok());
+        assert_eq!(graph.label_count(), 3);
+        assert_eq!(graph.max_label(), 1_000_000);
+        assert_eq!(
+            graph.labels_iter().collect::<Vec<_>>(),
+            vec![(0, 1), (42, 1), (1_000_000, 2)]
+        );
+        assert_eq!(graph.max_label_frequency(), 2);
+        assert_eq!(graph.label(0), 1_000_000);
+        assert_eq!(graph.labels(3), &[42]);
+        assert_eq!(graph.nodes_by_label(1_000_000), &[0, 2]);
+        assert_eq!(graph.nodes_by_label(42), &[3]);
+        assert_eq!(graph.labels_present(), &[0, 42, 1_000_000]);
+        assert!(graph.nodes_by_label(1).is_empty());
+        assert!(graph.nodes_by_label(2_000_000).is_empty());
+        assert_eq!(graph.label_frequency(0), 1);
+        assert_eq!(
+            graph.neighbor_label_frequency(2),
+            &[(0, 1), (42, 1)].iter().copied().collect::<HashMap<_, _>>()
+        );
+
+        // Queries don't need to be compacted.
+        let query_graph = "(n0:L1000000),(n1:L0),(n0)-->(n1)"
+            .parse::<GdlGraph>()
+            .unwrap();
+        assert_eq!(crate::find(&graph, &query_graph, Config::default()), 2);
+        assert_eq!(crate::find(&graph, &query_graph, Filter::Nlf), 2);
+
+        // Every constructor compacts the labels.
+        let built = Graph::from_edges(&[7, 3, 7], &[(0, 1), (1, 2)]).unwrap();
+        assert_eq!(built.label_frequencies.len(), 2);
+        assert_eq!(built.nodes_by_label(7), &[0, 2]);
+
+        let multi_labeled =
+            Graph::from_gdl("(n0:L5000:L7),(n1:L7),(n0)-->(n1)", LoadConfig::default()).unwrap();
+        assert_eq!(multi_labeled.label_frequencies.len(), 2);
+        assert_eq!(multi_labeled.nodes_by_label(7), &[0, 1]);
+        assert_eq!(multi_labeled.nodes_by_label(5000), &[0]);
+
+        let (relabeled, permutation) = crate::graph_ops::relabel_by_frequency(&graph);
+        assert_eq!(relabeled.labels_present(), &[0, 1, 2]);
+        assert_eq!(relabeled.label(permutation[0]), 0);
+        assert_eq!(relabeled.label(permutation[1]), 1);
+        assert_eq!(relabeled.label(permutation[3]), 2);
+    }
+
+    #[test]
+    fn raw_csr() {
+        // Node 4 is an isolated node beyond the largest node in an edge.
+        let graph = Graph::from_reader(
+            "t 5 4\nv 0 0 2\nv 1 * 2\nv 2 2 3\nv 3 1 1\nv 4 3 0\ne 0 1\ne 0 2\ne 1 2\ne 2 3\n"
+                .as_bytes(),
+            LoadConfig::default().with_wildcards(),
+        )
+        .unwrap();
+
+        let offsets = graph.csr_offsets();
+        let targets = graph.csr_targets();
+
+        assert_eq!(offsets, &[0, 2, 4, 7, 8, 8]);
+        assert_eq!(targets.len(), 2 * graph.edge_count());
+        for node in 0..graph.node_count() {
+            assert_eq!(
+                &targets[offsets[node]..offsets[node + 1]],
+                graph.neighbors(node)
+            );
+        }
+        assert_eq!(graph.node_labels(), &[0, ANY_LABEL, 2, 1, 3]);
+
+        // The arrays are materialized once.
+        assert!(std::ptr::eq(graph.csr_targets(), targets));
+    }
+
+    #[test]
+    fn without_node_label_index() {
+        let input = "
+        |t 5 6
+        |v 0 0 2
+        |v 1 1 3
+        |v 2 2 3
+        |v 3 1 2
+        |v 4 2 2
+        |e 0 1
+        |e 0 2
+        |e 1 2
+        |e 1 3
+        |e 2 4
+        |e 3 4
+        |"
+        .trim_margin()
+        .unwrap();
+        let indexed = input.parse::<Graph>().unwrap();
+        let graph = Graph::from_reader(
+            input.as_bytes(),
+            LoadConfig::default().without_node_label_index(),
+        )
+        .unwrap();
+
+        assert!(indexed.has_node_label_index());
+        assert!(!graph.has_node_label_index());
+        assert!(graph.label_index.is_none());
+        assert_eq!(graph.max_label_frequency(), 2);
+        assert!(graph.nodes_by_label(1).is_empty());
+        assert_eq!(graph.label_frequency(1), 2);
+        assert!(graph.validate().is_ok());
+
+        for node in 0..graph.node_count() {
+            assert_eq!(graph.degree(node), indexed.degree(node));
+        }
+        assert_eq!(
+            crate::graph_ops::coreness(&graph),
+            crate::graph_ops::coreness(&indexed)
+        );
+
+        // The filters scan all nodes instead.
+        let query_graph = "(n0:L1),(n1:L2),(n0)-->(n1)".parse::<GdlGraph>().unwrap();
+        let candidates = crate::filter::ldf_filter(&graph, &query_graph).unwrap();
+
+        assert_eq!(candidates.candidates(0), &[1, 3]);
+        assert_eq!(candidates.candidates(1), &[2, 4]);
+        assert_eq!(
+            crate::find(&graph, &query_graph, Config::default()),
+            crate::find(&indexed, &query_graph, Config::default())
+        );
+
+        let subgraph = graph.subgraph(&[0, 1, 2]).unwrap();
+        assert!(!subgraph.has_node_label_index());
+    }
+
+    #[test]
+    fn load_stages() {
+        let input = "
+        |t 3 2
+        |v 0 0 1
+        |v 1 1 2
+        |v 2 1 1
+        |e 0 1
+        |e 1 2
+        |"
+        .trim_margin()
+        .unwrap();
+
+        let path = std::env::temp_dir().join(format!("suma-stages-{}.graph", std::process::id()));
+        std::fs::write(&path, input).unwrap();
+
+        let mut stages = Vec::new();
+        let graph = load_with_stages(&path, LoadConfig::default(), |stage, _| stages.push(stage));
+        let mut directed_stages = Vec::new();
+        let directed = load_with_stages(
+            &path,
+            LoadConfig::default().with_wildcards().directed(),
+            |stage, _| directed_stages.push(stage),
+        );
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(graph.unwrap().edge_count(), 2);
+        assert_eq!(stages, vec![LoadStage::Parse, LoadStage::Build]);
+
+        assert!(directed.unwrap().is_directed());
+        assert_eq!(
+            directed_stages,
+            vec![LoadStage::Parse, LoadStage::Build, LoadStage::BuildDirected]
+        );
     }
 
     #[test]
@@ -319,4 +2912,790 @@ mod tests {
         assert_eq!(graph.neighbor_label_frequency(4).get(&1), Some(&1));
         assert_eq!(graph.neighbor_label_frequency(4).get(&4), None);
     }
+
+    #[test]
+    fn read_wildcards() {
+        let input = "
+        |t 3 2
+        |v 0 0 1
+        |v 1 * 2
+        |v 2 1 1
+        |e 0 1
+        |e 1 2
+        |"
+        .trim_margin()
+        .unwrap();
+        let load_config = LoadConfig::with_neighbor_label_frequency().with_wildcards();
+        let graph = Graph::from_reader(input.as_bytes(), load_config).unwrap();
+
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.label(0), 0);
+        assert_eq!(graph.label(1), ANY_LABEL);
+        assert_eq!(graph.label(2), 1);
+        assert!(graph.is_wildcard(1));
+        assert!(!graph.is_wildcard(2));
+
+        assert_eq!(graph.neighbor_label_frequency(0).get(&0), None);
+        assert_eq!(graph.neighbor_label_frequency(1).get(&0), Some(&1));
+        assert_eq!(graph.neighbor_label_frequency(1).get(&1), Some(&1));
+
+        let gdl = Graph::from_gdl(
+            "(n0:L0),(n1),(n2:L1),(n0)-->(n1),(n1)-->(n2)",
+            LoadConfig::default(),
+        )
+        .unwrap();
+        let edges = Graph::from_edges(&[0, ANY_LABEL, 1], &[(0, 1), (1, 2)]).unwrap();
+
+        for other in [gdl, edges] {
+            for node in 0..graph.node_count() {
+                assert_eq!(other.label(node), graph.label(node));
+                assert_eq!(other.neighbors(node), graph.neighbors(node));
+            }
+        }
+
+        // Wildcards need to be requested by the load config.
+        assert!(matches!(
+            input.parse::<Graph>(),
+            Err(Error::InvalidGraphFile { reason }) if reason == "invalid node line 'v 1 * 2'"
+        ));
+        assert!(matches!(
+            Graph::from_gdl("(n0:LX),(n1:L1),(n0)-->(n1)", LoadConfig::default()),
+            Err(Error::InvalidGdlGraph { .. })
+        ));
+    }
+
+    #[test]
+    fn read_multi_labels() {
+        let input = "
+        |t 3 2
+        |v 0 0 1
+        |v 1 3,1 2
+        |v 2 1 1
+        |e 0 1
+        |e 1 2
+        |"
+        .trim_margin()
+        .unwrap();
+        let load_config = LoadConfig::with_neighbor_label_frequency().multi_labeled();
+        let graph = Graph::from_reader(input.as_bytes(), load_config).unwrap();
+
+        assert!(graph.is_multi_labeled());
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.label_count(), 3);
+        assert_eq!(graph.max_label(), 3);
+        assert_eq!(graph.max_label_frequency(), 2);
+
+        assert_eq!(graph.label(1), 1);
+        assert_eq!(graph.labels(0), &[0]);
+        assert_eq!(graph.labels(1), &[1, 3]);
+        assert!(graph.has_label(1, 3));
+        assert!(!graph.has_label(1, 0));
+
+        assert_eq!(graph.nodes_by_label(1), &[1, 2]);
+        assert_eq!(graph.nodes_by_label(2), &[] as &[usize]);
+        assert_eq!(graph.nodes_by_label(3), &[1]);
+
+        assert_eq!(graph.neighbor_label_frequency(0).get(&1), Some(&1));
+        assert_eq!(graph.neighbor_label_frequency(0).get(&3), Some(&1));
+
+        let gdl = Graph::from_gdl(
+            "(n0:L0),(n1:L3:L1),(n2:L1),(n0)-->(n1),(n1)-->(n2)",
+            LoadConfig::default(),
+        )
+        .unwrap();
+
+        let path =
+            std::env::temp_dir().join(format!("suma-multi-labels-{}.bin", std::process::id()));
+        graph.write_binary(&path).unwrap();
+        let binary = Graph::read_binary(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        for other in [gdl, binary.unwrap()] {
+            for node in 0..graph.node_count() {
+                assert_eq!(other.labels(node), graph.labels(node));
+                assert_eq!(other.neighbors(node), graph.neighbors(node));
+            }
+        }
+
+        let single = "(n0:L0),(n1:L1),(n0)-->(n1)".parse::<GdlGraph>().unwrap();
+        assert!(!single.is_multi_labeled());
+        assert_eq!(single.labels(1), &[1]);
+
+        let malformed = input.replace("3,1", "3,x");
+        assert!(matches!(
+            Graph::from_reader(malformed.as_bytes(), load_config),
+            Err(Error::InvalidGraphFile { reason }) if reason == "invalid node line 'v 1 3,x 2'"
+        ));
+        assert!(matches!(
+            input.parse::<Graph>(),
+            Err(Error::InvalidGraphFile { .. })
+        ));
+    }
+
+    #[test]
+    fn clone_with_labels() {
+        let graph = "
+        |t 4 4
+        |v 0 0 2
+        |v 1 0 2
+        |v 2 0 2
+        |v 3 0 2
+        |e 0 1
+        |e 0 2
+        |e 1 3
+        |e 2 3
+        |"
+        .trim_margin()
+        .unwrap()
+        .parse::<Graph>()
+        .unwrap();
+
+        assert_eq!(graph.nodes_by_label(0), &[0, 1, 2, 3]);
+
+        let labeled = graph.clone_with_labels(&[1, 0, 2, 1]).unwrap();
+
+        assert_eq!(labeled.node_count(), 4);
+        assert_eq!(labeled.edge_count(), 4);
+        assert_eq!(labeled.label_count(), 3);
+        assert_eq!(labeled.nodes_by_label(0), &[1]);
+        assert_eq!(labeled.nodes_by_label(1), &[0, 3]);
+        assert_eq!(labeled.nodes_by_label(2), &[2]);
+
+        for node in 0..graph.node_count() {
+            assert_eq!(labeled.neighbors(node), graph.neighbors(node));
+        }
+
+        assert_eq!(labeled.neighbor_label_frequency(0).get(&0), Some(&1));
+        assert_eq!(labeled.neighbor_label_frequency(0).get(&2), Some(&1));
+
+        assert!(matches!(
+            graph.clone_with_labels(&[0, 1]),
+            Err(Error::InvalidLabelCount {
+                expected: 4,
+                actual: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn from_edges() {
+        let expected = "
+        |t 5 6
+        |v 0 0 2
+        |v 1 1 3
+        |v 2 2 3
+        |v 3 1 2
+        |v 4 2 2
+        |e 0 1
+        |e 0 2
+        |e 1 2
+        |e 1 3
+        |e 2 4
+        |e 3 4
+        |"
+        .trim_margin()
+        .unwrap()
+        .parse::<Graph>()
+        .unwrap();
+
+        // Edges are neither sorted nor in a consistent direction.
+        let graph = Graph::from_edges(
+            &[0, 1, 2, 1, 2],
+            &[(4, 3), (2, 4), (0, 2), (1, 3), (0, 1), (2, 1)],
+        )
+        .unwrap();
+
+        assert_eq!(graph.node_count(), expected.node_count());
+        assert_eq!(graph.edge_count(), expected.edge_count());
+
+        for node in 0..expected.node_count() {
+            assert_eq!(graph.neighbors(node), expected.neighbors(node));
+            assert_eq!(graph.degree(node), expected.degree(node));
+            assert_eq!(
+                graph.neighbor_label_frequency(node),
+                expected.neighbor_label_frequency(node)
+            );
+        }
+
+        for label in 0..=expected.max_label() {
+            assert_eq!(graph.nodes_by_label(label), expected.nodes_by_label(label));
+        }
+
+        assert!(matches!(
+            Graph::from_edges(&[0, 1], &[(0, 1), (1, 2)]),
+            Err(Error::InvalidNode {
+                node: 2,
+                node_count: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn read_from_edge_list() {
+        let expected = "
+        |t 6 6
+        |v 0 0 2
+        |v 1 1 3
+        |v 2 2 3
+        |v 3 1 2
+        |v 4 2 2
+        |v 5 0 0
+        |e 0 1
+        |e 0 2
+        |e 1 2
+        |e 1 3
+        |e 2 4
+        |e 3 4
+        |"
+        .trim_margin()
+        .unwrap()
+        .parse::<Graph>()
+        .unwrap();
+
+        // Contains a header, a duplicate and a reverse edge.
+        let edges = "
+        |source,target
+        |0,1
+        |0,2
+        |1,2
+        |2,1
+        |1,3
+        |2,4
+        |4,3
+        |0,1
+        |"
+        .trim_margin()
+        .unwrap();
+
+        // Node 5 has no edges.
+        let labels = "
+        |0,0
+        |1,1
+        |2,2
+        |3,1
+        |4,2
+        |5,0
+        |"
+        .trim_margin()
+        .unwrap();
+
+        let comma = Graph::from_edge_list(&edges, &labels, ',', LoadConfig::default()).unwrap();
+        let tab = Graph::from_edge_list(
+            &edges.replace(',', "\t"),
+            &labels.replace(',', "\t"),
+            '\t',
+            LoadConfig::default(),
+        )
+        .unwrap();
+
+        for graph in [comma, tab] {
+            assert_eq!(graph.node_count(), expected.node_count());
+            assert_eq!(graph.edge_count(), expected.edge_count());
+            assert_eq!(graph.label_count(), expected.label_count());
+            assert_eq!(graph.max_label(), expected.max_label());
+            assert_eq!(graph.max_degree(), expected.max_degree());
+            assert_eq!(graph.max_label_frequency(), expected.max_label_frequency());
+
+            for node in 0..expected.node_count() {
+                assert_eq!(graph.label(node), expected.label(node));
+                assert_eq!(graph.neighbors(node), expected.neighbors(node));
+            }
+
+            assert_eq!(graph.degree(5), 0);
+            assert_eq!(graph.nodes_by_label(0), &[0, 5]);
+        }
+
+        assert!(matches!(
+            Graph::from_edge_list("0,1\n1,6", &labels, ',', LoadConfig::default()),
+            Err(Error::ParseGraph { .. })
+        ));
+        assert!(matches!(
+            Graph::from_edge_list("0,1\n1;2", &labels, ',', LoadConfig::default()),
+            Err(Error::ParseGraph { .. })
+        ));
+    }
+
+    #[test]
+    fn read_from_graphml() {
+        let graphml = r#"
+        |<?xml version="1.0" encoding="UTF-8"?>
+        |<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+        |  <key id="d0" for="node" attr.name="label" attr.type="long"/>
+        |  <graph id="G" edgedefault="undirected">
+        |    <node id="n10"><data key="d0">0</data></node>
+        |    <node id="n20"><data key="d0">1</data></node>
+        |    <node id="n30"><data key="d0">2</data></node>
+        |    <node id="n40"><data key="d0">1</data></node>
+        |    <edge source="n10" target="n20"/>
+        |    <edge source="n10" target="n30"/>
+        |    <edge source="n20" target="n30"/>
+        |    <edge source="n40" target="n30"/>
+        |  </graph>
+        |</graphml>
+        |"#
+        .trim_margin()
+        .unwrap();
+
+        let graph = Graph::from_graphml(&graphml, "label", LoadConfig::default()).unwrap();
+
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.edge_count(), 4);
+
+        assert_eq!(graph.label(0), 0);
+        assert_eq!(graph.label(1), 1);
+        assert_eq!(graph.label(2), 2);
+        assert_eq!(graph.label(3), 1);
+        assert_eq!(graph.nodes_by_label(1), &[1, 3]);
+
+        assert_eq!(graph.neighbors(0), &[1, 2]);
+        assert_eq!(graph.neighbors(1), &[0, 2]);
+        assert_eq!(graph.neighbors(2), &[0, 1, 3]);
+        assert_eq!(graph.neighbors(3), &[2]);
+
+        let directed =
+            Graph::from_graphml(&graphml, "label", LoadConfig::default().directed()).unwrap();
+
+        assert_eq!(directed.out_neighbors(3), &[2]);
+        assert_eq!(directed.in_neighbors(3), &[] as &[usize]);
+
+        assert!(matches!(
+            Graph::from_graphml(&graphml, "color", LoadConfig::default()),
+            Err(Error::ParseGraphMl { .. })
+        ));
+    }
+
+    #[test]
+    fn read_edge_labels_from_gdl() {
+        let gdl = "
+        |(n0:L0),(n1:L1),(n2:L1),(n3:L2)
+        |(n0)-[:E0]->(n1)
+        |(n0)-[:E1]->(n2)
+        |(n2)-->(n3)
+        |"
+        .trim_margin()
+        .unwrap();
+
+        let graph = Graph::from_gdl(&gdl, LoadConfig::default().edge_labeled()).unwrap();
+
+        assert!(graph.has_edge_labels());
+        assert_eq!(graph.edge_label(0, 1), Some(0));
+        assert_eq!(graph.edge_label(1, 0), Some(0));
+        assert_eq!(graph.edge_label(0, 2), Some(1));
+        assert_eq!(graph.edge_label(2, 3), None);
+        assert_eq!(graph.edge_label(1, 2), None);
+
+        let graph = Graph::from_gdl(&gdl, LoadConfig::default()).unwrap();
+
+        assert!(!graph.has_edge_labels());
+        assert_eq!(graph.edge_label(0, 1), None);
+    }
+
+    #[test]
+    fn validate() {
+        let graphs = [
+            "(n0:L0),(n1:L1),(n2:L1),(n3),(n0)-->(n1),(n0)-->(n2),(n2)-->(n3)",
+            "(n0:L0:L1),(n1:L1),(n2),(n0)-[:E1]->(n1),(n1)-[:E2]->(n2)",
+        ];
+
+        for gdl in graphs.iter() {
+            let graph = Graph::from_gdl(
+                gdl,
+                LoadConfig::with_neighbor_label_frequency().edge_labeled(),
+            )
+            .unwrap();
+
+            assert!(graph.validate().is_ok());
+        }
+
+        let csr_graph = GraphBuilder::new()
+            .csr_layout(CsrLayout::Unsorted)
+            .edges(vec![(0, 2), (0, 1), (1, 2)])
+            .build();
+        let unsorted = Graph::from_csr(csr_graph, &[0, 1, 1], LoadConfig::default());
+
+        assert_eq!(unsorted.neighbors(0), &[2, 1]);
+        assert!(matches!(
+            unsorted.validate(),
+            Err(Error::InvalidGraph { reason }) if reason == "neighbors of node 0 are not sorted"
+        ));
+
+        let mut graph = Graph::from_gdl(graphs[0], LoadConfig::default()).unwrap();
+        graph.max_degree = 3;
+
+        assert!(matches!(
+            graph.validate(),
+            Err(Error::InvalidGraph { reason }) if reason == "the maximum degree is 2, but 3 is stored"
+        ));
+
+        graph.max_degree = 2;
+        graph.node_count = 3;
+
+        assert!(matches!(
+            graph.validate(),
+            Err(Error::InvalidGraph { reason }) if reason == "node 3 has neighbors, but the graph has 3 nodes"
+        ));
+    }
+
+    #[test]
+    fn exists() {
+        let input = "
+        |t 4 4
+        |v 0 0 3
+        |v 1 1 3
+        |v 2 1 2
+        |v 3 0 0
+        |e 0 1
+        |e 1 0
+        |e 0 2
+        |e 1 2
+        |"
+        .trim_margin()
+        .unwrap();
+        let graph = input.parse::<Graph>().unwrap();
+
+        assert!(graph.exists(0, 1));
+        assert!(graph.exists(1, 0));
+        assert!(graph.has_edge(2, 1));
+        assert!(!graph.exists(0, 3));
+        assert!(!graph.exists(0, 0));
+        assert!(!graph.has_edge(2, 2));
+        assert!(!graph.exists(7, 0));
+        assert!(!graph.exists(0, 7));
+
+        // The duplicate edge is stored in both directions.
+        assert_eq!(graph.edge_count_between(0, 1), 2);
+        assert_eq!(graph.edge_count_between(1, 0), 2);
+        assert_eq!(graph.edge_count_between(0, 2), 1);
+        assert_eq!(graph.edge_count_between(2, 0), 1);
+        assert_eq!(graph.edge_count_between(0, 3), 0);
+        assert_eq!(graph.edge_count_between(3, 3), 0);
+    }
+
+    #[test]
+    fn reject_self_loops() {
+        let input = "
+        |t 4 3
+        |v 0 0 1
+        |v 1 1 2
+        |v 2 1 1
+        |v 3 0 2
+        |e 0 1
+        |e 1 2
+        |e 3 3
+        |"
+        .trim_margin()
+        .unwrap();
+
+        assert!(matches!(
+            input.parse::<Graph>(),
+            Err(Error::SelfLoop { node: 3 })
+        ));
+        assert!(matches!(
+            Graph::from_gdl(
+                "(n0:L0),(n1:L1),(n0)-->(n1),(n1)-->(n1)",
+                LoadConfig::default()
+            ),
+            Err(Error::SelfLoop { node: 1 })
+        ));
+        assert!(matches!(
+            Graph::from_edges(&[0, 1], &[(0, 0), (0, 1)]),
+            Err(Error::SelfLoop { node: 0 })
+        ));
+    }
+
+    #[test]
+    fn reject_inconsistent_graph_files() {
+        let invalid = |input: &str| match input.parse::<Graph>() {
+            Err(Error::InvalidGraphFile { reason }) => Some(reason),
+            _ => None,
+        };
+        let nodes = "v 0 0 1\nv 1 1 2\nv 2 1 1\n";
+
+        assert_eq!(
+            invalid(&format!("t 3 3\n{}e 0 1\ne 1 2\n", nodes)).as_deref(),
+            Some("the header declares 3 edges, but there are 2 edge lines")
+        );
+        assert_eq!(
+            invalid(&format!("t 3 1\n{}e 0 1\ne 1 2\n", nodes)).as_deref(),
+            Some("the header declares 1 edges, but there are 2 edge lines")
+        );
+        assert_eq!(
+            invalid(&format!("t 4 2\n{}e 0 1\ne 1 2\n", nodes)).as_deref(),
+            Some("the header declares 4 nodes, but there are 3 node lines")
+        );
+        // The duplicate edge exceeds the declared degree of node 0.
+        assert_eq!(
+            invalid(&format!("t 3 3\n{}e 0 1\ne 1 2\ne 0 1\n", nodes)).as_deref(),
+            Some("node 0 declares degree 1, but has 2 edges")
+        );
+        assert_eq!(
+            invalid(&format!("t 3 2\n{}e 0 1\ne 1 3\n", nodes)).as_deref(),
+            Some("edge (1, 3) refers to node 3, but the header declares 3 nodes")
+        );
+        assert_eq!(
+            invalid("t 3 1\nv 0 0 1\nv 2 1 0\nv 1 1 1\ne 0 1\n").as_deref(),
+            Some("expected node 1, but found node 2")
+        );
+        assert_eq!(
+            invalid(&format!("t 3 2\n{}e 0 1\nx 1 2\n", nodes)).as_deref(),
+            Some("invalid line 'x 1 2'")
+        );
+
+        let path = std::env::temp_dir().join(format!("suma-invalid-{}.graph", std::process::id()));
+        std::fs::write(&path, format!("t 3 3\n{}e 0 1\ne 1 2\n", nodes)).unwrap();
+        let loaded = load(&path, LoadConfig::default());
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(loaded, Err(Error::InvalidGraphFile { .. })));
+    }
+
+    #[test]
+    fn read_edge_weights() {
+        let gdl = "
+        |(n0:L0),(n1:L1),(n2:L1),(n3:L2)
+        |(n0)-[{weight: 0.5}]->(n1)
+        |(n0)-[{weight: 2}]->(n2)
+        |(n2)-->(n3)
+        |"
+        .trim_margin()
+        .unwrap();
+
+        let graph = Graph::from_gdl(&gdl, LoadConfig::default().weighted()).unwrap();
+
+        assert!(graph.has_edge_weights());
+        assert_eq!(graph.edge_weight(0, 1), Some(0.5));
+        assert_eq!(graph.edge_weight(1, 0), Some(0.5));
+        assert_eq!(graph.edge_weight(0, 2), Some(2.0));
+        assert_eq!(graph.edge_weight(2, 3), None);
+        assert_eq!(graph.edge_weight(1, 2), None);
+
+        let graph = Graph::from_gdl(&gdl, LoadConfig::default()).unwrap();
+
+        assert!(!graph.has_edge_weights());
+        assert_eq!(graph.edge_weight(0, 1), None);
+
+        let input = "
+        |t 3 2
+        |v 0 0 1
+        |v 1 1 2
+        |v 2 1 1
+        |e 0 1 4 0.25
+        |e 1 2 * 7
+        |"
+        .trim_margin()
+        .unwrap();
+
+        let load_config = LoadConfig::default().edge_labeled().weighted();
+        let graph = Graph::from_reader(input.as_bytes(), load_config).unwrap();
+
+        assert_eq!(graph.edge_weight(1, 0), Some(0.25));
+        assert_eq!(graph.edge_weight(2, 1), Some(7.0));
+        assert_eq!(graph.edge_label(1, 0), Some(4));
+        assert_eq!(graph.edge_label(1, 2), None);
+
+        // The columns are fixed by the load config, so the third value is a
+        // weight if edge labels are not loaded and missing values are errors.
+        let unlabeled = "t 2 1\nv 0 0 1\nv 1 1 1\ne 0 1 3\n";
+        let graph = Graph::from_reader(unlabeled.as_bytes(), LoadConfig::default().weighted());
+
+        assert_eq!(graph.unwrap().edge_weight(0, 1), Some(3.0));
+        assert!(matches!(
+            Graph::from_reader(unlabeled.as_bytes(), load_config),
+            Err(Error::InvalidGraphFile { reason }) if reason == "invalid edge line 'e 0 1 3'"
+        ));
+        assert!(matches!(
+            Graph::from_reader(input.replace("0.25", "x").as_bytes(), load_config),
+            Err(Error::InvalidGraphFile { .. })
+        ));
+        assert!(matches!(
+            Graph::from_gdl("(n0:L0),(n1:L1),(n0)-[{weight: true}]->(n1)", load_config),
+            Err(Error::InvalidGdlGraph { .. })
+        ));
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let gdl = "
+        |(n0:L0),(n1:L1),(n2:L1),(n3:L2)
+        |(n0)-[:E0 {weight: 1.5}]->(n1)
+        |(n0)-[:E1]->(n2)
+        |(n2)-[{undirected: true}]->(n3)
+        |"
+        .trim_margin()
+        .unwrap();
+        let load_config = LoadConfig::with_neighbor_label_frequency()
+            .directed()
+            .edge_labeled()
+            .weighted();
+        let graph = Graph::from_gdl(&gdl, load_config).unwrap();
+
+        let path = std::env::temp_dir().join(format!("suma-round-trip-{}.bin", std::process::id()));
+        graph.write_binary(&path).unwrap();
+        let read = Graph::read_binary(&path);
+        std::fs::write(&path, b"SUMAGRPH\x03\x00\x00\x00").unwrap();
+        let invalid = Graph::read_binary(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let read = read.unwrap();
+
+        assert!(read == graph);
+        assert_eq!(read.node_count(), graph.node_count());
+        assert_eq!(read.edge_count(), graph.edge_count());
+        assert_eq!(read.max_degree(), graph.max_degree());
+        assert_eq!(read.max_label(), graph.max_label());
+        assert_eq!(read.label_frequencies, graph.label_frequencies);
+        assert_eq!(read.nodes_by_label(1), graph.nodes_by_label(1));
+
+        for node in 0..graph.node_count() {
+            assert_eq!(read.label(node), graph.label(node));
+            assert_eq!(read.neighbors(node), graph.neighbors(node));
+            assert_eq!(read.out_neighbors(node), graph.out_neighbors(node));
+            assert_eq!(read.in_neighbors(node), graph.in_neighbors(node));
+            assert_eq!(
+                read.neighbor_label_frequency(node),
+                graph.neighbor_label_frequency(node)
+            );
+
+            for &neighbor in graph.neighbors(node) {
+                assert_eq!(
+                    read.edge_label(node, neighbor),
+                    graph.edge_label(node, neighbor)
+                );
+                assert_eq!(
+                    read.edge_weight(node, neighbor),
+                    graph.edge_weight(node, neighbor)
+                );
+            }
+        }
+
+        assert!(read.is_undirected_edge(3, 2));
+        assert_eq!(read.edge_label(0, 2), Some(1));
+
+        assert!(matches!(invalid, Err(Error::InvalidBinaryGraph { .. })));
+    }
+
+    #[test]
+    fn graph_equality() {
+        let graph = |gdl: &str| gdl.parse::<GdlGraph>().unwrap();
+
+        let path = graph("(n0:L0),(n1:L1),(n2:L1),(n0)-->(n1),(n1)-->(n2)");
+        let reversed = graph("(n0:L1),(n1:L1),(n2:L0),(n2)-->(n1),(n1)-->(n0)");
+        let relabeled = graph("(n0:L1),(n1:L0),(n2:L1),(n0)-->(n1),(n1)-->(n2)");
+        let triangle = graph("(n0:L0),(n1:L1),(n2:L1),(n0)-->(n1),(n1)-->(n2),(n2)-->(n0)");
+
+        assert!(*path == *graph("(n0:L0),(n1:L1),(n2:L1),(n1)-->(n0),(n2)-->(n1)"));
+        assert!(*path != *reversed);
+        assert!(*path != *relabeled);
+        assert!(*path != *triangle);
+
+        assert!(path.is_isomorphic_to(&path, true));
+        assert!(path.is_isomorphic_to(&reversed, true));
+        assert!(!path.is_isomorphic_to(&relabeled, true));
+        assert!(path.is_isomorphic_to(&relabeled, false));
+        assert!(!path.is_isomorphic_to(&triangle, false));
+
+        let star = graph("(n0),(n1),(n2),(n3),(n0)-->(n1),(n0)-->(n2),(n0)-->(n3)");
+        let line = graph("(n0),(n1),(n2),(n3),(n0)-->(n1),(n1)-->(n2),(n2)-->(n3)");
+        assert!(!star.is_isomorphic_to(&line, false));
+
+        // Isolated nodes are matched as well.
+        let edge = graph("(n0:L0),(n1:L1),(n2:L1),(n0)-->(n1)");
+        let shifted = graph("(n0:L1),(n1:L0),(n2:L1),(n1)-->(n2)");
+        assert!(edge.is_isomorphic_to(&shifted, true));
+    }
+
+    #[test]
+    fn subgraph() {
+        let gdl = "
+        |(n0:L0),(n1:L1),(n2:L2),(n3:L1),(n4:L2)
+        |(n0)-[:E0]->(n1)
+        |(n0)-[:E1]->(n2)
+        |(n1)-[:E2]->(n2)
+        |(n1)-[:E3]->(n3)
+        |(n2)-[:E4]->(n4)
+        |(n4)-[:E5]->(n3)
+        |"
+        .trim_margin()
+        .unwrap();
+        let load_config = LoadConfig::with_neighbor_label_frequency()
+            .directed()
+            .edge_labeled();
+        let graph = Graph::from_gdl(&gdl, load_config).unwrap();
+
+        // The neighborhood of n3, which maps n1 -> 0, n3 -> 1 and n4 -> 2.
+        let subgraph = graph.subgraph(&[4, 1, 3, 3]).unwrap();
+
+        assert_eq!(subgraph.node_count(), 3);
+        assert_eq!(subgraph.edge_count(), 2);
+        assert_eq!(subgraph.max_degree(), 2);
+        assert_eq!(subgraph.labels(0), &[1]);
+        assert_eq!(subgraph.labels(1), &[1]);
+        assert_eq!(subgraph.labels(2), &[2]);
+        assert_eq!(subgraph.nodes_by_label(1), &[0, 1]);
+        assert_eq!(subgraph.neighbors(0), &[1]);
+        assert_eq!(subgraph.neighbors(1), &[0, 2]);
+        assert_eq!(subgraph.neighbors(2), &[1]);
+        assert_eq!(subgraph.neighbor_label_frequency(1).get(&2), Some(&1));
+        assert!(subgraph.exists_directed(0, 1));
+        assert!(subgraph.exists_directed(2, 1));
+        assert!(!subgraph.exists_directed(1, 2));
+        assert_eq!(subgraph.edge_label(1, 0), Some(3));
+        assert_eq!(subgraph.edge_label(1, 2), Some(5));
+
+        assert!(matches!(
+            graph.subgraph(&[0, 5]),
+            Err(Error::InvalidNode {
+                node: 5,
+                node_count: 5
+            })
+        ));
+    }
+
+    #[test]
+    fn read_edge_labels() {
+        let input = "
+        |t 3 3
+        |v 0 0 2
+        |v 1 1 3
+        |v 2 1 1
+        |e 0 1 4
+        |e 1 0 5
+        |e 1 2 *
+        |"
+        .trim_margin()
+        .unwrap();
+        let load_config = LoadConfig::default().edge_labeled();
+
+        let graph = Graph::from_reader(input.as_bytes(), load_config).unwrap();
+        let gdl = Graph::from_gdl(
+            "(n0:L0),(n1:L1),(n2:L1),(n0)-[:E4]->(n1),(n1)-[:E5]->(n0),(n1)-->(n2)",
+            load_config,
+        )
+        .unwrap();
+        let subgraph = graph.subgraph(&[0, 1]).unwrap();
+
+        for graph in [&graph, &gdl, &subgraph] {
+            assert!(graph.validate().is_ok());
+            assert_eq!(graph.edge_count_between(0, 1), 2);
+            // Edges in both directions keep their own label.
+            assert_eq!(graph.edge_label(0, 1), Some(4));
+            assert_eq!(graph.edge_label(1, 0), Some(5));
+        }
+        assert_eq!(graph.edge_label(1, 2), None);
+        assert_eq!(gdl.edge_label(1, 2), None);
+
+        let unlabeled = input.replace("e 1 2 *", "e 1 2");
+        assert!(matches!(
+            Graph::from_reader(unlabeled.as_bytes(), load_config),
+            Err(Error::InvalidGraphFile { reason }) if reason == "invalid edge line 'e 1 2'"
+        ));
+        assert!(matches!(
+            Graph::from_reader(input.replace("e 0 1 4", "e 0 1 x").as_bytes(), load_config),
+            Err(Error::InvalidGraphFile { .. })
+        ));
+        assert!(matches!(
+            Graph::from_gdl("(n0:L0),(n1:L1),(n0)-[:EX]->(n1)", load_config),
+            Err(Error::InvalidGdlGraph { .. })
+        ));
+    }
 }
@@ -0,0 +1,428 @@
+//! A versioned binary format for graphs.
+//!
+//! The layout is a header followed by the labels, the label index and the
+//! CSR of the undirected graph and optional sections for edge direction,
+//! edge labels, edge weights, node label sets, anti-edges and neighbor label
+//! frequencies, such that a graph can be restored without rebuilding any of
+//! them:
+//!
+//! ```text
+//! magic "SUMAGRPH" | version: u32 | flags: u32
+//! node_count | max_degree | label_count
+//! label_values: [label_count] | labels: [node_count] | label_frequencies: [label_count]
+//! if indexed: label_offsets: [label_count + 1] | nodes: [label_offsets[label_count]]
+//! CSR: byte_count | [u8]
+//! if directed: CSR: byte_count | [u8] | count | [(source, target)] (undirected edges)
+//! if edge labeled: count | [count] (aligned with neighbors)
+//! if multi-labeled: label_offsets: [node_count + 1] | [label_offsets[node_count]]
+//! if anti-edges: count | [(source, target)]
+//! if edge weighted: count | [count] (aligned with neighbors, f64 bits)
+//! if neighbor label frequencies: for each node: count | [(label, frequency)]
+//! ```
+//!
+//! Unless noted otherwise, all values are little-endian `u64`. The CSR
+//! sections are written by the `graph` crate in native byte order, i.e. the
+//! files are not portable between platforms of different endianness.
+
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    io::{Read, Write},
+    sync::OnceLock,
+};
+
+use graph::{
+    graph_ops::{DeserializeGraphOp, SerializeGraphOp},
+    prelude::{DirectedNeighbors, Graph as OtherGraph, UndirectedNeighbors},
+};
+
+use crate::Error;
+
+use super::{DirectedGraph, Graph, LabelIndex, MultiLabels, UndirectedGraph, ANY_LABEL};
+
+const MAGIC: &[u8; 8] = b"SUMAGRPH";
+const VERSION: u32 = 2;
+
+const NEIGHBOR_LABEL_FREQUENCY: u32 = 1;
+const DIRECTED: u32 = 1 << 1;
+const EDGE_LABELS: u32 = 1 << 2;
+const MULTI_LABELS: u32 = 1 << 3;
+const ANTI_EDGES: u32 = 1 << 4;
+const EDGE_WEIGHTS: u32 = 1 << 5;
+const LABEL_INDEX: u32 = 1 << 6;
+
+pub(super) fn write<W: Write>(graph: &Graph, writer: &mut W) -> Result<(), Error> {
+    let mut flags = 0;
+    if graph.neighbor_label_frequencies.is_some() {
+        flags |= NEIGHBOR_LABEL_FREQUENCY;
+    }
+    if graph.directed.is_some() {
+        flags |= DIRECTED;
+    }
+    if graph.edge_labels.is_some() {
+        flags |= EDGE_LABELS;
+    }
+    if graph.multi_labels.is_some() {
+        flags |= MULTI_LABELS;
+    }
+    if !graph.anti_edges.is_empty() {
+        flags |= ANTI_EDGES;
+    }
+    if graph.edge_weights.is_some() {
+        flags |= EDGE_WEIGHTS;
+    }
+    if graph.label_index.is_some() {
+        flags |= LABEL_INDEX;
+    }
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&VERSION.to_le_bytes())?;
+    writer.write_all(&flags.to_le_bytes())?;
+
+    for value in [graph.node_count, graph.max_degree, graph.label_values.len()] {
+        write_usize(writer, value)?;
+    }
+
+    write_values(writer, &graph.label_values)?;
+    write_values(writer, &graph.labels)?;
+    write_values(writer, &graph.label_frequencies)?;
+
+    if let Some(label_index) = &graph.label_index {
+        write_values(writer, &label_index.offsets)?;
+        write_values(writer, &label_index.nodes)?;
+    }
+
+    write_csr(writer, &graph.graph)?;
+
+    if let Some(directed) = &graph.directed {
+        write_csr(writer, directed)?;
+        write_pairs(writer, &graph.undirected_edges)?;
+    }
+
+    if let Some(edge_labels) = &graph.edge_labels {
+        write_values(
+            writer,
+            &[edge_labels.iter().map(|labels| labels.len()).sum()],
+        )?;
+        for labels in edge_labels.iter() {
+            write_values(writer, labels)?;
+        }
+    }
+
+    if let Some(multi_labels) = &graph.multi_labels {
+        let offsets = std::iter::once(0)
+            .chain(multi_labels.labels.iter().scan(0, |offset, labels| {
+                *offset += labels.len();
+                Some(*offset)
+            }))
+            .collect::<Vec<_>>();
+        write_values(writer, &offsets)?;
+        for labels in multi_labels.labels.iter() {
+            write_values(writer, labels)?;
+        }
+    }
+
+    if !graph.anti_edges.is_empty() {
+        write_pairs(writer, &graph.anti_edges)?;
+    }
+
+    if let Some(edge_weights) = &graph.edge_weights {
+        write_usize(
+            writer,
+            edge_weights.iter().map(|weights| weights.len()).sum(),
+        )?;
+        edge_weights
+            .iter()
+            .flat_map(|weights| weights.iter())
+            .try_for_each(|weight| writer.write_all(&weight.to_bits().to_le_bytes()))?;
+    }
+
+    if let Some(nlfs) = &graph.neighbor_label_frequencies {
+        for nlf in nlfs.iter() {
+            let mut frequencies = nlf
+                .iter()
+                .map(|(&label, &frequency)| (label, frequency))
+                .collect::<Vec<_>>();
+            frequencies.sort_unstable();
+            write_pairs(writer, &frequencies)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub(super) fn read<R: Read>(reader: &mut R) -> Result<Graph, Error> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a binary graph file"));
+    }
+
+    let version = read_u32(reader)?;
+    if version != VERSION {
+        return Err(invalid(format!(
+            "unsupported version {}, expected {}",
+            version, VERSION
+        )));
+    }
+
+    let flags = read_u32(reader)?;
+
+    let node_count = read_usize(reader)?;
+    let max_degree = read_usize(reader)?;
+    let label_count = read_usize(reader)?;
+
+    let label_values = read_values(reader, label_count)?;
+    if label_values.windows(2).any(|window| window[0] >= window[1]) {
+        return Err(invalid("label values are not sorted"));
+    }
+    let labels = read_values(reader, node_count)?;
+    if labels
+        .iter()
+        .any(|&label| label >= label_count && label != ANY_LABEL)
+    {
+        return Err(invalid("label out of range"));
+    }
+    let label_frequencies = read_values(reader, label_count)?;
+
+    let label_index = if flags & LABEL_INDEX != 0 {
+        let offsets = read_offsets(reader, label_count)?;
+        let nodes = read_values(reader, offsets[label_count])?;
+        if nodes.iter().any(|&node| node >= node_count) {
+            return Err(invalid("indexed node out of range"));
+        }
+        Some(LabelIndex {
+            offsets: offsets.into_boxed_slice(),
+            nodes: nodes.into_boxed_slice(),
+        })
+    } else {
+        None
+    };
+
+    let csr = read_csr::<_, UndirectedGraph>(reader)?;
+    let degrees = (0..node_count)
+        .map(|node| {
+            if node < csr.node_count() {
+                csr.neighbors(node).len()
+            } else {
+                0
+            }
+        })
+        .collect::<Vec<_>>();
+    if csr.node_count() > node_count.max(1)
+        || (0..csr.node_count()).any(|node| csr.neighbors(node).iter().any(|&n| n >= node_count))
+    {
+        return Err(invalid("neighbor out of range"));
+    }
+
+    let (directed, undirected_edges) = if flags & DIRECTED != 0 {
+        let directed = read_csr::<_, DirectedGraph>(reader)?;
+        let undirected_edges = read_pairs(reader)?;
+        if directed.node_count() > node_count.max(1)
+            || (0..directed.node_count()).any(|node| {
+                directed
+                    .out_neighbors(node)
+                    .iter()
+                    .any(|&target| target >= node_count)
+            })
+            || out_of_range(&undirected_edges, node_count)
+        {
+            return Err(invalid("directed edge out of range"));
+        }
+        (Some(directed), undirected_edges)
+    } else {
+        (None, Vec::new())
+    };
+
+    let edge_labels = if flags & EDGE_LABELS != 0 {
+        let count = read_usize(reader)?;
+        let edge_labels = read_values(reader, count)?;
+        Some(split_by_degree(edge_labels, &degrees)?)
+    } else {
+        None
+    };
+
+    let multi_labels = if flags & MULTI_LABELS != 0 {
+        let label_offsets = read_offsets(reader, node_count)?;
+        let labels = read_values(reader, label_offsets[node_count])?;
+        if labels
+            .iter()
+            .any(|label| label_values.binary_search(label).is_err() && *label != ANY_LABEL)
+        {
+            return Err(invalid("label out of range"));
+        }
+        Some(MultiLabels {
+            labels: label_offsets
+                .windows(2)
+                .map(|window| labels[window[0]..window[1]].into())
+                .collect(),
+        })
+    } else {
+        None
+    };
+
+    let anti_edges = if flags & ANTI_EDGES != 0 {
+        let anti_edges = read_pairs(reader)?;
+        if out_of_range(&anti_edges, node_count) {
+            return Err(invalid("anti-edge out of range"));
+        }
+        anti_edges
+    } else {
+        Vec::new()
+    };
+
+    let edge_weights = if flags & EDGE_WEIGHTS != 0 {
+        let count = read_usize(reader)?;
+        let mut edge_weights = Vec::new();
+        for _ in 0..count {
+            let mut bytes = [0; 8];
+            reader.read_exact(&mut bytes)?;
+            edge_weights.push(f64::from_bits(u64::from_le_bytes(bytes)));
+        }
+        Some(split_by_degree(edge_weights, &degrees)?)
+    } else {
+        None
+    };
+
+    let neighbor_label_frequencies = if flags & NEIGHBOR_LABEL_FREQUENCY != 0 {
+        let mut nlfs = Vec::new();
+        for _ in 0..node_count {
+            nlfs.push(read_pairs(reader)?.into_iter().collect::<HashMap<_, _>>());
+        }
+        Some(nlfs.into_boxed_slice())
+    } else {
+        None
+    };
+
+    Ok(Graph {
+        graph: csr,
+        node_count,
+        max_degree,
+        labels: labels.into_boxed_slice(),
+        label_index,
+        label_frequencies: label_frequencies.into_boxed_slice(),
+        directed,
+        undirected_edges: undirected_edges.into_boxed_slice(),
+        anti_edges: anti_edges.into_boxed_slice(),
+        edge_labels,
+        edge_weights,
+        neighbor_label_frequencies,
+        multi_labels,
+        label_values: label_values.into_boxed_slice(),
+        raw_csr: OnceLock::new(),
+    })
+}
+
+fn write_csr<W, G>(writer: &mut W, graph: &G) -> Result<(), Error>
+where
+    W: Write,
+    G: for<'a> SerializeGraphOp<&'a mut Vec<u8>>,
+{
+    let mut bytes = Vec::new();
+    graph.serialize(&mut bytes)?;
+    write_usize(writer, bytes.len())?;
+    writer.write_all(&bytes)?;
+    Ok(())
+}
+
+// The section is limited to its byte count, such that a corrupt CSR can't
+// consume the following sections.
+fn read_csr<R, G>(reader: &mut R) -> Result<G, Error>
+where
+    R: Read,
+    G: for<'a> DeserializeGraphOp<std::io::Take<&'a mut R>, G>,
+{
+    let byte_count = read_usize(reader)?;
+    Ok(G::deserialize(reader.take(byte_count as u64))?)
+}
+
+fn write_usize<W: Write>(writer: &mut W, value: usize) -> Result<(), Error> {
+    writer.write_all(&(value as u64).to_le_bytes())?;
+    Ok(())
+}
+
+fn write_values<W: Write>(writer: &mut W, values: &[usize]) -> Result<(), Error> {
+    values
+        .iter()
+        .try_for_each(|&value| write_usize(writer, value))
+}
+
+fn write_pairs<W: Write>(writer: &mut W, pairs: &[(usize, usize)]) -> Result<(), Error> {
+    write_usize(writer, pairs.len())?;
+    pairs.iter().try_for_each(|&(source, target)| {
+        write_usize(writer, source)?;
+        write_usize(writer, target)
+    })
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> Result<u32, Error> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_usize<R: Read>(reader: &mut R) -> Result<usize, Error> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    usize::try_from(u64::from_le_bytes(bytes)).map_err(|_| invalid("value exceeds usize"))
+}
+
+// Counts are read from the file, so we grow the vectors as we go instead of
+// allocating upfront, which could exhaust memory for a corrupt count.
+fn read_values<R: Read>(reader: &mut R, count: usize) -> Result<Vec<usize>, Error> {
+    let mut values = Vec::new();
+    for _ in 0..count {
+        values.push(read_usize(reader)?);
+    }
+    Ok(values)
+}
+
+/// Reads `count + 1` monotonic offsets.
+fn read_offsets<R: Read>(reader: &mut R, count: usize) -> Result<Vec<usize>, Error> {
+    let offset_count = count
+        .checked_add(1)
+        .ok_or_else(|| invalid("count out of range"))?;
+    let offsets = read_values(reader, offset_count)?;
+    if offsets.windows(2).any(|window| window[0] > window[1]) {
+        return Err(invalid("offsets are not monotonic"));
+    }
+    Ok(offsets)
+}
+
+fn read_pairs<R: Read>(reader: &mut R) -> Result<Vec<(usize, usize)>, Error> {
+    let count = read_usize(reader)?;
+    let mut pairs = Vec::new();
+    for _ in 0..count {
+        pairs.push((read_usize(reader)?, read_usize(reader)?));
+    }
+    Ok(pairs)
+}
+
+fn out_of_range(pairs: &[(usize, usize)], node_count: usize) -> bool {
+    pairs
+        .iter()
+        .any(|&(source, target)| source >= node_count || target >= node_count)
+}
+
+/// Splits values that are aligned with the neighbors of all nodes into one
+/// slice per node.
+fn split_by_degree<T: Copy>(values: Vec<T>, degrees: &[usize]) -> Result<Box<[Box<[T]>]>, Error> {
+    if values.len() != degrees.iter().sum::<usize>() {
+        return Err(invalid("edge values are not aligned with the neighbors"));
+    }
+
+    let mut offset = 0;
+    Ok(degrees
+        .iter()
+        .map(|&degree| {
+            offset += degree;
+            values[offset - degree..offset].into()
+        })
+        .collect())
+}
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::InvalidBinaryGraph {
+        reason: reason.into(),
+    }
+}
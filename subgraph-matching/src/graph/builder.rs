@@ -0,0 +1,218 @@
+//! Incremental construction of node labeled, undirected graphs.
+
+use std::collections::HashSet;
+
+use crate::Error;
+
+use super::{Graph, LoadConfig};
+
+/// Buffers nodes and edges of an evolving graph and builds the CSR graph,
+/// including the label index and neighbor label frequencies, on demand.
+///
+/// Adding nodes and edges is cheap, whereas [`GraphBuilderMut::finalize`]
+/// rebuilds the whole graph if anything has been added since the last call,
+/// so changes should be added in batches.
+#[derive(Default)]
+pub struct GraphBuilderMut {
+    labels: Vec<usize>,
+    edges: Vec<(usize, usize)>,
+    // Edges as (smaller node, larger node) to detect duplicates.
+    edge_set: HashSet<(usize, usize)>,
+    graph: Option<Graph>,
+}
+
+impl GraphBuilderMut {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts with the nodes and edges of the given graph, e.g. to extend a
+    /// loaded data graph.
+    ///
+    /// Returns [`Error::InvalidGraph`] if the graph has properties the
+    /// builder can't represent, i.e. multiple labels per node, edge labels,
+    /// edge weights, anti-edges or edge direction.
+    pub fn from_graph(graph: &Graph) -> Result<Self, Error> {
+        let unsupported = [
+            (graph.multi_labels.is_some(), "multiple labels per node"),
+            (graph.edge_labels.is_some(), "edge labels"),
+            (graph.edge_weights.is_some(), "edge weights"),
+            (!graph.anti_edges.is_empty(), "anti-edges"),
+            (graph.directed.is_some(), "edge direction"),
+        ];
+        if let Some((_, property)) = unsupported.iter().find(|(present, _)| *present) {
+            return Err(Error::InvalidGraph {
+                reason: format!("the builder does not support {}", property),
+            });
+        }
+
+        let edges = graph.edges();
+        Ok(Self {
+            labels: (0..graph.node_count())
+                .map(|node| graph.label(node))
+                .collect(),
+            edge_set: edges.iter().copied().collect(),
+            edges,
+            graph: None,
+        })
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Adds a node with the given label, which may be [`super::ANY_LABEL`],
+    /// and returns its id.
+    pub fn add_node(&mut self, label: usize) -> usize {
+        self.graph = None;
+        self.labels.push(label);
+        self.labels.len() - 1
+    }
+
+    /// Adds an undirected edge between two existing nodes and returns
+    /// whether it has been added, i.e. `false` if the nodes are already
+    /// connected.
+    ///
+    /// Returns [`Error::InvalidNode`] if one of the nodes has not been added
+    /// yet and [`Error::SelfLoop`] if both nodes are the same.
+    pub fn add_edge(&mut self, source: usize, target: usize) -> Result<bool, Error> {
+        let node_count = self.node_count();
+        if let Some(&node) = [source, target].iter().find(|&&node| node >= node_count) {
+            return Err(Error::InvalidNode { node, node_count });
+        }
+        if source == target {
+            return Err(Error::SelfLoop { node: source });
+        }
+
+        if !self
+            .edge_set
+            .insert((source.min(target), source.max(target)))
+        {
+            return Ok(false);
+        }
+
+        self.graph = None;
+        self.edges.push((source, target));
+        Ok(true)
+    }
+
+    /// Returns the graph of all nodes and edges added so far.
+    ///
+    /// The graph is only rebuilt if nodes or edges have been added since the
+    /// last call. The builder can be extended afterwards and finalized again.
+    pub fn finalize(&mut self) -> Result<&Graph, Error> {
+        let graph = match self.graph.take() {
+            Some(graph) => graph,
+            None => Graph::from_parts(
+                &self.labels,
+                &self.edges,
+                LoadConfig::with_neighbor_label_frequency(),
+            )?,
+        };
+        debug_assert!(graph.validate().is_ok());
+        Ok(self.graph.insert(graph))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use trim_margin::MarginTrimmable;
+
+    #[test]
+    fn test_finalize() {
+        let parsed = "
+        |t 5 6
+        |v 0 0 2
+        |v 1 1 3
+        |v 2 2 3
+        |v 3 1 2
+        |v 4 2 2
+        |e 0 1
+        |e 0 2
+        |e 1 2
+        |e 1 3
+        |e 2 4
+        |e 3 4
+        |"
+        .trim_margin()
+        .unwrap()
+        .parse::<Graph>()
+        .unwrap();
+
+        let mut builder = GraphBuilderMut::new();
+        for label in [0, 1, 2, 1, 2] {
+            builder.add_node(label);
+        }
+        for (source, target) in [(0, 1), (0, 2), (1, 2)] {
+            builder.add_edge(source, target).unwrap();
+        }
+
+        let partial = builder.finalize().unwrap();
+
+        assert_eq!(partial.edge_count(), 3);
+        assert_eq!(partial.degree(3), 0);
+
+        // Unchanged builders return the cached graph.
+        let partial = partial as *const Graph;
+        assert!(std::ptr::eq(builder.finalize().unwrap(), partial));
+
+        for (source, target) in [(1, 3), (2, 4), (3, 4)] {
+            assert!(builder.add_edge(source, target).unwrap());
+        }
+        assert!(!builder.add_edge(1, 0).unwrap());
+        assert!(!builder.add_edge(3, 4).unwrap());
+        assert_eq!(builder.edge_count(), 6);
+
+        let graph = builder.finalize().unwrap();
+
+        assert!(*graph == parsed);
+        assert_eq!(graph.max_degree(), 3);
+        assert_eq!(graph.label_frequency(1), 2);
+        for node in 0..graph.node_count() {
+            assert_eq!(graph.degree(node), parsed.degree(node));
+            assert_eq!(
+                graph.neighbor_label_frequency(node),
+                parsed.neighbor_label_frequency(node)
+            );
+        }
+
+        // Extending a finalized graph keeps its nodes and edges.
+        let mut builder = GraphBuilderMut::from_graph(graph).unwrap();
+        let node = builder.add_node(0);
+        builder.add_edge(node, 4).unwrap();
+        assert!(!builder.add_edge(2, 0).unwrap());
+        let extended = builder.finalize().unwrap();
+
+        assert_eq!(extended.node_count(), 6);
+        assert_eq!(extended.edge_count(), 7);
+        assert_eq!(extended.neighbors(4), &[2, 3, 5]);
+        assert_eq!(extended.label_frequency(0), 2);
+
+        assert!(matches!(
+            builder.add_edge(0, 6),
+            Err(Error::InvalidNode { node: 6, .. })
+        ));
+        assert!(matches!(
+            builder.add_edge(2, 2),
+            Err(Error::SelfLoop { node: 2 })
+        ));
+
+        let directed = Graph::from_gdl("(n0)-->(n1)", LoadConfig::default().directed()).unwrap();
+        let edge_labeled =
+            Graph::from_gdl("(n0)-[:E1]->(n1)", LoadConfig::default().edge_labeled()).unwrap();
+
+        assert!(matches!(
+            GraphBuilderMut::from_graph(&directed),
+            Err(Error::InvalidGraph { reason }) if reason.contains("direction")
+        ));
+        assert!(matches!(
+            GraphBuilderMut::from_graph(&edge_labeled),
+            Err(Error::InvalidGraph { reason }) if reason.contains("edge labels")
+        ));
+    }
+}
@@ -0,0 +1,307 @@
+//! A minimal GraphML reader that extracts node labels and edges.
+//!
+//! Only the subset of GraphML needed for labeled graphs is supported:
+//! `<key>` declarations, `<node>` and `<edge>` elements and `<data>`
+//! values of the configured label key. Everything else is skipped.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::Error;
+
+/// The node labels, indexed by dense node id, and the edges of a GraphML graph.
+pub(super) struct GraphMl {
+    pub(super) labels: Vec<usize>,
+    pub(super) edges: Vec<(usize, usize)>,
+}
+
+/// Parses the given GraphML document, reading node labels from the
+/// `<data>` element whose key has the given `id` or `attr.name`.
+///
+/// Node ids are remapped to `0..n` in document order.
+pub(super) fn parse(input: &str, label_key: &str) -> Result<GraphMl, Error> {
+    let mut label_keys = HashSet::new();
+    let mut node_ids = HashMap::new();
+    let mut node_names = Vec::new();
+    let mut labels = Vec::<Option<usize>>::new();
+    let mut edges = Vec::new();
+
+    // The node whose `<data>` elements we are currently reading.
+    let mut current_node = None;
+    // Set while reading the text of a label `<data>` element.
+    let mut label_text = None::<String>;
+
+    let mut rest = input;
+
+    while let Some(start) = rest.find('<') {
+        if let Some(text) = label_text.as_mut() {
+            text.push_str(&rest[..start]);
+        }
+        rest = &rest[start..];
+
+        // Skip comments, processing instructions and declarations.
+        if let Some(skipped) = skip(rest, "<!--", "-->")
+            .or_else(|| skip(rest, "<?", "?>"))
+            .or_else(|| skip(rest, "<!", ">"))
+        {
+            rest = skipped?;
+            continue;
+        }
+
+        let end = rest.find('>').ok_or_else(|| invalid("unterminated tag"))?;
+        let tag = Tag::parse(&rest[1..end])?;
+        rest = &rest[end + 1..];
+
+        match (tag.name, tag.kind) {
+            ("key", TagKind::Open | TagKind::Empty) => {
+                let applies_to_nodes = matches!(tag.attribute("for"), None | Some("node" | "all"));
+                let matches_key = tag.attribute("id") == Some(label_key)
+                    || tag.attribute("attr.name") == Some(label_key);
+
+                if applies_to_nodes && matches_key {
+                    label_keys.insert(tag.required_attribute("id")?);
+                }
+            }
+            ("node", kind @ (TagKind::Open | TagKind::Empty)) => {
+                let name = tag.required_attribute("id")?;
+                if node_ids.contains_key(&name) {
+                    return Err(invalid(format!("duplicate node '{}'", name)));
+                }
+                node_ids.insert(name.clone(), labels.len());
+                node_names.push(name);
+                labels.push(None);
+
+                if kind == TagKind::Open {
+                    current_node = Some(labels.len() - 1);
+                }
+            }
+            ("node", TagKind::Close) => current_node = None,
+            ("edge", TagKind::Open | TagKind::Empty) => {
+                edges.push((
+                    tag.required_attribute("source")?,
+                    tag.required_attribute("target")?,
+                ));
+            }
+            ("data", TagKind::Open) if current_node.is_some() => {
+                if label_keys.contains(tag.required_attribute("key")?.as_str()) {
+                    label_text = Some(String::new());
+                }
+            }
+            ("data", TagKind::Close) => {
+                if let (Some(text), Some(node)) = (label_text.take(), current_node) {
+                    let label = unescape(text.trim()).parse::<usize>().map_err(|_| {
+                        invalid(format!(
+                            "node '{}' has non-integer label '{}'",
+                            node_names[node],
+                            text.trim()
+                        ))
+                    })?;
+                    labels[node] = Some(label);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if label_keys.is_empty() {
+        return Err(invalid(format!("no node key '{}' declared", label_key)));
+    }
+
+    let labels = labels
+        .into_iter()
+        .enumerate()
+        .map(|(node, label)| {
+            label.ok_or_else(|| {
+                invalid(format!(
+                    "node '{}' has no label for key '{}'",
+                    node_names[node], label_key
+                ))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let node_id = |name: &String| {
+        node_ids
+            .get(name)
+            .copied()
+            .ok_or_else(|| invalid(format!("edge references unknown node '{}'", name)))
+    };
+
+    let edges = edges
+        .iter()
+        .map(|(source, target)| Ok((node_id(source)?, node_id(target)?)))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(GraphMl { labels, edges })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagKind {
+    Open,
+    Close,
+    Empty,
+}
+
+struct Tag<'a> {
+    name: &'a str,
+    kind: TagKind,
+    attributes: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> Tag<'a> {
+    /// Parses the content between `<` and `>`.
+    fn parse(content: &'a str) -> Result<Self, Error> {
+        let (kind, content) = if let Some(content) = content.strip_prefix('/') {
+            (TagKind::Close, content)
+        } else if let Some(content) = content.strip_suffix('/') {
+            (TagKind::Empty, content)
+        } else {
+            (TagKind::Open, content)
+        };
+
+        let content = content.trim();
+        let name_end = content.find(char::is_whitespace).unwrap_or(content.len());
+        let name = &content[..name_end];
+        // Ignore namespace prefixes, e.g. `<graphml:node>`.
+        let name = name.rsplit(':').next().unwrap_or(name);
+
+        let mut attributes = Vec::new();
+        let mut rest = content[name_end..].trim_start();
+
+        while !rest.is_empty() {
+            let eq = rest
+                .find('=')
+                .ok_or_else(|| invalid(format!("malformed attribute in <{}>", name)))?;
+            let key = rest[..eq].trim();
+            let value = rest[eq + 1..].trim_start();
+            let quote = value
+                .chars()
+                .next()
+                .filter(|c| *c == '"' || *c == '\'')
+                .ok_or_else(|| invalid(format!("unquoted attribute '{}' in <{}>", key, name)))?;
+            let value_end = value[1..].find(quote).ok_or_else(|| {
+                invalid(format!("unterminated attribute '{}' in <{}>", key, name))
+            })?;
+
+            attributes.push((key, &value[1..value_end + 1]));
+            rest = value[value_end + 2..].trim_start();
+        }
+
+        Ok(Self {
+            name,
+            kind,
+            attributes,
+        })
+    }
+
+    fn attribute(&self, key: &str) -> Option<&'a str> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| *v)
+    }
+
+    fn required_attribute(&self, key: &str) -> Result<String, Error> {
+        self.attribute(key)
+            .map(unescape)
+            .ok_or_else(|| invalid(format!("<{}> is missing attribute '{}'", self.name, key)))
+    }
+}
+
+/// Skips a section that starts with `open`, returning the remaining input,
+/// or `None` if `input` does not start with `open`.
+fn skip<'a>(input: &'a str, open: &str, close: &str) -> Option<Result<&'a str, Error>> {
+    if !input.starts_with(open) {
+        return None;
+    }
+    Some(
+        input[open.len()..]
+            .find(close)
+            .map(|end| &input[open.len() + end + close.len()..])
+            .ok_or_else(|| invalid(format!("missing '{}'", close))),
+    )
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn invalid(reason: impl Into<String>) -> Error {
+    Error::ParseGraphMl {
+        reason: reason.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use trim_margin::MarginTrimmable;
+
+    #[test]
+    fn test_parse() {
+        let input = r#"
+        |<?xml version="1.0" encoding="UTF-8"?>
+        |<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+        |  <!-- labels are stored as integers -->
+        |  <key id="d0" for="node" attr.name="label" attr.type="int"/>
+        |  <key id="d1" for="node" attr.name="name" attr.type="string"/>
+        |  <graph id="G" edgedefault="undirected">
+        |    <node id="b"><data key="d1">&lt;b&gt;</data><data key="d0"> 1 </data></node>
+        |    <node id="a">
+        |      <data key="d0">0</data>
+        |    </node>
+        |    <edge source="a" target="b"/>
+        |  </graph>
+        |</graphml>
+        |"#
+        .trim_margin()
+        .unwrap();
+
+        let graph = parse(&input, "label").unwrap();
+
+        assert_eq!(graph.labels, vec![1, 0]);
+        assert_eq!(graph.edges, vec![(1, 0)]);
+
+        // The key can also be referenced by its id.
+        assert_eq!(parse(&input, "d0").unwrap().labels, vec![1, 0]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let missing_label = r#"
+        |<graphml>
+        |  <key id="label" for="node"/>
+        |  <graph><node id="a"><data key="label">0</data></node><node id="b"/></graph>
+        |</graphml>
+        |"#
+        .trim_margin()
+        .unwrap();
+
+        assert_eq!(
+            parse(&missing_label, "label").err().unwrap().to_string(),
+            "error while parsing GraphML: node 'b' has no label for key 'label'"
+        );
+        assert_eq!(
+            parse(&missing_label, "weight").err().unwrap().to_string(),
+            "error while parsing GraphML: no node key 'weight' declared"
+        );
+
+        let unknown_node = r#"
+        |<graphml>
+        |  <key id="label" for="node"/>
+        |  <graph><node id="a"><data key="label">0</data></node><edge source="a" target="c"/></graph>
+        |</graphml>
+        |"#
+        .trim_margin()
+        .unwrap();
+
+        assert_eq!(
+            parse(&unknown_node, "label").err().unwrap().to_string(),
+            "error while parsing GraphML: edge references unknown node 'c'"
+        );
+    }
+}
@@ -1,4 +1,6 @@
-use crate::Graph;
+use std::cmp::Reverse;
+
+use crate::{enumerate::intersect_sorted, graph::ANY_LABEL, Graph};
 
 /// The k-core of a graph is a maximal subgraph in which
 /// each node has at least degree k. The coreness of a
@@ -80,40 +82,309 @@ pub fn coreness(graph: &Graph) -> Vec<usize> {
     core_table
 }
 
+/// Counts the triangles in the graph, ignoring node labels.
+///
+/// For each edge `(u, v)` with `u < v`, the sorted adjacency lists of `u`
+/// and `v` are intersected and each common neighbor `w > v` closes a
+/// triangle. This counts each triangle exactly once.
+pub fn count_triangles(graph: &Graph) -> usize {
+    let mut triangle_count = 0;
+    let mut common = Vec::new();
+
+    for u in 0..graph.node_count() {
+        let u_neighbors = graph.neighbors(u);
+
+        for &v in u_neighbors.iter().filter(|&&v| v > u) {
+            let v_neighbors = graph.neighbors(v);
+
+            // Both lists are sorted, so we can skip all nodes up to v.
+            let u_start = u_neighbors.partition_point(|&w| w <= v);
+            let v_start = v_neighbors.partition_point(|&w| w <= v);
+
+            intersect_sorted(
+                &u_neighbors[u_start..],
+                &v_neighbors[v_start..],
+                &mut common,
+            );
+            triangle_count += common.len();
+        }
+    }
+
+    triangle_count
+}
+
+/// Computes the connected components of the graph, ignoring edge direction.
+///
+/// Returns the component id of each node. Components are numbered `0..k`
+/// in ascending order of their smallest node, i.e. node `0` is always in
+/// component `0`. Isolated nodes form their own component.
+pub fn connected_components(graph: &Graph) -> Vec<usize> {
+    let mut components = vec![usize::MAX; graph.node_count()];
+    let mut queue = std::collections::VecDeque::new();
+    let mut component_count = 0;
+
+    for start in 0..graph.node_count() {
+        if components[start] != usize::MAX {
+            continue;
+        }
+
+        components[start] = component_count;
+        queue.push_back(start);
+
+        while let Some(node) = queue.pop_front() {
+            for &neighbor in graph.neighbors(node) {
+                if components[neighbor] == usize::MAX {
+                    components[neighbor] = component_count;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        component_count += 1;
+    }
+
+    components
+}
+
+/// Returns the sorted nodes of the connected component with the most
+/// nodes, e.g. to extract it via [`Graph::subgraph`].
+///
+/// If several components have the same size, the one containing the
+/// smallest node is returned. Returns an empty vector for an empty graph.
+pub fn largest_component(graph: &Graph) -> Vec<usize> {
+    let components = connected_components(graph);
+
+    let mut sizes = vec![0_usize; components.iter().max().map_or(0, |&max| max + 1)];
+    for &component in &components {
+        sizes[component] += 1;
+    }
+
+    // `max_by_key` returns the last maximum, so we look for the first one.
+    let largest = match sizes.iter().max() {
+        Some(max) => sizes.iter().position(|size| size == max),
+        None => return Vec::new(),
+    };
+
+    components
+        .iter()
+        .enumerate()
+        .filter(|(_, &component)| Some(component) == largest)
+        .map(|(node, _)| node)
+        .collect()
+}
+
+/// Returns the data edges used by an embedding of the query graph.
+///
+/// For each query edge `(u, w)` with `u < w`, in ascending order, the data
+/// edge `(embedding[u], embedding[w])` is emitted.
+pub fn embedding_edges(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    embedding: &[usize],
+) -> Vec<(usize, usize)> {
+    let mut edges = Vec::with_capacity(query_graph.edge_count());
+
+    for u in 0..query_graph.node_count() {
+        for &w in query_graph.neighbors(u).iter().filter(|&&w| w > u) {
+            debug_assert!(data_graph.exists(embedding[u], embedding[w]));
+            edges.push((embedding[u], embedding[w]));
+        }
+    }
+
+    edges
+}
+
+/// Relabels the graph such that more frequent labels get smaller ids and
+/// renumbers the nodes such that the nodes of each label are contiguous,
+/// e.g. to improve the locality of filters that iterate over the nodes of
+/// a label.
+///
+/// Labels are ranked by descending frequency, ties by ascending label.
+/// Nodes are ordered by their new label and then by their id, wildcard
+/// nodes come last. Returns the relabeled graph and the permutation, i.e.
+/// node `n` of the given graph is node `permutation[n]` of the relabeled
+/// one. Query graphs need to be relabeled with the same label mapping,
+/// i.e. label `graph.label(n)` becomes `relabeled.label(permutation[n])`.
+pub fn relabel_by_frequency(graph: &Graph) -> (Graph, Vec<usize>) {
+    // The label map is indexed by the position of a label in the labels
+    // present, such that it is sized by the number of distinct labels.
+    let labels = graph.labels_present();
+    let mut ranking = (0..labels.len()).collect::<Vec<_>>();
+    ranking.sort_by_key(|&idx| (Reverse(graph.label_frequency(labels[idx])), labels[idx]));
+
+    let mut label_map = vec![0; labels.len()];
+    for (new_label, &idx) in ranking.iter().enumerate() {
+        label_map[idx] = new_label;
+    }
+
+    let new_label = |node: usize| match graph.label(node) {
+        ANY_LABEL => ANY_LABEL,
+        label => label_map[labels.partition_point(|&present| present < label)],
+    };
+
+    let mut nodes = (0..graph.node_count()).collect::<Vec<_>>();
+    nodes.sort_by_key(|&node| (new_label(node), node));
+
+    let mut permutation = vec![0; nodes.len()];
+    for (new_node, &node) in nodes.iter().enumerate() {
+        permutation[node] = new_node;
+    }
+
+    let relabeled = graph
+        .relabel(&permutation, &label_map)
+        .expect("permutation and label map are valid");
+
+    (relabeled, permutation)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::graph::GdlGraph;
     use trim_margin::MarginTrimmable;
 
+    // d(n0) = 1
+    // d(n1) = 4
+    // d(n2) = 3
+    // d(n3) = 2
+    // d(n4) = 4
+    const TEST_GRAPH: &str = "
+        |(n0:L0)
+        |(n1:L0)
+        |(n2:L0)
+        |(n3:L0)
+        |(n4:L0)
+        |(n0)-->(n1)
+        |(n1)-->(n2)
+        |(n1)-->(n3)
+        |(n2)-->(n4)
+        |(n3)-->(n4)
+        |(n4)-->(n1)
+        |(n4)-->(n2)
+        |";
+
+    fn graph(gdl: &str) -> GdlGraph {
+        gdl.trim_margin().unwrap().parse::<GdlGraph>().unwrap()
+    }
+
     #[test]
     fn test_coreness() {
-        // d(n0) = 1
-        // d(n1) = 4
-        // d(n2) = 3
-        // d(n3) = 2
-        // d(n4) = 4
-        let graph = "
-            |(n0:L0)
-            |(n1:L0)
-            |(n2:L0)
-            |(n3:L0)
-            |(n4:L0)
-            |(n0)-->(n1)
-            |(n1)-->(n2)
-            |(n1)-->(n3)
-            |(n2)-->(n4)
-            |(n3)-->(n4)
-            |(n4)-->(n1)
-            |(n4)-->(n2)
-            |"
-        .trim_margin()
-        .unwrap()
-        .parse::<GdlGraph>()
-        .unwrap();
+        let graph = graph(TEST_GRAPH);
 
         let core_table = coreness(&graph);
 
         assert_eq!(core_table, vec![1, 2, 2, 2, 2])
     }
+
+    #[test]
+    fn test_count_triangles() {
+        let graph = graph(TEST_GRAPH);
+
+        // (n1, n2, n4) and (n1, n3, n4)
+        assert_eq!(count_triangles(&graph), 2);
+    }
+
+    #[test]
+    fn test_embedding_edges() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph("(n0:L0),(n1:L0),(n2:L0),(n0)-->(n1),(n1)-->(n2),(n2)-->(n0)");
+
+        // maps the query triangle to (n4, n1, n2)
+        let edges = embedding_edges(&data_graph, &query_graph, &[4, 1, 2]);
+
+        assert_eq!(edges, vec![(4, 1), (4, 2), (1, 2)]);
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let triangles = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L1),(n3:L0),(n4:L1),(n5:L1)
+            |(n0)-->(n2),(n2)-->(n4),(n4)-->(n0)
+            |(n1)-->(n3),(n3)-->(n5),(n5)-->(n1)
+            |",
+        );
+
+        let components = connected_components(&triangles);
+
+        assert_eq!(components, vec![0, 1, 0, 1, 0, 1]);
+        for component in 0..2 {
+            assert_eq!(components.iter().filter(|&&c| c == component).count(), 3);
+        }
+
+        // Both triangles have 3 nodes, the first one contains n0.
+        assert_eq!(largest_component(&triangles), vec![0, 2, 4]);
+
+        let graph = graph("(n0:L0),(n1:L0),(n2:L0),(n3:L0),(n1)-->(n2),(n2)-->(n3)");
+
+        assert_eq!(connected_components(&graph), vec![0, 1, 1, 1]);
+        assert_eq!(largest_component(&graph), vec![1, 2, 3]);
+        assert_eq!(
+            graph
+                .subgraph(&largest_component(&graph))
+                .unwrap()
+                .edge_count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_relabel_by_frequency() {
+        let data_graph = graph(
+            "
+            |(n0:L1),(n1:L2),(n2:L0),(n3:L2),(n4:L2),(n5:L1)
+            |(n0)-->(n1),(n1)-->(n2),(n2)-->(n3),(n3)-->(n4),(n4)-->(n5)
+            |(n5)-->(n0),(n1)-->(n4),(n0)-->(n3)
+            |",
+        );
+
+        let (relabeled, permutation) = relabel_by_frequency(&data_graph);
+
+        // L2 -> 0, L1 -> 1, L0 -> 2
+        assert_eq!(permutation, vec![3, 0, 5, 1, 2, 4]);
+        assert_eq!(relabeled.nodes_by_label(0), &[0, 1, 2]);
+        assert_eq!(relabeled.nodes_by_label(1), &[3, 4]);
+        assert_eq!(relabeled.nodes_by_label(2), &[5]);
+        assert_eq!(relabeled.edge_count(), data_graph.edge_count());
+        assert!(relabeled.validate().is_ok());
+
+        let mut sorted = permutation.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..data_graph.node_count()).collect::<Vec<_>>());
+
+        for node in 0..data_graph.node_count() {
+            for &neighbor in data_graph.neighbors(node) {
+                assert!(relabeled.exists(permutation[node], permutation[neighbor]));
+            }
+        }
+
+        let mut label_map = vec![0; data_graph.label_count()];
+        for node in 0..data_graph.node_count() {
+            label_map[data_graph.label(node)] = relabeled.label(permutation[node]);
+        }
+
+        let queries = [
+            "(n0:L2),(n1:L2),(n0)-->(n1)",
+            "(n0:L1),(n1:L2),(n2:L1),(n0)-->(n1),(n1)-->(n2)",
+            "(n0:L1),(n1:L2),(n2:L0),(n3:L2),(n0)-->(n1),(n1)-->(n2),(n2)-->(n3),(n3)-->(n0)",
+            "(n0:L2),(n1:L2),(n2:L1),(n0)-->(n1),(n1)-->(n2),(n2)-->(n0)",
+        ];
+
+        for gdl in queries.iter() {
+            let query_graph = graph(gdl);
+            let labels = (0..query_graph.node_count())
+                .map(|node| label_map[query_graph.label(node)])
+                .collect::<Vec<_>>();
+            let relabeled_query = query_graph.clone_with_labels(&labels).unwrap();
+
+            for config in [crate::Config::default(), crate::Filter::Nlf.into()].iter() {
+                assert_eq!(
+                    crate::find(&relabeled, &relabeled_query, *config),
+                    crate::find(&data_graph, &query_graph, *config),
+                    "query: {}",
+                    gdl
+                );
+            }
+        }
+    }
 }
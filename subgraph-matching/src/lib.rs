@@ -13,17 +13,22 @@ The corresponding [paper](https://dl.acm.org/doi/10.1145/3318464.3380581) was pu
 MIT
 */
 #![allow(dead_code)]
+mod bitset;
 pub mod config;
 pub mod enumerate;
 pub mod filter;
 pub mod graph;
 pub mod graph_ops;
+pub mod matcher;
 pub mod order;
 
-use std::io;
+use rayon::prelude::*;
+use std::{cmp::Ordering, collections::HashSet, io, ops::ControlFlow};
 
 pub use crate::graph::Graph;
-pub use config::{Config, Enumeration, Filter, Order};
+pub use config::{CandidateBackend, Config, ConfigBuilder, Enumeration, Filter, Order};
+pub use enumerate::{Embeddings, Progress, Summary};
+pub use matcher::Matcher;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -43,10 +48,86 @@ pub enum Error {
         #[from]
         source: ::graph::Error,
     },
+    /// A GDL graph whose labels or properties can't be read as numbers.
+    #[error("invalid GDL graph: {reason}")]
+    InvalidGdlGraph { reason: String },
+    #[error("error while parsing GraphML: {reason}")]
+    ParseGraphMl { reason: String },
+    #[error("invalid binary graph file: {reason}")]
+    InvalidBinaryGraph { reason: String },
+    /// A .graph file whose lines contradict its header or node degrees.
+    #[error("invalid graph file: {reason}")]
+    InvalidGraphFile { reason: String },
+    #[error("invalid graph: {reason}")]
+    InvalidGraph { reason: String },
+    #[error("node {node} is out of range for a graph with {node_count} nodes")]
+    InvalidNode { node: usize, node_count: usize },
+    /// Graphs are rejected while loading if they contain a self-loop, since
+    /// it would be stored twice in the adjacency list of its node.
+    #[error("node {node} has a self-loop, which is not supported")]
+    SelfLoop { node: usize },
+    #[error("expected {expected} labels, got {actual}")]
+    InvalidLabelCount { expected: usize, actual: usize },
+    #[error("no candidates found for at least one query node")]
+    NoCandidates,
+    /// The orders and enumerations need a query node to start from.
+    #[error("query graph has no nodes")]
+    EmptyQuery,
+    #[error("invalid matching order: {reason}")]
+    InvalidOrder { reason: String },
+    #[error("query graph is not connected, which requires the RI order")]
+    DisconnectedQuery,
+    #[error("neighbor label frequencies have not been loaded")]
+    MissingNeighborLabelFrequencies,
+    /// The candidate product exceeds [`Config::max_candidate_product`].
+    #[error("candidate product {estimate} exceeds the budget")]
+    TooExpensive { estimate: u128 },
 }
 
 pub fn find(data_graph: &Graph, query_graph: &Graph, config: impl Into<Config>) -> usize {
-    find_with(data_graph, query_graph, |_| {}, config)
+    find_checked(data_graph, query_graph, config).unwrap_or(0)
+}
+
+/// Counts all embeddings like [`find`], but returns [`Error::NoCandidates`]
+/// if the filter already rules out any match. This allows callers to
+/// distinguish a failed filter from an enumeration that found no embedding.
+pub fn find_checked(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    config: impl Into<Config>,
+) -> Result<usize, Error> {
+    let config = config.into();
+
+    let (candidates, order) = prepare(data_graph, query_graph, config)?;
+
+    let summary = run_count(data_graph, query_graph, &candidates, &order, config);
+
+    Ok(summary.embedding_count)
+}
+
+/// Checks if the query graph occurs in the data graph at all.
+///
+/// Stops the enumeration as soon as the first embedding has been found. If
+/// the filter rules out any match, the enumeration is skipped entirely.
+pub fn contains_match(data_graph: &Graph, query_graph: &Graph, config: impl Into<Config>) -> bool {
+    let config = config.into();
+
+    let (candidates, order) = match prepare(data_graph, query_graph, config) {
+        Ok(prepared) => prepared,
+        Err(_) => return false,
+    };
+
+    let summary = run_enumeration(
+        data_graph,
+        query_graph,
+        &candidates,
+        &order,
+        None::<enumerate::Comparator>,
+        |_: &[usize]| ControlFlow::Break(()),
+        config,
+    );
+
+    summary.embedding_count > 0
 }
 
 pub fn find_with<F>(
@@ -55,35 +136,744 @@ pub fn find_with<F>(
     action: F,
     config: impl Into<Config>,
 ) -> usize
+where
+    F: FnMut(&[usize]),
+{
+    find_with_summary(data_graph, query_graph, action, config).embedding_count
+}
+
+/// Finds all embeddings like [`find_with`], but emits each embedding as
+/// `(query_node, data_node)` pairs ordered by query node.
+///
+/// The pairs are written into a buffer that is reused across embeddings.
+/// Use [`find_with`] to avoid the extra copy.
+pub fn find_with_pairs<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    mut action: F,
+    config: impl Into<Config>,
+) -> usize
+where
+    F: FnMut(&[(usize, usize)]),
+{
+    let mut pairs = Vec::with_capacity(query_graph.node_count());
+
+    find_with(
+        data_graph,
+        query_graph,
+        |embedding| {
+            pairs.clear();
+            pairs.extend(embedding.iter().copied().enumerate());
+            action(&pairs);
+        },
+        config,
+    )
+}
+
+/// Collects all embeddings of the query graph in the data graph.
+///
+/// Each embedding maps the query node at index `i` to the data node at
+/// index `i`. The embeddings are returned in the order in which they are
+/// emitted by [`find_with`].
+pub fn find_all(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    config: impl Into<Config>,
+) -> Vec<Vec<usize>> {
+    let mut embeddings = Vec::new();
+    find_with(
+        data_graph,
+        query_graph,
+        |embedding| embeddings.push(embedding.to_vec()),
+        config,
+    );
+    embeddings
+}
+
+/// Collects all embeddings like [`find_all`], but represents each embedding
+/// as `(query_node, data_node)` pairs ordered by query node.
+pub fn find_all_mapped(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    config: impl Into<Config>,
+) -> Vec<Vec<(usize, usize)>> {
+    let mut embeddings = Vec::new();
+    find_with(
+        data_graph,
+        query_graph,
+        |embedding| embeddings.push(embedding.iter().copied().enumerate().collect()),
+        config,
+    );
+    embeddings
+}
+
+/// Returns a lazy iterator over all embeddings of the query graph in the
+/// data graph, e.g. to only collect the first few of them using `take`.
+///
+/// The embeddings are yielded in the same order as they are emitted by
+/// [`find_with`]. The iterator ends early once the edge check budget of the
+/// config is exhausted.
+pub fn iter<'a>(
+    data_graph: &'a Graph,
+    query_graph: &Graph,
+    config: impl Into<Config>,
+) -> impl Iterator<Item = Vec<usize>> + 'a {
+    let config = config.into();
+
+    let embeddings = prepare(data_graph, query_graph, config)
+        .ok()
+        .map(|(candidates, order)| {
+            Embeddings::with_options(
+                data_graph,
+                query_graph,
+                &candidates,
+                &order,
+                enumeration_options(config),
+            )
+        });
+
+    embeddings
+        .into_iter()
+        .flatten()
+        .take(config.limit.unwrap_or(usize::MAX))
+}
+
+/// Folds all embeddings of the query graph in the data graph into a single
+/// value without materializing them, e.g. to count embeddings grouped by
+/// the label of a matched node.
+pub fn find_fold<A, F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    init: A,
+    mut fold: F,
+    config: impl Into<Config>,
+) -> A
+where
+    F: FnMut(A, &[usize]) -> A,
+{
+    let mut acc = Some(init);
+
+    find_with(
+        data_graph,
+        query_graph,
+        |embedding| acc = acc.take().map(|acc| fold(acc, embedding)),
+        config,
+    );
+
+    acc.expect("accumulator is always present")
+}
+
+/// Finds all embeddings like [`find_with`], but returns a [`Summary`] that
+/// also tells if the enumeration stopped early because it exceeded the edge
+/// check budget defined in [`Config::max_edge_checks`].
+pub fn find_with_summary<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    action: F,
+    config: impl Into<Config>,
+) -> Summary
 where
     F: FnMut(&[usize]),
 {
     let config = config.into();
 
-    let mut candidates = match config.filter {
-        Filter::Ldf => filter::ldf_filter(data_graph, query_graph).unwrap_or_default(),
-        Filter::Gql => filter::gql_filter(data_graph, query_graph).unwrap_or_default(),
-        Filter::Nlf => filter::nlf_filter(data_graph, query_graph).unwrap_or_default(),
+    let (candidates, order) = match prepare(data_graph, query_graph, config) {
+        Ok(prepared) => prepared,
+        Err(_) => return Summary::default(),
     };
 
-    // Sort candidates to support set intersections
-    candidates.sort();
+    run_enumeration(
+        data_graph,
+        query_graph,
+        &candidates,
+        &order,
+        None::<enumerate::Comparator>,
+        enumerate::continuing(action),
+        config,
+    )
+}
+
+/// Finds all embeddings of the query graph in the data graph and emits them
+/// to `action` in an order that is controlled by `cmp`.
+///
+/// The comparator is called as `cmp(query_node, data_node_1, data_node_2)`
+/// and determines in which order the valid candidates of a query node are
+/// visited. Embeddings are still emitted one root candidate after the other,
+/// i.e., the order is only customized within each root's subtree. This avoids
+/// materializing all embeddings, which a full sort would require.
+pub fn find_with_ordered<C, F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    cmp: C,
+    action: F,
+    config: impl Into<Config>,
+) -> usize
+where
+    C: FnMut(usize, usize, usize) -> Ordering,
+    F: FnMut(&[usize]),
+{
+    let config = config.into();
+
+    let (candidates, order) = match prepare(data_graph, query_graph, config) {
+        Ok(prepared) => prepared,
+        Err(_) => return 0,
+    };
+
+    run_enumeration(
+        data_graph,
+        query_graph,
+        &candidates,
+        &order,
+        Some(cmp),
+        enumerate::continuing(action),
+        config,
+    )
+    .embedding_count
+}
+
+/// Finds all embeddings like [`find_with`], but uses the given matching order
+/// instead of computing one, e.g. an order that is known to perform well.
+///
+/// The order needs to be a permutation of the query nodes in which each node
+/// after the first is adjacent to at least one of its predecessors. Otherwise,
+/// [`Error::InvalidOrder`] is returned.
+pub fn find_with_order<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    order: &[usize],
+    action: F,
+    config: impl Into<Config>,
+) -> Result<usize, Error>
+where
+    F: FnMut(&[usize]),
+{
+    let config = config.into();
+
+    order::validate_order(query_graph, order)?;
+
+    let candidates = match check_query_node_count(data_graph, query_graph, config)
+        .and_then(|_| filter_candidates(data_graph, query_graph, config))
+    {
+        Ok(candidates) => candidates,
+        Err(Error::NoCandidates) => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let summary = run_enumeration(
+        data_graph,
+        query_graph,
+        &candidates,
+        order,
+        None::<enumerate::Comparator>,
+        enumerate::continuing(action),
+        config,
+    );
+
+    Ok(summary.embedding_count)
+}
+
+/// Finds all embeddings like [`find_with`], but uses the given candidates
+/// and matching order instead of computing them, e.g. to measure the filter,
+/// order and enumeration phases separately.
+///
+/// The candidates of each query node need to be sorted and the order needs
+/// to be valid, see [`find_with_order`]. The filter and order of the config
+/// are ignored.
+pub fn find_with_candidates<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &filter::Candidates,
+    order: &[usize],
+    action: F,
+    config: impl Into<Config>,
+) -> usize
+where
+    F: FnMut(&[usize]),
+{
+    run_enumeration(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        None::<enumerate::Comparator>,
+        enumerate::continuing(action),
+        config.into(),
+    )
+    .embedding_count
+}
+
+/// Finds all embeddings like [`find_with`], but computes the candidates
+/// using the given filter instead of the filter of the config, e.g. a
+/// closure that prunes data nodes by domain-specific predicates.
+pub fn find_with_filter<C, F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    filter: &C,
+    action: F,
+    config: impl Into<Config>,
+) -> usize
+where
+    C: filter::CandidateFilter + ?Sized,
+    F: FnMut(&[usize]),
+{
+    let config = config.into();
+
+    let prepared = prepare_filtered(data_graph, query_graph, config, || {
+        let mut candidates = filter
+            .filter(data_graph, query_graph)
+            .ok_or(Error::NoCandidates)?;
+        candidates.sort();
+        Ok(candidates)
+    });
+
+    let (candidates, order) = match prepared {
+        Ok(prepared) => prepared,
+        Err(_) => return 0,
+    };
+
+    run_enumeration(
+        data_graph,
+        query_graph,
+        &candidates,
+        &order,
+        None::<enumerate::Comparator>,
+        enumerate::continuing(action),
+        config,
+    )
+    .embedding_count
+}
+
+/// Finds all embeddings like [`find_with`], but enumerates them using the
+/// given enumerator instead of the enumeration of the config, e.g. an
+/// experimental search strategy.
+///
+/// The filter and order of the config are applied as usual. If the
+/// enumerator is an [`Enumeration`], it replaces the enumeration of the
+/// config, e.g. to filter candidates for homomorphisms. The limit and edge
+/// check budget are up to the enumerator.
+pub fn find_with_enumerator<E, F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    enumerator: &E,
+    mut action: F,
+    config: impl Into<Config>,
+) -> usize
+where
+    E: enumerate::Enumerator + ?Sized,
+    F: FnMut(&[usize]),
+{
+    let mut config = config.into();
+    if let Some(enumeration) = enumerator.enumeration() {
+        config.enumeration = enumeration;
+    }
+
+    let (candidates, order) = match prepare(data_graph, query_graph, config) {
+        Ok(prepared) => prepared,
+        Err(_) => return 0,
+    };
+
+    enumerator.enumerate(data_graph, query_graph, &candidates, &order, &mut action)
+}
+
+/// Finds up to `k` distinct sets of data nodes that the query graph can be
+/// mapped to.
+///
+/// Embeddings that only differ in how the query nodes are assigned to the
+/// same data nodes, e.g. due to automorphisms of the query graph, lead to a
+/// single set. Each set is sorted by node id and the sets are returned in the
+/// order in which they have been found. The enumeration stops as soon as `k`
+/// distinct sets have been found.
+pub fn find_distinct_sets(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    k: usize,
+    config: impl Into<Config>,
+) -> Vec<Vec<usize>> {
+    let config = config.into();
+    let mut seen = HashSet::new();
+    let mut sets = Vec::new();
+
+    if k == 0 {
+        return sets;
+    }
+
+    let (candidates, order) = match prepare(data_graph, query_graph, config) {
+        Ok(prepared) => prepared,
+        Err(_) => return sets,
+    };
+
+    run_enumeration(
+        data_graph,
+        query_graph,
+        &candidates,
+        &order,
+        None::<enumerate::Comparator>,
+        |embedding: &[usize]| {
+            let mut set = embedding.to_vec();
+            set.sort_unstable();
+
+            if seen.insert(set.clone()) {
+                sets.push(set);
+            }
+
+            if sets.len() == k {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        },
+        config,
+    );
+
+    sets
+}
+
+/// Counts the embeddings of the query graph in each of the data graphs,
+/// e.g. to find the molecules of a database that contain a pattern.
+///
+/// The data graphs are matched independently and in parallel. The counts are
+/// returned in the order of the data graphs, see [`find`].
+pub fn find_in_many(
+    data_graphs: &[Graph],
+    query_graph: &Graph,
+    config: impl Into<Config>,
+) -> Vec<usize> {
+    let config = config.into();
+
+    data_graphs
+        .par_iter()
+        .map(|data_graph| find(data_graph, query_graph, config))
+        .collect()
+}
+
+/// Estimates the number of embeddings like [`find`] from `samples` random
+/// walks along the matching order, see [`enumerate::estimate`].
+///
+/// The estimate is unbiased, i.e. it matches the exact count on average,
+/// but a single estimate has a variance that shrinks with the number of
+/// samples. The walks follow the GQL enumeration, so the limit and the edge
+/// check budget of the config are ignored and VF2 and LFTJ estimate the same
+/// embeddings as GQL. Returns `0.0` if the filter rules out any match.
+pub fn estimate_count<R>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    config: impl Into<Config>,
+    samples: usize,
+    rng: &mut R,
+) -> f64
+where
+    R: rand::Rng,
+{
+    let config = config.into();
+
+    let (candidates, order) = match prepare(data_graph, query_graph, config) {
+        Ok(prepared) => prepared,
+        Err(_) => return 0.0,
+    };
+
+    enumerate::estimate_with_options(
+        data_graph,
+        query_graph,
+        &candidates,
+        &order,
+        enumeration_options(config),
+        samples,
+        rng,
+    )
+}
+
+/// Runs the enumeration phase for the given config.
+fn run_enumeration<C, F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &filter::Candidates,
+    order: &[usize],
+    cmp: Option<C>,
+    action: F,
+    config: Config,
+) -> Summary
+where
+    C: FnMut(usize, usize, usize) -> Ordering,
+    F: FnMut(&[usize]) -> ControlFlow<()>,
+{
+    run_enumeration_with(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        cmp,
+        &mut enumerate::VisitedSet::new(data_graph.node_count()),
+        action,
+        config,
+    )
+}
+
+/// Runs the enumeration phase like [`run_enumeration`], but only counts the
+/// embeddings, which avoids mapping the last query node if possible.
+fn run_count(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &filter::Candidates,
+    order: &[usize],
+    config: Config,
+) -> Summary {
+    // VF2, LFTJ and the limit need to visit each embedding.
+    if config.enumeration == Enumeration::Vf2
+        || config.enumeration == Enumeration::Lftj
+        || config.limit.is_some()
+    {
+        return run_enumeration(
+            data_graph,
+            query_graph,
+            candidates,
+            order,
+            None::<enumerate::Comparator>,
+            enumerate::continuing(|_| {}),
+            config,
+        );
+    }
+
+    enumerate::gql_with_options(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        None::<enumerate::Arrangement>,
+        enumerate::Options {
+            count_only: true,
+            ..enumeration_options(config)
+        },
+        enumerate::continuing(|_| {}),
+    )
+}
+
+/// Returns the options of the GQL enumeration for the given config.
+fn enumeration_options(config: Config) -> enumerate::Options<'static> {
+    enumerate::Options {
+        max_edge_checks: config.max_edge_checks,
+        induced: config.enumeration == Enumeration::GqlInduced,
+        homomorphism: config.enumeration == Enumeration::GqlHomomorphism,
+        failing_sets: config.enumeration == Enumeration::GqlFailingSet,
+        edge_weight_bounds: config.edge_weight_bounds,
+        ..enumerate::Options::default()
+    }
+}
+
+/// Runs the enumeration phase like [`run_enumeration`], but reuses the given
+/// set to track visited data nodes.
+#[allow(clippy::too_many_arguments)]
+fn run_enumeration_with<C, F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &filter::Candidates,
+    order: &[usize],
+    cmp: Option<C>,
+    visited: &mut enumerate::VisitedSet,
+    mut action: F,
+    config: Config,
+) -> Summary
+where
+    C: FnMut(usize, usize, usize) -> Ordering,
+    F: FnMut(&[usize]) -> ControlFlow<()>,
+{
+    let limit = config.limit.unwrap_or(usize::MAX);
+    if limit == 0 {
+        return Summary::default();
+    }
+    let mut embedding_count = 0;
+    let action = |embedding: &[usize]| {
+        embedding_count += 1;
+        match action(embedding) {
+            ControlFlow::Continue(()) if embedding_count == limit => ControlFlow::Break(()),
+            flow => flow,
+        }
+    };
+
+    // VF2 visits the candidates in their given order.
+    if config.enumeration == Enumeration::Vf2 {
+        return enumerate::vf2_with_budget(
+            data_graph,
+            query_graph,
+            candidates,
+            order,
+            config.max_edge_checks,
+            config.edge_weight_bounds,
+            action,
+        );
+    }
+    // LFTJ visits the values of each intersection in ascending order.
+    if config.enumeration == Enumeration::Lftj {
+        return enumerate::lftj_with_budget(
+            data_graph,
+            query_graph,
+            candidates,
+            order,
+            config.max_edge_checks,
+            config.edge_weight_bounds,
+            action,
+        );
+    }
+
+    enumerate::gql_with_visited(
+        data_graph,
+        query_graph,
+        candidates,
+        order,
+        cmp.map(enumerate::sorting),
+        enumeration_options(config),
+        visited,
+        action,
+    )
+}
+
+/// Runs the filter and order phase for the given config.
+///
+/// Returns [`Error::EmptyQuery`] if the query graph has no nodes and
+/// [`Error::NoCandidates`] if it has more nodes than the data graph or a
+/// label that is absent from it, or if the filter found no
+/// candidates for at least one query node, in which case there can't be any
+/// embedding. Returns [`Error::DisconnectedQuery`] if the query graph is disconnected
+/// and the order requires each node to be adjacent to a predecessor.
+fn prepare(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    config: Config,
+) -> Result<(filter::Candidates, Vec<usize>), Error> {
+    prepare_with(data_graph, query_graph, config, None)
+}
+
+/// Runs the filter and order phase like [`prepare`], but uses the given
+/// coreness of the data graph instead of computing it, if present.
+fn prepare_with(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    config: Config,
+    data_coreness: Option<&[usize]>,
+) -> Result<(filter::Candidates, Vec<usize>), Error> {
+    prepare_filtered(data_graph, query_graph, config, || {
+        filter_candidates_with(data_graph, query_graph, config, data_coreness)
+    })
+}
+
+/// Returns [`Error::EmptyQuery`] if the query graph has no nodes and
+/// [`Error::NoCandidates`] if it has more nodes than the data graph.
+fn check_query_node_count(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    config: Config,
+) -> Result<(), Error> {
+    if query_graph.node_count() == 0 {
+        return Err(Error::EmptyQuery);
+    }
+
+    // An embedding maps each query node to a distinct data node with its
+    // labels, unless it is a homomorphism.
+    if config.enumeration != Enumeration::GqlHomomorphism
+        && query_graph.node_count() > data_graph.node_count()
+    {
+        return Err(Error::NoCandidates);
+    }
+
+    Ok(())
+}
+
+/// Runs the filter and order phase like [`prepare`], but computes the
+/// sorted candidates using `filter` instead of the filter of the config.
+fn prepare_filtered<F>(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    config: Config,
+    filter: F,
+) -> Result<(filter::Candidates, Vec<usize>), Error>
+where
+    F: FnOnce() -> Result<filter::Candidates, Error>,
+{
+    check_query_node_count(data_graph, query_graph, config)?;
+    if filter::has_absent_label(data_graph, query_graph) {
+        return Err(Error::NoCandidates);
+    }
+
+    // Only the RI order may continue with a node that is not adjacent to
+    // any of its predecessors, the other orders would miss the remaining
+    // components of the query graph.
+    if config.order != Order::Ri
+        && graph_ops::connected_components(query_graph)
+            .iter()
+            .any(|&component| component != 0)
+    {
+        return Err(Error::DisconnectedQuery);
+    }
+
+    let candidates = filter()?;
+
+    if let Some(max_candidate_product) = config.max_candidate_product {
+        let estimate = candidates.candidate_product();
+        if estimate > max_candidate_product {
+            return Err(Error::TooExpensive { estimate });
+        }
+    }
 
     let order = match config.order {
         Order::Gql => order::gql_order(data_graph, query_graph, &candidates),
+        Order::Ri => order::ri_order(query_graph, &candidates),
+        Order::Dpiso => order::dpiso_order(data_graph, query_graph, &candidates),
+        Order::Bfs => order::bfs_order(query_graph, &candidates),
+        Order::Nlf => order::nlf_order(data_graph, query_graph, &candidates)?,
+        Order::Connectivity => order::connectivity_order(query_graph, &candidates),
     };
 
-    match config.enumeration {
-        Enumeration::Gql => {
-            enumerate::gql_with(data_graph, query_graph, &candidates, &order, action)
+    Ok((candidates, order))
+}
+
+/// Runs the filter phase for the given config.
+///
+/// The filters rely on an injective mapping, e.g. a data node needs at least
+/// the degree of its query node. Homomorphisms only require matching labels,
+/// so [`Config::filter`] is ignored for [`Enumeration::GqlHomomorphism`] and
+/// the candidates are all data nodes with the labels of the query node.
+fn filter_candidates(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    config: Config,
+) -> Result<filter::Candidates, Error> {
+    filter_candidates_with(data_graph, query_graph, config, None)
+}
+
+/// Runs the filter phase like [`filter_candidates`], but uses the given
+/// coreness of the data graph instead of computing it, if present.
+fn filter_candidates_with(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    config: Config,
+    data_coreness: Option<&[usize]>,
+) -> Result<filter::Candidates, Error> {
+    if config.enumeration == Enumeration::GqlHomomorphism {
+        let candidates = (0..query_graph.node_count())
+            .map(|node| {
+                filter::nodes_with_labels(data_graph, query_graph.labels(node)).collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        if candidates.iter().any(Vec::is_empty) {
+            return Err(Error::NoCandidates);
         }
+        return Ok(filter::Candidates::new(candidates));
     }
+
+    let candidates = filter::filter_candidates_with(data_graph, query_graph, config, data_coreness);
+
+    let mut candidates = candidates.ok_or(Error::NoCandidates)?;
+
+    // Sort candidates to support set intersections
+    candidates.sort();
+
+    Ok(candidates)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::graph::GdlGraph;
+    use crate::graph::{GdlGraph, LoadConfig};
     use trim_margin::MarginTrimmable;
 
     fn graph(gdl: &str) -> GdlGraph {
@@ -119,7 +909,7 @@ mod tests {
     }
 
     #[test]
-    fn test_find_with() {
+    fn test_find_ri_order() {
         let data_graph = graph(TEST_GRAPH);
         let query_graph = graph(
             "
@@ -129,16 +919,1308 @@ mod tests {
             |",
         );
 
+        assert_eq!(find(&data_graph, &query_graph, Order::Ri), 2)
+    }
+
+    #[test]
+    fn test_find_with_filter() {
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L1),(n3:L1),(n4:L1)
+            |(n0)-->(n1),(n0)-->(n2),(n0)-->(n3),(n0)-->(n4)
+            |",
+        );
+        let query_graph = graph("(n0:L0),(n1:L1),(n0)-->(n1)");
+
+        let even_nodes = |data_graph: &Graph, query_graph: &Graph| {
+            filter::ldf_filter(data_graph, query_graph).map(|mut candidates| {
+                candidates.retain(|_, data_node| data_node % 2 == 0);
+                candidates
+            })
+        };
+
         let mut embeddings = Vec::new();
-        let count = find_with(
+        let count = find_with_filter(
             &data_graph,
             &query_graph,
-            |embedding| embeddings.push(Vec::from(embedding)),
+            &even_nodes,
+            |embedding| embeddings.push(embedding.to_vec()),
             Config::default(),
         );
 
+        assert_eq!(find(&data_graph, &query_graph, Config::default()), 4);
         assert_eq!(count, 2);
-        assert_eq!(embeddings[0], vec![2, 1, 3]);
-        assert_eq!(embeddings[1], vec![4, 3, 1])
+        assert_eq!(embeddings, vec![vec![0, 2], vec![0, 4]]);
+
+        // Built-in filters and the filter enum can be used as well.
+        let filters: [&dyn filter::CandidateFilter; 2] = [&filter::nlf_filter, &Filter::Cfl];
+        for filter in filters.iter() {
+            assert_eq!(
+                find_with_filter(
+                    &data_graph,
+                    &query_graph,
+                    *filter,
+                    |_| {},
+                    Config::default()
+                ),
+                4
+            );
+        }
+
+        // Without candidates for a query node, there is no embedding.
+        let no_candidates = |_: &Graph, _: &Graph| None;
+        assert_eq!(
+            find_with_filter(
+                &data_graph,
+                &query_graph,
+                &no_candidates,
+                |_| {},
+                Config::default()
+            ),
+            0
+        );
+    }
+
+    #[test]
+    fn test_find_with_enumerator() {
+        struct RootedAt(usize);
+
+        impl enumerate::Enumerator for RootedAt {
+            fn enumerate(
+                &self,
+                data_graph: &Graph,
+                query_graph: &Graph,
+                candidates: &filter::Candidates,
+                order: &[usize],
+                action: &mut dyn FnMut(&[usize]),
+            ) -> usize {
+                let mut count = 0;
+                enumerate::gql_with(data_graph, query_graph, candidates, order, |embedding| {
+                    if embedding[order[0]] == self.0 {
+                        count += 1;
+                        action(embedding);
+                    }
+                });
+                count
+            }
+        }
+
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0)
+            |(n0)-->(n1),(n1)-->(n2),(n2)-->(n3)
+            |",
+        );
+        let query_graph = graph("(n0:L0),(n1:L0),(n0)-->(n1)");
+
+        let mut embeddings = Vec::new();
+        let count = find_with_enumerator(
+            &data_graph,
+            &query_graph,
+            &RootedAt(0),
+            |embedding| embeddings.push(embedding.to_vec()),
+            Config::default(),
+        );
+
+        assert_eq!(find(&data_graph, &query_graph, Config::default()), 6);
+        assert_eq!(count, 1);
+        assert_eq!(embeddings, vec![vec![0, 1]]);
+        assert_eq!(
+            find_with_enumerator(
+                &data_graph,
+                &query_graph,
+                &RootedAt(1),
+                |_| {},
+                Config::default()
+            ),
+            2
+        );
+
+        // The enumeration enum maps to the built-in enumerations.
+        for enumeration in [
+            Enumeration::Gql,
+            Enumeration::GqlInduced,
+            Enumeration::GqlFailingSet,
+            Enumeration::Vf2,
+            Enumeration::Lftj,
+        ]
+        .iter()
+        {
+            assert_eq!(
+                find_with_enumerator(
+                    &data_graph,
+                    &query_graph,
+                    enumeration,
+                    |_| {},
+                    Config::default()
+                ),
+                6
+            );
+        }
+
+        // The query path is longer than the data path, which only
+        // homomorphisms can map it to.
+        let long_path = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0),(n4:L0)
+            |(n0)-->(n1),(n1)-->(n2),(n2)-->(n3),(n3)-->(n4)
+            |",
+        );
+        let homomorphisms = find(&data_graph, &long_path, Enumeration::GqlHomomorphism);
+        assert!(homomorphisms > 0);
+        assert_eq!(
+            find_with_enumerator(
+                &data_graph,
+                &long_path,
+                &Enumeration::GqlHomomorphism,
+                |_| {},
+                Config::default()
+            ),
+            homomorphisms
+        );
+        assert_eq!(
+            find_with_enumerator(
+                &data_graph,
+                &long_path,
+                &Enumeration::Gql,
+                |_| {},
+                Config::default()
+            ),
+            0
+        );
+    }
+
+    #[test]
+    fn test_find_impossible_queries() {
+        let data_graph = graph(TEST_GRAPH);
+        let oversized = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L2),(n3:L1),(n4:L2),(n5:L1)
+            |(n0)-->(n1),(n1)-->(n2),(n2)-->(n3),(n3)-->(n4),(n4)-->(n5)
+            |",
+        );
+        let absent_label = graph("(n0:L1),(n1:L9),(n0)-->(n1)");
+
+        for query_graph in [&oversized, &absent_label].iter() {
+            for filter in [Filter::Ldf, Filter::Gql, Filter::Nlf, Filter::Cfl].iter() {
+                for order in [
+                    Order::Gql,
+                    Order::Ri,
+                    Order::Dpiso,
+                    Order::Bfs,
+                    Order::Nlf,
+                    Order::Connectivity,
+                ]
+                .iter()
+                {
+                    let config = Config::new(*filter, *order, Enumeration::Gql);
+                    assert_eq!(find(&data_graph, query_graph, config), 0);
+                    assert!(matches!(
+                        find_checked(&data_graph, query_graph, config),
+                        Err(Error::NoCandidates)
+                    ));
+                }
+            }
+        }
+
+        // Homomorphisms may map several query nodes to the same data node.
+        assert!(find(&data_graph, &oversized, Enumeration::GqlHomomorphism) > 0);
+        assert_eq!(
+            find(&data_graph, &absent_label, Enumeration::GqlHomomorphism),
+            0
+        );
+    }
+
+    #[test]
+    fn test_find_with_candidates() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph("(n0:L2),(n1:L1),(n2:L1),(n0)-->(n1),(n1)-->(n2)");
+
+        let candidates = filter::ldf_filter(&data_graph, &query_graph).unwrap();
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+        let mut embeddings = Vec::new();
+        let count = find_with_candidates(
+            &data_graph,
+            &query_graph,
+            &candidates,
+            &order,
+            |embedding| embeddings.push(Vec::from(embedding)),
+            Config::default(),
+        );
+
+        assert_eq!(count, 2);
+        assert_eq!(
+            embeddings,
+            find_all(&data_graph, &query_graph, Config::default())
+        );
+        assert_eq!(
+            find_with_candidates(
+                &data_graph,
+                &query_graph,
+                &candidates,
+                &order,
+                |_| {},
+                Config::builder().limit(1).build(),
+            ),
+            1
+        );
+    }
+
+    #[test]
+    fn test_find_with_order() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L2),(n1:L1),(n2:L1)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |",
+        );
+
+        let mut embeddings = Vec::new();
+        let count = find_with_order(
+            &data_graph,
+            &query_graph,
+            &[2, 1, 0],
+            |embedding| embeddings.push(Vec::from(embedding)),
+            Config::default(),
+        )
+        .unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(embeddings, vec![vec![4, 3, 1], vec![2, 1, 3]]);
+
+        // n2 is not adjacent to n0
+        let disconnected = find_with_order(
+            &data_graph,
+            &query_graph,
+            &[0, 2, 1],
+            |_| {},
+            Config::default(),
+        );
+        assert!(matches!(disconnected, Err(Error::InvalidOrder { .. })));
+
+        let incomplete = find_with_order(
+            &data_graph,
+            &query_graph,
+            &[0, 1],
+            |_| {},
+            Config::default(),
+        );
+        assert!(matches!(incomplete, Err(Error::InvalidOrder { .. })));
+
+        let duplicate = find_with_order(
+            &data_graph,
+            &query_graph,
+            &[0, 1, 0],
+            |_| {},
+            Config::default(),
+        );
+        assert!(matches!(duplicate, Err(Error::InvalidOrder { .. })));
+    }
+
+    #[test]
+    fn test_find_disconnected_query() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph("(n0:L0),(n1:L1),(n2:L2),(n3:L1),(n0)-->(n1),(n2)-->(n3)");
+
+        for order in [Order::Gql, Order::Dpiso, Order::Bfs] {
+            assert!(matches!(
+                find_checked(&data_graph, &query_graph, order),
+                Err(Error::DisconnectedQuery)
+            ));
+            assert_eq!(find(&data_graph, &query_graph, order), 0);
+            assert_eq!(find_with(&data_graph, &query_graph, |_| {}, order), 0);
+        }
+
+        // (n0, n1) maps to the only L0-L1 edge (n0, n1), which leaves the
+        // L2-L1 edge (n4, n3) for (n2, n3).
+        assert_eq!(
+            find_all(&data_graph, &query_graph, Order::Ri),
+            vec![vec![0, 1, 4, 3]]
+        );
+    }
+
+    #[test]
+    fn test_find_checked() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L2),(n1:L1),(n2:L1)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |",
+        );
+
+        assert_eq!(
+            find_checked(&data_graph, &query_graph, Config::default()).unwrap(),
+            2
+        );
+
+        // The filter succeeds, but there is no L0 node with two L1 neighbors.
+        let query_graph = graph("(n0:L0),(n1:L1),(n2:L1),(n0)-->(n1),(n0)-->(n2)");
+        assert_eq!(
+            find_checked(&data_graph, &query_graph, Config::default()).unwrap(),
+            0
+        );
+
+        // There is no L0 node with a degree of three in the data graph.
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L1),(n3:L1)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n0)-->(n3)
+            |",
+        );
+        assert!(matches!(
+            find_checked(&data_graph, &query_graph, Config::default()),
+            Err(Error::NoCandidates)
+        ));
+        assert_eq!(find(&data_graph, &query_graph, Config::default()), 0);
+        assert_eq!(
+            iter(&data_graph, &query_graph, Config::default()).count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_find_max_candidate_product() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L2),(n1:L1),(n2:L1)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |",
+        );
+
+        let config = Config::builder().max_candidate_product(4).build();
+
+        assert!(matches!(
+            find_checked(&data_graph, &query_graph, config),
+            Err(Error::TooExpensive { estimate: 8 })
+        ));
+        assert_eq!(find_with(&data_graph, &query_graph, |_| {}, config), 0);
+
+        let config = Config::builder().max_candidate_product(1000).build();
+
+        assert_eq!(find_checked(&data_graph, &query_graph, config).unwrap(), 2);
+        assert_eq!(find_with(&data_graph, &query_graph, |_| {}, config), 2);
+    }
+
+    #[test]
+    fn test_find_with() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L2),(n1:L1),(n2:L1)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |",
+        );
+
+        let mut embeddings = Vec::new();
+        let count = find_with(
+            &data_graph,
+            &query_graph,
+            |embedding| embeddings.push(Vec::from(embedding)),
+            Config::default(),
+        );
+
+        assert_eq!(count, 2);
+        assert_eq!(embeddings[0], vec![2, 1, 3]);
+        assert_eq!(embeddings[1], vec![4, 3, 1])
+    }
+
+    #[test]
+    fn test_find_with_pairs() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L2)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |",
+        );
+
+        let mut embeddings = Vec::new();
+        let count = find_with_pairs(
+            &data_graph,
+            &query_graph,
+            |pairs| embeddings.push(Vec::from(pairs)),
+            Config::default(),
+        );
+
+        assert_eq!(count, 1);
+        assert_eq!(embeddings, vec![vec![(0, 0), (1, 1), (2, 2)]]);
+    }
+
+    #[test]
+    fn test_find_all() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L2),(n1:L1),(n2:L1)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |",
+        );
+
+        let embeddings = find_all(&data_graph, &query_graph, Config::default());
+
+        assert_eq!(embeddings, vec![vec![2, 1, 3], vec![4, 3, 1]]);
+
+        let embeddings = find_all_mapped(&data_graph, &query_graph, Config::default());
+
+        assert_eq!(
+            embeddings,
+            vec![vec![(0, 2), (1, 1), (2, 3)], vec![(0, 4), (1, 3), (2, 1)]]
+        );
+    }
+
+    #[test]
+    fn test_find_directed() {
+        fn directed_graph(gdl: &str) -> Graph {
+            Graph::from_gdl(
+                &gdl.trim_margin().unwrap(),
+                LoadConfig::default().directed(),
+            )
+            .unwrap()
+        }
+
+        let data_graph = directed_graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L0),(n3:L1),(n4:L1)
+            |(n0)-->(n1)
+            |(n0)-->(n3)
+            |(n4)-->(n2)
+            |",
+        );
+
+        assert_eq!(data_graph.out_degree(0), 2);
+        assert_eq!(data_graph.in_degree(0), 0);
+        assert_eq!(data_graph.out_degree(4), 1);
+        assert_eq!(data_graph.in_degree(1), 1);
+
+        let forward = directed_graph("(a:L0),(b:L1),(a)-->(b)");
+        let backward = directed_graph("(a:L0),(b:L1),(b)-->(a)");
+
+        assert_eq!(find(&data_graph, &forward, Config::default()), 2);
+        assert_eq!(find(&data_graph, &backward, Config::default()), 1);
+
+        // Without direction, both queries match all three edges.
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L0),(n3:L1),(n4:L1)
+            |(n0)-->(n1)
+            |(n0)-->(n3)
+            |(n4)-->(n2)
+            |",
+        );
+        assert_eq!(
+            find(
+                &data_graph,
+                &graph("(a:L0),(b:L1),(a)-->(b)"),
+                Config::default()
+            ),
+            3
+        );
+        assert_eq!(
+            find(
+                &data_graph,
+                &graph("(a:L0),(b:L1),(b)-->(a)"),
+                Config::default()
+            ),
+            3
+        );
+    }
+
+    #[test]
+    fn test_find_edge_labeled() {
+        fn labeled_graph(gdl: &str) -> Graph {
+            Graph::from_gdl(
+                &gdl.trim_margin().unwrap(),
+                LoadConfig::default().edge_labeled(),
+            )
+            .unwrap()
+        }
+
+        let data_graph = labeled_graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L1),(n3:L0)
+            |(n0)-[:E0]->(n1)
+            |(n0)-[:E1]->(n2)
+            |(n3)-[:E1]->(n2)
+            |",
+        );
+
+        let any = labeled_graph("(a:L0),(b:L1),(a)-->(b)");
+        let knows = labeled_graph("(a:L0),(b:L1),(a)-[:E0]->(b)");
+        let likes = labeled_graph("(a:L0),(b:L1),(a)-[:E1]->(b)");
+
+        assert_eq!(find(&data_graph, &any, Config::default()), 3);
+        assert_eq!(find(&data_graph, &knows, Config::default()), 1);
+        assert_eq!(find(&data_graph, &likes, Config::default()), 2);
+    }
+
+    #[test]
+    fn test_find_reciprocal_and_parallel_edge_labels() {
+        let load_config = LoadConfig::default().directed().edge_labeled();
+        let directed = |gdl: &str| Graph::from_gdl(gdl, load_config).unwrap();
+
+        // Both edges between a and b have their own label.
+        let data_graph = directed("(a:L0),(b:L1),(a)-[:E1]->(b),(b)-[:E2]->(a)");
+        let queries = [
+            ("(x:L0),(y:L1),(x)-[:E1]->(y)", 1),
+            ("(x:L0),(y:L1),(y)-[:E2]->(x)", 1),
+            ("(x:L0),(y:L1),(x)-[:E2]->(y)", 0),
+            ("(x:L0),(y:L1),(x)-[:E1]->(y),(y)-[:E2]->(x)", 1),
+            ("(x:L0),(y:L1),(x)-[:E2]->(y),(y)-[:E1]->(x)", 0),
+        ];
+
+        // Parallel edges between node 0 and 1 with the labels 5 and 6.
+        let parallel = "
+            |t 2 2
+            |v 0 0 2
+            |v 1 1 2
+            |e 0 1 5
+            |e 0 1 6
+            |"
+        .trim_margin()
+        .unwrap();
+        let parallel_graph =
+            Graph::from_reader(parallel.as_bytes(), LoadConfig::default().edge_labeled()).unwrap();
+        let parallel_query = |label: usize| {
+            let input = format!("t 2 1\nv 0 0 1\nv 1 1 1\ne 0 1 {}\n", label);
+            Graph::from_reader(input.as_bytes(), LoadConfig::default().edge_labeled()).unwrap()
+        };
+
+        for enumeration in [Enumeration::Gql, Enumeration::Vf2, Enumeration::Lftj] {
+            for (gdl, expected) in queries.iter() {
+                assert_eq!(
+                    find(&data_graph, &directed(gdl), enumeration),
+                    *expected,
+                    "query: {}, enumeration: {}",
+                    gdl,
+                    enumeration
+                );
+            }
+
+            assert_eq!(find(&parallel_graph, &parallel_query(5), enumeration), 1);
+            assert_eq!(find(&parallel_graph, &parallel_query(6), enumeration), 1);
+            assert_eq!(find(&parallel_graph, &parallel_query(7), enumeration), 0);
+        }
+    }
+
+    #[test]
+    fn test_iter() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L1),(n1:L2),(n2:L1),(n3:L2)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |",
+        );
+
+        let mut expected = Vec::new();
+        find_with(
+            &data_graph,
+            &query_graph,
+            |embedding| expected.push(Vec::from(embedding)),
+            Config::default(),
+        );
+
+        let embeddings = iter(&data_graph, &query_graph, Config::default()).collect::<Vec<_>>();
+        assert_eq!(embeddings, expected);
+
+        let first = iter(&data_graph, &query_graph, Config::default())
+            .take(1)
+            .collect::<Vec<_>>();
+        assert_eq!(first, vec![vec![1, 2, 3, 4]]);
+
+        let mut embeddings = iter(&data_graph, &query_graph, Config::default());
+        assert_eq!(embeddings.by_ref().count(), 2);
+        assert_eq!(embeddings.next(), None);
+
+        // The iterator stops at the edge check budget like find_with.
+        for max_edge_checks in 0..10 {
+            let config = Config::builder().max_edge_checks(max_edge_checks).build();
+
+            let mut expected = Vec::new();
+            find_with(
+                &data_graph,
+                &query_graph,
+                |embedding| expected.push(Vec::from(embedding)),
+                config,
+            );
+
+            let embeddings = iter(&data_graph, &query_graph, config).collect::<Vec<_>>();
+            assert_eq!(embeddings, expected);
+        }
+    }
+
+    #[test]
+    fn test_find_induced() {
+        let data_graph = graph(TEST_GRAPH);
+        // n0 and n2 are adjacent in the data graph, but not in the query.
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L2)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |",
+        );
+
+        assert_eq!(find(&data_graph, &query_graph, Enumeration::Gql), 1);
+        assert_eq!(find(&data_graph, &query_graph, Enumeration::GqlInduced), 0);
+
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L1),(n3:L2)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |(n2)-->(n3)
+            |",
+        );
+
+        assert_eq!(find(&data_graph, &query_graph, Enumeration::Gql), 1);
+        assert_eq!(find(&data_graph, &query_graph, Enumeration::GqlInduced), 1);
+    }
+
+    #[test]
+    fn test_find_anti_edge() {
+        let data_graph = graph(TEST_GRAPH);
+        let open_triangle = graph("(n0),(n1),(n2),(n0)-->(n1),(n1)-->(n2)");
+        // n0 and n2 must not be adjacent in the data graph.
+        let anti_edge = graph("(n0),(n1),(n2),(n0)-->(n1),(n1)-->(n2),(n0)-[{anti: true}]->(n2)");
+
+        assert_eq!(anti_edge.edge_count(), 2);
+        assert_eq!(anti_edge.anti_edges(), &[(0, 2)]);
+        assert!(anti_edge.is_anti_edge(2, 0));
+
+        // Each of the 6 paths in the triangle n0, n1, n2 is closed.
+        assert_eq!(find(&data_graph, &open_triangle, Config::default()), 18);
+        assert_eq!(find(&data_graph, &anti_edge, Config::default()), 12);
+        assert_eq!(iter(&data_graph, &anti_edge, Config::default()).count(), 12);
+        assert_eq!(
+            find(&data_graph, &open_triangle, Enumeration::GqlInduced),
+            find(&data_graph, &anti_edge, Enumeration::GqlInduced)
+        );
+
+        let from_reader = "
+            |t 3 2
+            |v 0 * 1
+            |v 1 * 2
+            |v 2 * 1
+            |e 0 1
+            |e 1 2
+            |!e 0 2
+            |"
+        .trim_margin()
+        .unwrap();
+        let load_config = LoadConfig::with_neighbor_label_frequency()
+            .with_wildcards()
+            .with_anti_edges();
+        let from_reader = Graph::from_reader(from_reader.as_bytes(), load_config).unwrap();
+
+        assert_eq!(from_reader.anti_edges(), &[(0, 2)]);
+        assert_eq!(find(&data_graph, &from_reader, Config::default()), 12);
+
+        let malformed = "t 3 2\nv 0 * 1\nv 1 * 2\nv 2 * 1\ne 0 1\ne 1 2\n!e 0 x\n";
+        assert!(matches!(
+            Graph::from_reader(malformed.as_bytes(), load_config),
+            Err(Error::InvalidGraphFile { .. })
+        ));
+    }
+
+    #[test]
+    fn test_find_edge_weight_bounds() {
+        let data_graph = Graph::from_gdl(
+            &"
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0)
+            |(n0)-[{weight: 1.0}]->(n1)
+            |(n1)-[{weight: 2.0}]->(n2)
+            |(n2)-[{weight: 3.0}]->(n3)
+            |"
+            .trim_margin()
+            .unwrap(),
+            LoadConfig::with_neighbor_label_frequency().weighted(),
+        )
+        .unwrap();
+        let query_graph = graph("(n0:L0),(n1:L0),(n2:L0),(n0)-->(n1),(n1)-->(n2)");
+
+        let bounded = |enumeration: Enumeration, min: f64, max: f64| {
+            Config::builder()
+                .enumeration(enumeration)
+                .edge_weight_bounds(min, max)
+                .build()
+        };
+
+        for enumeration in [Enumeration::Gql, Enumeration::Vf2, Enumeration::Lftj] {
+            assert_eq!(find(&data_graph, &query_graph, enumeration), 4);
+            assert_eq!(
+                find(&data_graph, &query_graph, bounded(enumeration, 1.0, 3.0)),
+                4
+            );
+            // Tightening the bounds excludes the path over the first edge.
+            assert_eq!(
+                find(&data_graph, &query_graph, bounded(enumeration, 2.0, 3.0)),
+                2
+            );
+            assert_eq!(
+                find(&data_graph, &query_graph, bounded(enumeration, 2.0, 2.5)),
+                0
+            );
+        }
+
+        assert_eq!(
+            iter(
+                &data_graph,
+                &query_graph,
+                bounded(Enumeration::Gql, 2.0, 3.0)
+            )
+            .count(),
+            2
+        );
+
+        // Parallel edges match if one of them is within the bounds.
+        let parallel = "
+            |t 2 2
+            |v 0 0 2
+            |v 1 1 2
+            |e 0 1 1.0
+            |e 0 1 5.0
+            |"
+        .trim_margin()
+        .unwrap();
+        let parallel_graph =
+            Graph::from_reader(parallel.as_bytes(), LoadConfig::default().weighted()).unwrap();
+        let edge = Graph::from_gdl("(x:L0),(y:L1),(x)-->(y)", LoadConfig::default()).unwrap();
+
+        // Reciprocal edges only match in the direction of the query edge.
+        let load_config = LoadConfig::default().directed().weighted();
+        let reciprocal_graph = Graph::from_gdl(
+            "(a:L0),(b:L1),(a)-[{weight: 1.0}]->(b),(b)-[{weight: 5.0}]->(a)",
+            load_config,
+        )
+        .unwrap();
+        let directed = |gdl: &str| Graph::from_gdl(gdl, LoadConfig::default().directed()).unwrap();
+        let queries = [
+            ("(x:L0),(y:L1),(x)-->(y)", 0),
+            ("(x:L0),(y:L1),(y)-->(x)", 1),
+            ("(x:L0),(y:L1),(x)-->(y),(y)-->(x)", 0),
+        ];
+
+        for enumeration in [Enumeration::Gql, Enumeration::Vf2, Enumeration::Lftj] {
+            assert_eq!(
+                find(&parallel_graph, &edge, bounded(enumeration, 4.0, 6.0)),
+                1
+            );
+            assert_eq!(
+                find(&parallel_graph, &edge, bounded(enumeration, 0.0, 2.0)),
+                1
+            );
+            assert_eq!(
+                find(&parallel_graph, &edge, bounded(enumeration, 2.0, 4.0)),
+                0
+            );
+
+            for (gdl, expected) in queries.iter() {
+                assert_eq!(
+                    find(
+                        &reciprocal_graph,
+                        &directed(gdl),
+                        bounded(enumeration, 4.0, 6.0)
+                    ),
+                    *expected,
+                    "query: {}, enumeration: {}",
+                    gdl,
+                    enumeration
+                );
+            }
+            assert_eq!(
+                find(
+                    &reciprocal_graph,
+                    &directed("(x:L0),(y:L1),(x)-->(y),(y)-->(x)"),
+                    bounded(enumeration, 0.0, 6.0)
+                ),
+                1
+            );
+        }
+
+        // Edges without weight never satisfy the bounds.
+        let unweighted = graph(TEST_GRAPH);
+        let edge = graph("(n0),(n1),(n0)-->(n1)");
+        assert_eq!(
+            find(&unweighted, &edge, bounded(Enumeration::Gql, 0.0, f64::MAX)),
+            0
+        );
+    }
+
+    #[test]
+    fn test_find_wildcard() {
+        let data_graph = graph(TEST_GRAPH);
+        let fixed = graph("(n0:L2),(n1:L1),(n0)-->(n1)");
+        // n1 has no label and matches any neighbor of an L2 node.
+        let wildcard = graph("(n0:L2),(n1),(n0)-->(n1)");
+
+        assert!(wildcard.is_wildcard(1));
+        assert_eq!(wildcard.label_frequency(0), 0);
+        assert_eq!(wildcard.label_frequency(2), 1);
+
+        for filter in [
+            Filter::Ldf,
+            Filter::Gql,
+            Filter::Nlf,
+            Filter::Nlf2,
+            Filter::Cfl,
+            Filter::LdfCore,
+        ] {
+            assert_eq!(find(&data_graph, &fixed, filter), 2);
+            assert_eq!(find(&data_graph, &wildcard, filter), 5);
+        }
+
+        assert_eq!(
+            find(&data_graph, &wildcard, Enumeration::GqlHomomorphism),
+            5
+        );
+    }
+
+    #[test]
+    fn test_find_multi_labels() {
+        // n0 is both an L0 and an L1 node.
+        let data_graph = graph(
+            "
+            |(n0:L0:L1),(n1:L2),(n2:L2)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |",
+        );
+        let l0 = graph("(n0:L0),(n1:L2),(n0)-->(n1)");
+        let l1 = graph("(n0:L1),(n1:L2),(n0)-->(n1)");
+        let both = graph("(n0:L0:L1),(n1:L2),(n0)-->(n1)");
+        // n0 can't be mapped to both query nodes at once.
+        let distinct = graph("(n0:L0),(n1:L1),(n2:L2),(n0)-->(n2),(n1)-->(n2)");
+
+        assert_eq!(data_graph.labels(0), &[0, 1]);
+
+        for filter in [
+            Filter::Ldf,
+            Filter::Gql,
+            Filter::Nlf,
+            Filter::Nlf2,
+            Filter::Cfl,
+            Filter::LdfCore,
+        ] {
+            assert_eq!(find(&data_graph, &l0, filter), 2);
+            assert_eq!(find(&data_graph, &l1, filter), 2);
+            assert_eq!(find(&data_graph, &both, filter), 2);
+            assert_eq!(find(&data_graph, &distinct, filter), 0);
+        }
+
+        assert_eq!(
+            find(&data_graph, &distinct, Enumeration::GqlHomomorphism),
+            2
+        );
+    }
+
+    #[test]
+    fn test_find_empty_query() {
+        let data_graph = graph(TEST_GRAPH);
+        let empty = "t 0 0\n".parse::<Graph>().unwrap();
+
+        for filter in [
+            Filter::Ldf,
+            Filter::Gql,
+            Filter::Nlf,
+            Filter::Nlf2,
+            Filter::Cfl,
+            Filter::LdfCore,
+        ] {
+            for order in [
+                Order::Gql,
+                Order::Ri,
+                Order::Dpiso,
+                Order::Bfs,
+                Order::Nlf,
+                Order::Connectivity,
+            ] {
+                for enumeration in [
+                    Enumeration::Gql,
+                    Enumeration::GqlInduced,
+                    Enumeration::GqlHomomorphism,
+                    Enumeration::GqlFailingSet,
+                    Enumeration::Vf2,
+                    Enumeration::Lftj,
+                ] {
+                    let config = Config::new(filter, order, enumeration);
+
+                    assert_eq!(find(&data_graph, &empty, config), 0, "config: {}", config);
+                    assert!(matches!(
+                        find_checked(&data_graph, &empty, config),
+                        Err(Error::EmptyQuery)
+                    ));
+                    assert!(!contains_match(&data_graph, &empty, config));
+                }
+            }
+        }
+
+        assert!(matches!(
+            find_with_order(&data_graph, &empty, &[], |_| {}, Config::default()),
+            Err(Error::EmptyQuery)
+        ));
+
+        // The enumerations agree if they are called directly.
+        let candidates = filter::ldf_filter(&data_graph, &empty).unwrap();
+        assert_eq!(enumerate::vf2(&data_graph, &empty, &candidates, &[]), 0);
+        assert_eq!(enumerate::lftj(&data_graph, &empty, &candidates, &[]), 0);
+    }
+
+    #[test]
+    fn test_contains_match() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L1),(n1:L2),(n2:L1),(n3:L2)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |",
+        );
+
+        assert!(contains_match(&data_graph, &query_graph, Config::default()));
+
+        // L7 does not exist in the data graph, so the filter rules out a match.
+        let query_graph = graph(
+            "
+            |(n0:L1),(n1:L7)
+            |(n0)-->(n1)
+            |",
+        );
+
+        assert!(matches!(
+            find_checked(&data_graph, &query_graph, Config::default()),
+            Err(Error::NoCandidates)
+        ));
+        assert!(!contains_match(
+            &data_graph,
+            &query_graph,
+            Config::default()
+        ));
+    }
+
+    #[test]
+    fn test_find_homomorphism() {
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L0)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |",
+        );
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L0)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |",
+        );
+
+        assert_eq!(find(&data_graph, &query_graph, Enumeration::Gql), 2);
+        // Both ends of the query path may map to the same data node.
+        assert_eq!(
+            find(&data_graph, &query_graph, Enumeration::GqlHomomorphism),
+            4
+        );
+
+        let mut embeddings =
+            iter(&data_graph, &query_graph, Enumeration::GqlHomomorphism).collect::<Vec<_>>();
+        embeddings.sort();
+        assert_eq!(
+            embeddings,
+            vec![vec![0, 1, 0], vec![0, 1, 2], vec![2, 1, 0], vec![2, 1, 2]]
+        );
+
+        // The query graph has more nodes than the data graph and its inner
+        // node has a higher degree than any data node. The filter of the
+        // config is ignored, as it would reject those queries.
+        let star = graph(
+            "
+            |(n0:L1),(n1:L0),(n2:L0),(n3:L0)
+            |(n0)-->(n1),(n0)-->(n2),(n0)-->(n3)
+            |",
+        );
+        for filter in [
+            Filter::Ldf,
+            Filter::Gql,
+            Filter::Nlf,
+            Filter::Nlf2,
+            Filter::Cfl,
+            Filter::LdfCore,
+        ]
+        .iter()
+        {
+            let config = Config::new(*filter, Order::Gql, Enumeration::GqlHomomorphism);
+            assert_eq!(find(&data_graph, &star, config), 8);
+        }
+    }
+
+    #[test]
+    fn test_find_fold() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L2),(n1:L1),(n2:L1)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |",
+        );
+
+        let sum = find_fold(
+            &data_graph,
+            &query_graph,
+            0,
+            |sum, embedding| sum + embedding.iter().sum::<usize>(),
+            Config::default(),
+        );
+
+        let mut embeddings = Vec::new();
+        find_with(
+            &data_graph,
+            &query_graph,
+            |embedding| embeddings.push(Vec::from(embedding)),
+            Config::default(),
+        );
+
+        assert_eq!(sum, embeddings.iter().flatten().sum::<usize>());
+        assert_eq!(sum, 14);
+    }
+
+    #[test]
+    fn test_find_with_ordered() {
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L1),(n3:L1),(n4:L0)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n0)-->(n3)
+            |(n4)-->(n1)
+            |(n4)-->(n3)
+            |",
+        );
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L1)
+            |(n0)-->(n1)
+            |",
+        );
+
+        let mut embeddings = Vec::new();
+        let count = find_with_ordered(
+            &data_graph,
+            &query_graph,
+            |_, v1, v2| v2.cmp(&v1),
+            |embedding| embeddings.push(Vec::from(embedding)),
+            Config::default(),
+        );
+
+        assert_eq!(count, 5);
+        // root candidates keep their order, their subtrees are descending
+        assert_eq!(
+            embeddings,
+            vec![vec![0, 3], vec![0, 2], vec![0, 1], vec![4, 3], vec![4, 1]]
+        );
+    }
+
+    #[test]
+    fn test_find_with_summary() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L2),(n1:L1),(n2:L1)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |",
+        );
+
+        let summary = find_with_summary(&data_graph, &query_graph, |_| {}, Config::default());
+
+        assert_eq!(summary.embedding_count, 2);
+        assert!(!summary.budget_exceeded);
+
+        let config = Config {
+            max_edge_checks: Some(1),
+            ..Config::default()
+        };
+
+        let mut embeddings = Vec::new();
+        let summary = find_with_summary(
+            &data_graph,
+            &query_graph,
+            |embedding| embeddings.push(Vec::from(embedding)),
+            config,
+        );
+
+        assert!(summary.budget_exceeded);
+        assert_eq!(summary.edge_checks, 1);
+        assert!(summary.embedding_count < 2);
+        assert_eq!(summary.embedding_count, embeddings.len());
+    }
+
+    #[test]
+    fn test_find_with_limit() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L1),(n1:L2),(n2:L1),(n3:L2)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |",
+        );
+
+        assert_eq!(find(&data_graph, &query_graph, Config::default()), 2);
+
+        for (limit, expected) in [(0, 0), (1, 1), (3, 2)] {
+            let config = Config::builder().filter(Filter::Gql).limit(limit).build();
+
+            assert_eq!(find(&data_graph, &query_graph, config), expected);
+            assert_eq!(iter(&data_graph, &query_graph, config).count(), expected);
+        }
+    }
+
+    #[test]
+    fn test_find_ignores_gdl_direction() {
+        let data_graph = graph(TEST_GRAPH);
+        let forward = graph("(n0:L0),(n1:L1),(n2:L2),(n0)-->(n1),(n1)-->(n2)");
+        let backward = graph("(n0:L0),(n1:L1),(n2:L2),(n1)-->(n0),(n2)-->(n1)");
+
+        for node in 0..3 {
+            assert_eq!(forward.neighbors(node), backward.neighbors(node));
+        }
+        assert_eq!(find(&data_graph, &forward, Config::default()), 1);
+        assert_eq!(find(&data_graph, &backward, Config::default()), 1);
+    }
+
+    #[test]
+    fn test_find_in_many() {
+        let data_graphs = [
+            "
+            |(n0:L0),(n1:L1),(n2:L1),(n3:L1)
+            |(n0)-->(n1),(n0)-->(n2),(n1)-->(n2),(n2)-->(n3)
+            |",
+            "
+            |(n0:L0),(n1:L1),(n2:L1)
+            |(n0)-->(n1),(n1)-->(n2)
+            |",
+            "
+            |(n0:L0),(n1:L1),(n2:L1)
+            |(n0)-->(n1),(n0)-->(n2),(n1)-->(n2)
+            |",
+        ]
+        .iter()
+        .map(|gdl| {
+            Graph::from_gdl(
+                &gdl.trim_margin().unwrap(),
+                LoadConfig::with_neighbor_label_frequency(),
+            )
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L1)
+            |(n0)-->(n1),(n0)-->(n2),(n1)-->(n2)
+            |",
+        );
+
+        let counts = find_in_many(&data_graphs, &query_graph, Config::default());
+
+        assert_eq!(counts, vec![2, 0, 2]);
+        assert!(find_in_many(&[], &query_graph, Config::default()).is_empty());
+    }
+
+    #[test]
+    fn test_estimate_count() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        // A pseudo-random graph with 30 nodes, 2 labels and irregular degrees.
+        let labels = (0..30).map(|node| node % 2).collect::<Vec<_>>();
+        let edges = (0..30)
+            .flat_map(|source| {
+                (source + 1..30)
+                    .filter(move |target| (source * 7 + target * 13) % 5 == 0)
+                    .map(move |target| (source, target))
+            })
+            .collect::<Vec<_>>();
+        let data_graph = Graph::from_edges(&labels, &edges).unwrap();
+        let query_graph = Graph::from_edges(&[0, 1, 0, 1], &[(0, 1), (1, 2), (2, 3)]).unwrap();
+
+        let exact = find(&data_graph, &query_graph, Config::default());
+        assert!(exact > 0);
+
+        let mut rng = StdRng::seed_from_u64(42);
+        let estimate = estimate_count(&data_graph, &query_graph, Config::default(), 5000, &mut rng);
+        assert!(
+            (estimate - exact as f64).abs() < 0.05 * exact as f64,
+            "estimate {} is too far from {}",
+            estimate,
+            exact
+        );
+
+        // The same seed leads to the same estimate.
+        let mut rng = StdRng::seed_from_u64(42);
+        assert_eq!(
+            estimate_count(&data_graph, &query_graph, Config::default(), 5000, &mut rng),
+            estimate
+        );
+
+        // The filter rules out any match.
+        let query_graph = Graph::from_edges(&[2, 0], &[(0, 1)]).unwrap();
+        assert_eq!(
+            estimate_count(&data_graph, &query_graph, Config::default(), 10, &mut rng),
+            0.0
+        );
+    }
+
+    #[test]
+    fn test_find_distinct_sets() {
+        // Every 4-subset of the clique contains the diamond.
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0),(n4:L0)
+            |(n0)-->(n1),(n0)-->(n2),(n0)-->(n3),(n0)-->(n4)
+            |(n1)-->(n2),(n1)-->(n3),(n1)-->(n4)
+            |(n2)-->(n3),(n2)-->(n4)
+            |(n3)-->(n4)
+            |",
+        );
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0)
+            |(n0)-->(n1),(n0)-->(n2),(n1)-->(n2),(n1)-->(n3),(n2)-->(n3)
+            |",
+        );
+
+        assert_eq!(find(&data_graph, &query_graph, Config::default()), 120);
+
+        let sets = find_distinct_sets(&data_graph, &query_graph, 3, Config::default());
+
+        assert_eq!(sets.len(), 3);
+        assert!(sets.iter().all(|set| set.len() == 4));
+        assert_eq!(sets.iter().collect::<HashSet<_>>().len(), 3);
+
+        let sets = find_distinct_sets(&data_graph, &query_graph, 10, Config::default());
+        assert_eq!(sets.len(), 5);
+
+        let sets = find_distinct_sets(&data_graph, &query_graph, 0, Config::default());
+        assert!(sets.is_empty());
     }
 }
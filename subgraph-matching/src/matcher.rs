@@ -0,0 +1,173 @@
+use std::cell::RefCell;
+
+use crate::{
+    enumerate::{self, VisitedSet},
+    graph_ops::coreness,
+    prepare_with, run_enumeration_with, Config, Graph,
+};
+
+/// Matches many query graphs against the same data graph.
+///
+/// State that only depends on the data graph is computed once when the
+/// matcher is created, e.g. the coreness of the data nodes that is used by
+/// [`crate::Filter::LdfCore`]. The set that tracks visited data nodes during
+/// the enumeration is allocated once and reused across queries.
+pub struct Matcher<'a> {
+    data_graph: &'a Graph,
+    data_coreness: Vec<usize>,
+    visited: RefCell<VisitedSet>,
+}
+
+impl<'a> Matcher<'a> {
+    pub fn new(data_graph: &'a Graph) -> Self {
+        Self {
+            data_graph,
+            data_coreness: coreness(data_graph),
+            visited: RefCell::new(VisitedSet::new(data_graph.node_count())),
+        }
+    }
+
+    pub fn data_graph(&self) -> &'a Graph {
+        self.data_graph
+    }
+
+    /// Counts all embeddings of the query graph like [`crate::find`].
+    pub fn match_query(&self, query_graph: &Graph, config: impl Into<Config>) -> usize {
+        self.match_query_with(query_graph, |_| {}, config)
+    }
+
+    /// Finds all embeddings of the query graph and emits them to `action`
+    /// like [`crate::find_with`].
+    pub fn match_query_with<F>(
+        &self,
+        query_graph: &Graph,
+        action: F,
+        config: impl Into<Config>,
+    ) -> usize
+    where
+        F: FnMut(&[usize]),
+    {
+        let config = config.into();
+
+        let (candidates, order) = match prepare_with(
+            self.data_graph,
+            query_graph,
+            config,
+            Some(&self.data_coreness),
+        ) {
+            Ok(prepared) => prepared,
+            Err(_) => return 0,
+        };
+
+        // The action might match another query using this matcher.
+        let mut fresh_visited;
+        let mut shared_visited = self.visited.try_borrow_mut();
+        let visited = match shared_visited.as_deref_mut() {
+            Ok(visited) => visited,
+            Err(_) => {
+                fresh_visited = VisitedSet::new(self.data_graph.node_count());
+                &mut fresh_visited
+            }
+        };
+
+        run_enumeration_with(
+            self.data_graph,
+            query_graph,
+            &candidates,
+            &order,
+            None::<enumerate::Comparator>,
+            visited,
+            enumerate::continuing(action),
+            config,
+        )
+        .embedding_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find, find_with, graph::GdlGraph, Enumeration, Filter, Order};
+    use trim_margin::MarginTrimmable;
+
+    fn graph(gdl: &str) -> GdlGraph {
+        gdl.trim_margin().unwrap().parse::<GdlGraph>().unwrap()
+    }
+
+    const TEST_GRAPH: &str = "
+        |(n0:L0)
+        |(n1:L1)
+        |(n2:L2)
+        |(n3:L1)
+        |(n4:L2)
+        |(n0)-->(n1)
+        |(n0)-->(n2)
+        |(n1)-->(n2)
+        |(n1)-->(n3)
+        |(n2)-->(n4)
+        |(n3)-->(n4)
+        |";
+
+    #[test]
+    fn test_match_query() {
+        let data_graph = graph(TEST_GRAPH);
+        let queries = [
+            // line
+            "
+            |(n0:L0),(n1:L1),(n2:L2)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |",
+            // triangle
+            "
+            |(n0:L0),(n1:L1),(n2:L2)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |(n2)-->(n0)
+            |",
+            // diamond
+            "
+            |(n0:L1),(n1:L2),(n2:L1),(n3:L2)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |",
+        ];
+        let configs = [
+            Config::default(),
+            Config::new(Filter::LdfCore, Order::Gql, Enumeration::Gql),
+            Config::new(Filter::Gql, Order::Ri, Enumeration::GqlHomomorphism),
+            // Stops early and leaves visited nodes behind for the next query.
+            Config::builder().limit(1).build(),
+        ];
+
+        let matcher = Matcher::new(&data_graph);
+
+        for query in queries {
+            let query_graph = graph(query);
+
+            for config in configs {
+                assert_eq!(
+                    matcher.match_query(&query_graph, config),
+                    find(&data_graph, &query_graph, config)
+                );
+
+                let mut expected = Vec::new();
+                find_with(
+                    &data_graph,
+                    &query_graph,
+                    |embedding| expected.push(Vec::from(embedding)),
+                    config,
+                );
+                let mut actual = Vec::new();
+                matcher.match_query_with(
+                    &query_graph,
+                    |embedding| actual.push(Vec::from(embedding)),
+                    config,
+                );
+                assert_eq!(actual, expected);
+            }
+        }
+    }
+}
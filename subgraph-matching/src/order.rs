@@ -1,8 +1,14 @@
-use crate::{filter::Candidates, graph::Graph};
+use crate::{filter::Candidates, graph::Graph, Error};
 
 /// Builds a matching order by starting with the node with the minimum
 /// number of candidates and iteratively selecting nodes that are adjacent
 /// to already selected nodes and having the minimum number of candidates.
+///
+/// Ties are broken by picking the node with the higher degree and then the
+/// one with the smaller id, so the order, and therefore the order in which
+/// embeddings are emitted, only depends on the query graph and candidates.
+/// If no unvisited node is adjacent to the selected ones, i.e. the query
+/// graph is disconnected, the order continues with the best unvisited node.
 pub fn gql_order(data_graph: &Graph, query_graph: &Graph, candidates: &Candidates) -> Vec<usize> {
     let node_count = query_graph.node_count();
 
@@ -17,22 +23,30 @@ pub fn gql_order(data_graph: &Graph, query_graph: &Graph, candidates: &Candidate
 
     for _ in 1..node_count {
         let mut next_node = usize::MAX;
-        let mut min_value = data_graph.node_count() + 1;
+        let mut min_rank = (
+            data_graph.node_count() + 1,
+            std::cmp::Reverse(0),
+            usize::MAX,
+        );
 
         for curr_node in 0..node_count {
             if !visited[curr_node] && adjacent[curr_node] {
-                let num_candidates = candidates.candidate_count(curr_node);
+                let rank = gql_rank(query_graph, candidates, curr_node);
 
-                if num_candidates < min_value {
-                    min_value = num_candidates;
-                    next_node = curr_node;
-                } else if num_candidates == min_value
-                    && query_graph.degree(curr_node) > query_graph.degree(next_node)
-                {
+                if rank < min_rank {
+                    min_rank = rank;
                     next_node = curr_node;
                 }
             }
         }
+
+        if next_node == usize::MAX {
+            next_node = (0..node_count)
+                .filter(|&node| !visited[node])
+                .min_by_key(|&node| gql_rank(query_graph, candidates, node))
+                .expect("an unvisited node remains");
+        }
+
         update_valid_vertices(query_graph, next_node, &mut visited, &mut adjacent);
         order.push(next_node);
     }
@@ -40,20 +54,552 @@ pub fn gql_order(data_graph: &Graph, query_graph: &Graph, candidates: &Candidate
     order
 }
 
+/// Builds a matching order following the RI heuristic, which only depends
+/// on the topology of the query graph.
+///
+/// Starts with the node of maximum degree and iteratively selects the node
+/// with the most edges to already selected nodes. Ties are broken by picking
+/// the node with the higher degree and then the one with fewer candidates.
+pub fn ri_order(query_graph: &Graph, candidates: &Candidates) -> Vec<usize> {
+    let node_count = query_graph.node_count();
+
+    let mut selected = vec![false; node_count];
+    // The number of edges from each node to already selected nodes.
+    let mut back_edges = vec![0_usize; node_count];
+    let mut order = Vec::<usize>::with_capacity(node_count);
+
+    let rank = |node: usize, back_edges: &[usize]| {
+        (
+            back_edges[node],
+            query_graph.degree(node),
+            std::cmp::Reverse(candidates.candidate_count(node)),
+        )
+    };
+
+    for _ in 0..node_count {
+        let next_node = (0..node_count)
+            .filter(|&node| !selected[node])
+            .fold(None, |next: Option<usize>, node| match next {
+                Some(next) if rank(next, &back_edges) >= rank(node, &back_edges) => Some(next),
+                _ => Some(node),
+            })
+            .expect("unselected node expected");
+
+        selected[next_node] = true;
+        for &neighbor in query_graph.neighbors(next_node) {
+            back_edges[neighbor] += 1;
+        }
+        order.push(next_node);
+    }
+
+    order
+}
+
+/// Builds a matching order by traversing a BFS spanning tree of the query
+/// graph, which is cheap to compute and works well for tree-shaped queries.
+///
+/// Starts with the same node as [`gql_order`]. The unvisited neighbors of
+/// each node are enqueued in ascending order of their candidate count, ties
+/// are broken by node id. Each node after the first is adjacent to its BFS
+/// parent, so the query graph needs to be connected.
+pub fn bfs_order(query_graph: &Graph, candidates: &Candidates) -> Vec<usize> {
+    let node_count = query_graph.node_count();
+
+    let mut visited = vec![false; node_count];
+    let mut order = Vec::<usize>::with_capacity(node_count);
+    let mut queue = std::collections::VecDeque::new();
+
+    let start = gql_start_node(query_graph, candidates);
+    visited[start] = true;
+    queue.push_back(start);
+
+    while let Some(node) = queue.pop_front() {
+        order.push(node);
+
+        let mut neighbors = query_graph
+            .neighbors(node)
+            .iter()
+            .copied()
+            .filter(|&neighbor| !visited[neighbor])
+            .collect::<Vec<_>>();
+        // Neighbor lists may contain duplicates for parallel edges.
+        neighbors.dedup();
+        neighbors.sort_by_key(|&neighbor| (candidates.candidate_count(neighbor), neighbor));
+
+        for neighbor in neighbors {
+            visited[neighbor] = true;
+            queue.push_back(neighbor);
+        }
+    }
+
+    order
+}
+
+/// Builds a matching order that prefers query nodes with selective neighbor
+/// label profiles, i.e. whose neighbors have labels that are rare in the
+/// data graph.
+///
+/// The selectivity of a node is the data graph frequency of the rarest label
+/// in its neighbor label frequency. Starts with the most selective node and
+/// iteratively selects the most selective node that is adjacent to already
+/// selected nodes. Ties are broken by picking the node with fewer candidates
+/// and then the one with the smaller id. Like [`gql_order`], the order
+/// continues with the most selective unvisited node if the query graph is
+/// disconnected.
+///
+/// Returns [`Error::MissingNeighborLabelFrequencies`] if the query graph has
+/// not been loaded with neighbor label frequencies.
+pub fn nlf_order(
+    data_graph: &Graph,
+    query_graph: &Graph,
+    candidates: &Candidates,
+) -> Result<Vec<usize>, Error> {
+    if !query_graph.has_neighbor_label_frequencies() {
+        return Err(Error::MissingNeighborLabelFrequencies);
+    }
+
+    let node_count = query_graph.node_count();
+    let ranks = (0..node_count)
+        .map(|node| {
+            let rarest_label_frequency = query_graph
+                .neighbor_label_frequency(node)
+                .keys()
+                .filter(|&&label| label != crate::graph::ANY_LABEL)
+                .map(|&label| data_graph.label_frequency(label))
+                .min()
+                .unwrap_or(usize::MAX);
+            (
+                rarest_label_frequency,
+                candidates.candidate_count(node),
+                node,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let mut visited = vec![false; node_count];
+    let mut adjacent = vec![false; node_count];
+    let mut order = Vec::<usize>::with_capacity(node_count);
+
+    for _ in 0..node_count {
+        let next_node = (0..node_count)
+            .filter(|&node| !visited[node] && adjacent[node])
+            .min_by_key(|&node| ranks[node])
+            .or_else(|| {
+                (0..node_count)
+                    .filter(|&node| !visited[node])
+                    .min_by_key(|&node| ranks[node])
+            })
+            .expect("an unvisited node remains");
+
+        update_valid_vertices(query_graph, next_node, &mut visited, &mut adjacent);
+        order.push(next_node);
+    }
+
+    Ok(order)
+}
+
+/// Builds a matching order that maximizes the number of edges to already
+/// selected nodes, i.e. the number of constraints that prune the candidates
+/// of each node, which pays off for cyclic query graphs.
+///
+/// Starts with the same node as [`gql_order`] and iteratively selects the
+/// adjacent node with the most edges to already selected nodes. Ties are
+/// broken by picking the node with fewer candidates and then the one with
+/// the smaller id. Like [`gql_order`], the order continues with the best
+/// unvisited node if the query graph is disconnected.
+pub fn connectivity_order(query_graph: &Graph, candidates: &Candidates) -> Vec<usize> {
+    let node_count = query_graph.node_count();
+
+    let mut visited = vec![false; node_count];
+    let mut adjacent = vec![false; node_count];
+    let mut back_edges = vec![0_usize; node_count];
+    let mut order = Vec::<usize>::with_capacity(node_count);
+
+    let rank = |back_edges: &[usize], node: usize| {
+        (
+            std::cmp::Reverse(back_edges[node]),
+            candidates.candidate_count(node),
+            node,
+        )
+    };
+
+    for _ in 0..node_count {
+        let next_node = if order.is_empty() {
+            gql_start_node(query_graph, candidates)
+        } else {
+            (0..node_count)
+                .filter(|&node| !visited[node] && adjacent[node])
+                .min_by_key(|&node| rank(&back_edges, node))
+                .or_else(|| {
+                    (0..node_count)
+                        .filter(|&node| !visited[node])
+                        .min_by_key(|&node| rank(&back_edges, node))
+                })
+                .expect("an unvisited node remains")
+        };
+
+        // Neighbor lists may contain duplicates for parallel edges.
+        let mut neighbors = query_graph.neighbors(next_node).to_vec();
+        neighbors.dedup();
+        for neighbor in neighbors {
+            back_edges[neighbor] += 1;
+        }
+
+        update_valid_vertices(query_graph, next_node, &mut visited, &mut adjacent);
+        order.push(next_node);
+    }
+
+    order
+}
+
+/// Builds the static matching order of DPiso, which is the BFS order of the
+/// query graph starting at the node with the fewest candidates per neighbor.
+///
+/// Only nodes with at least two neighbors are considered as start node, so
+/// the BFS tree is rooted in the dense part of the query. Within each BFS
+/// level, nodes with fewer candidates are visited first and ties are broken
+/// by picking the node with the higher degree.
+pub fn dpiso_order(data_graph: &Graph, query_graph: &Graph, candidates: &Candidates) -> Vec<usize> {
+    let node_count = query_graph.node_count();
+
+    let mut visited = vec![false; node_count];
+    let mut order = Vec::<usize>::with_capacity(node_count);
+
+    let start = dpiso_start_node(data_graph, query_graph, candidates);
+    visited[start] = true;
+
+    let mut level = vec![start];
+
+    while !level.is_empty() {
+        level.sort_by_key(|&node| {
+            (
+                candidates.candidate_count(node),
+                std::cmp::Reverse(query_graph.degree(node)),
+                node,
+            )
+        });
+        order.extend_from_slice(&level);
+
+        let mut next_level = Vec::new();
+        for &node in &level {
+            for &neighbor in query_graph.neighbors(node) {
+                if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    next_level.push(neighbor);
+                }
+            }
+        }
+        level = next_level;
+    }
+
+    order
+}
+
+/// Estimates the cost of enumerating the embeddings along the given order
+/// as the total number of partial embeddings, without enumerating them.
+///
+/// At each depth, the number of partial embeddings grows by the candidate
+/// count of the next node. Each edge to an already visited neighbor `w`
+/// scales this by the selectivity `1 / |C(w)|`, assuming the candidates of
+/// adjacent query nodes are evenly connected in the data graph. The value
+/// is only meaningful for comparing filters and orders on the same query.
+pub fn estimated_cost(query_graph: &Graph, candidates: &Candidates, order: &[usize]) -> f64 {
+    let mut visited = vec![false; query_graph.node_count()];
+    let mut partial_embeddings = 1.0;
+    let mut cost = 0.0;
+
+    for &node in order {
+        let mut extensions = candidates.candidate_count(node) as f64;
+
+        for &neighbor in query_graph.neighbors(node) {
+            if visited[neighbor] {
+                extensions /= usize::max(1, candidates.candidate_count(neighbor)) as f64;
+            }
+        }
+
+        partial_embeddings *= extensions;
+        cost += partial_embeddings;
+        visited[node] = true;
+    }
+
+    cost
+}
+
+/// Renders the given order for debugging, one `query_node(label=L,
+/// candidates=C)` entry per position, e.g.
+/// `0(label=0, candidates=1) -> 1(label=1, candidates=2)`.
+///
+/// Wildcard nodes are rendered with `label=*`.
+pub fn describe(order: &[usize], query_graph: &Graph, candidates: &Candidates) -> String {
+    order
+        .iter()
+        .map(|&node| {
+            let label = match query_graph.label(node) {
+                crate::graph::ANY_LABEL => String::from("*"),
+                label => label.to_string(),
+            };
+            format!(
+                "{}(label={}, candidates={})",
+                node,
+                label,
+                candidates.candidate_count(node)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" -> ")
+}
+
+/// Checks that the given order is a permutation of the query nodes in which
+/// each node after the first is adjacent to at least one of its predecessors.
+pub fn validate_order(query_graph: &Graph, order: &[usize]) -> Result<(), Error> {
+    let node_count = query_graph.node_count();
+
+    if order.len() != node_count {
+        return Err(Error::InvalidOrder {
+            reason: format!("expected {} nodes, got {}", node_count, order.len()),
+        });
+    }
+
+    let mut visited = vec![false; node_count];
+
+    for (idx, &node) in order.iter().enumerate() {
+        if node >= node_count || visited[node] {
+            return Err(Error::InvalidOrder {
+                reason: format!("node {} is unknown or appears twice", node),
+            });
+        }
+
+        if idx > 0
+            && !query_graph
+                .neighbors(node)
+                .iter()
+                .any(|&neighbor| visited[neighbor])
+        {
+            return Err(Error::InvalidOrder {
+                reason: format!("node {} is not adjacent to any previous node", node),
+            });
+        }
+
+        visited[node] = true;
+    }
+
+    Ok(())
+}
+
+/// Assigns an orbit id to each query node via color refinement (1-WL),
+/// starting from the labels and degree of each node.
+///
+/// In each round, a node's color is refined by the multiset of its
+/// neighbors' colors until the number of colors is stable. Ids are numbered
+/// `0..k` in ascending order of their smallest node. Nodes in the same
+/// automorphism orbit always share an id, the converse does not hold in
+/// general, e.g. for regular graphs. Node-local properties, like the ones
+/// used by [`crate::filter::ldf_filter`], are equal within an id.
+pub fn query_orbits(query_graph: &Graph) -> Vec<usize> {
+    let node_count = query_graph.node_count();
+
+    let mut colors = assign_colors(
+        (0..node_count).map(|node| (query_graph.labels(node).to_vec(), query_graph.degree(node))),
+    );
+    let mut color_count = colors.iter().max().map_or(0, |&max| max + 1);
+
+    loop {
+        let refined = assign_colors((0..node_count).map(|node| {
+            let mut neighbor_colors = query_graph
+                .neighbors(node)
+                .iter()
+                .map(|&neighbor| colors[neighbor])
+                .collect::<Vec<_>>();
+            neighbor_colors.sort_unstable();
+            (colors[node], neighbor_colors)
+        }));
+        let refined_count = refined.iter().max().map_or(0, |&max| max + 1);
+
+        // Refinement only splits colors, so it is stable once no color splits.
+        if refined_count == color_count {
+            return refined;
+        }
+
+        colors = refined;
+        color_count = refined_count;
+    }
+}
+
+/// Maps equal keys to the same color, numbered in order of first occurrence.
+fn assign_colors<K, I>(keys: I) -> Vec<usize>
+where
+    K: std::hash::Hash + Eq,
+    I: Iterator<Item = K>,
+{
+    let mut ids = std::collections::HashMap::new();
+    keys.map(|key| {
+        let next_id = ids.len();
+        *ids.entry(key).or_insert(next_id)
+    })
+    .collect()
+}
+
+/// Computes symmetry-breaking constraints for the automorphisms of the query graph.
+///
+/// Each constraint `(u, v)` requires that `u` is mapped to a smaller data node
+/// than `v`. Enforcing all constraints retains exactly one embedding out of
+/// each set of embeddings that only differ by an automorphism of the query.
+///
+/// Follows Grochow and Kellis: for each node in turn, all nodes in its orbit
+/// under the automorphisms that fix the previous nodes must be mapped to
+/// larger data nodes. Automorphisms preserve node labels, edge direction,
+/// edge labels and anti-edges.
+pub fn symmetry_constraints(query_graph: &Graph) -> Vec<(usize, usize)> {
+    let node_count = query_graph.node_count();
+    let mut constraints = Vec::new();
+    // Maps each node to its image, the fixed nodes are mapped to themselves.
+    let mut mapping = vec![usize::MAX; node_count];
+
+    for u in 0..node_count {
+        for v in u + 1..node_count {
+            let mut candidate = mapping.clone();
+            candidate[u] = v;
+            if extend_automorphism(query_graph, &mut candidate) {
+                constraints.push((u, v));
+            }
+        }
+        mapping[u] = u;
+    }
+
+    constraints
+}
+
+/// Tries to extend the given partial mapping to an automorphism of the graph.
+fn extend_automorphism(graph: &Graph, mapping: &mut [usize]) -> bool {
+    let mut used = vec![false; mapping.len()];
+    for &image in mapping.iter().filter(|&&image| image != usize::MAX) {
+        used[image] = true;
+    }
+
+    let mapped = (0..mapping.len())
+        .filter(|&node| mapping[node] != usize::MAX)
+        .collect::<Vec<_>>();
+
+    // The preset nodes have to be consistent among themselves.
+    mapped
+        .iter()
+        .all(|&node| preserves_edges(graph, mapping, node))
+        && extend_automorphism_from(graph, mapping, &mut used, 0)
+}
+
+fn extend_automorphism_from(
+    graph: &Graph,
+    mapping: &mut [usize],
+    used: &mut [bool],
+    node: usize,
+) -> bool {
+    if node == mapping.len() {
+        return true;
+    }
+    if mapping[node] != usize::MAX {
+        return extend_automorphism_from(graph, mapping, used, node + 1);
+    }
+
+    for image in 0..mapping.len() {
+        if used[image]
+            || graph.labels(image) != graph.labels(node)
+            || graph.degree(image) != graph.degree(node)
+        {
+            continue;
+        }
+
+        mapping[node] = image;
+        used[image] = true;
+
+        if preserves_edges(graph, mapping, node)
+            && extend_automorphism_from(graph, mapping, used, node + 1)
+        {
+            return true;
+        }
+
+        mapping[node] = usize::MAX;
+        used[image] = false;
+    }
+
+    false
+}
+
+/// Checks that the edges between `node` and the already mapped nodes are
+/// mapped to edges. Since the mapping is a bijection, this also holds for
+/// non-edges once all nodes are mapped.
+fn preserves_edges(graph: &Graph, mapping: &[usize], node: usize) -> bool {
+    let image = mapping[node];
+
+    graph
+        .out_neighbors(node)
+        .iter()
+        .filter(|&&neighbor| mapping[neighbor] != usize::MAX)
+        .all(|&neighbor| {
+            let neighbor_image = mapping[neighbor];
+            graph.exists_directed(image, neighbor_image)
+                && graph.edge_label(image, neighbor_image) == graph.edge_label(node, neighbor)
+                && graph.is_undirected_edge(image, neighbor_image)
+                    == graph.is_undirected_edge(node, neighbor)
+        })
+        && graph
+            .in_neighbors(node)
+            .iter()
+            .filter(|&&neighbor| mapping[neighbor] != usize::MAX)
+            .all(|&neighbor| graph.exists_directed(mapping[neighbor], image))
+        && graph
+            .anti_edges()
+            .iter()
+            .filter_map(|&(source, target)| {
+                if source == node {
+                    Some(target)
+                } else if target == node {
+                    Some(source)
+                } else {
+                    None
+                }
+            })
+            .filter(|&other| mapping[other] != usize::MAX)
+            .all(|other| graph.is_anti_edge(image, mapping[other]))
+}
+
 /// Selects the node with the minimum number of candidates as start node.
 ///
 /// Ties are handles by picking the node with a higher degree.
 fn gql_start_node(query_graph: &Graph, candidates: &Candidates) -> usize {
+    (0..query_graph.node_count())
+        .min_by_key(|&node| gql_rank(query_graph, candidates, node))
+        .unwrap_or(0)
+}
+
+/// Ranks query nodes for [`gql_order`], smaller is better: fewer candidates
+/// first, then higher degree, then smaller node id.
+fn gql_rank(
+    query_graph: &Graph,
+    candidates: &Candidates,
+    node: usize,
+) -> (usize, std::cmp::Reverse<usize>, usize) {
+    (
+        candidates.candidate_count(node),
+        std::cmp::Reverse(query_graph.degree(node)),
+        node,
+    )
+}
+
+/// Selects the node with the minimum ratio of candidates to degree among
+/// the nodes with at least two neighbors as start node.
+fn dpiso_start_node(data_graph: &Graph, query_graph: &Graph, candidates: &Candidates) -> usize {
     let mut start = 0;
+    let mut min_score = data_graph.node_count() as f64;
 
-    for node in 1..query_graph.node_count() {
-        let num_node_candidates = candidates.candidate_count(node);
-        let num_start_candidates = candidates.candidate_count(start);
+    for node in 0..query_graph.node_count() {
+        let degree = query_graph.degree(node);
+        if degree <= 1 {
+            continue;
+        }
 
-        if num_node_candidates < num_start_candidates
-            || (num_node_candidates == num_start_candidates
-                && query_graph.degree(node) > query_graph.degree(start))
-        {
+        let score = candidates.candidate_count(node) as f64 / degree as f64;
+        if score < min_score {
+            min_score = score;
             start = node;
         }
     }
@@ -136,4 +682,415 @@ mod tests {
 
         assert_eq!(order, vec![1, 2, 0, 4, 3]);
     }
+
+    #[test]
+    fn test_gql_order_symmetric_query() {
+        let cycle = "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |(n2)-->(n3)
+            |(n3)-->(n0)
+            |";
+        let data_graph = graph(cycle);
+        let query_graph = graph(cycle);
+        let candidates = ldf_filter(&data_graph, &query_graph).unwrap();
+
+        // All nodes tie on candidates and degree, so node ids decide.
+        assert_eq!(
+            gql_order(&data_graph, &query_graph, &candidates),
+            vec![0, 1, 2, 3]
+        );
+
+        let mut embeddings = Vec::new();
+        crate::enumerate::gql_with(
+            &data_graph,
+            &query_graph,
+            &candidates,
+            &gql_order(&data_graph, &query_graph, &candidates),
+            |embedding| embeddings.push(Vec::from(embedding)),
+        );
+
+        assert_eq!(
+            embeddings,
+            vec![
+                vec![0, 1, 2, 3],
+                vec![0, 3, 2, 1],
+                vec![1, 0, 3, 2],
+                vec![1, 2, 3, 0],
+                vec![2, 1, 0, 3],
+                vec![2, 3, 0, 1],
+                vec![3, 0, 1, 2],
+                vec![3, 2, 1, 0],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gql_order_disconnected_query() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L1),(n1:L2),(n2:L1),(n3:L2)
+            |(n0)-->(n1)
+            |(n2)-->(n3)
+            |",
+        );
+        let candidates = ldf_filter(&data_graph, &query_graph).unwrap();
+
+        assert_eq!(
+            gql_order(&data_graph, &query_graph, &candidates),
+            vec![1, 0, 3, 2]
+        );
+    }
+
+    #[test]
+    fn test_estimated_cost() {
+        let data_graph = graph(TEST_GRAPH);
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L2)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n2)
+            |",
+        );
+
+        let candidates = ldf_filter(&data_graph, &query_graph).unwrap();
+        assert_eq!(candidates.candidate_product(), 2);
+
+        // 1 + (1 * 2) + (2 * 1 / (1 * 2))
+        assert_eq!(estimated_cost(&query_graph, &candidates, &[0, 1, 2]), 4.0);
+        // 1 + (1 * 1) + (1 * 2 / (1 * 1))
+        assert_eq!(estimated_cost(&query_graph, &candidates, &[0, 2, 1]), 4.0);
+        // 2 + (2 * 1 / 2) + (1 * 1 / (2 * 1))
+        assert_eq!(estimated_cost(&query_graph, &candidates, &[1, 0, 2]), 3.5);
+    }
+
+    #[test]
+    fn test_symmetry_constraints() {
+        // The diamond can be mirrored along the (n1, n2) diagonal.
+        let diamond = graph(
+            "
+            |(n0:L1),(n1:L2),(n2:L1),(n3:L2)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |",
+        );
+        assert_eq!(symmetry_constraints(&diamond), vec![(0, 2)]);
+
+        // A triangle with equal labels has all 6 permutations as automorphisms.
+        let triangle = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0)
+            |(n0)-->(n1)
+            |(n0)-->(n2)
+            |(n1)-->(n2)
+            |",
+        );
+        assert_eq!(
+            symmetry_constraints(&triangle),
+            vec![(0, 1), (0, 2), (1, 2)]
+        );
+
+        // Distinct labels rule out any automorphism.
+        let path = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L2)
+            |(n0)-->(n1)
+            |(n1)-->(n2)
+            |",
+        );
+        assert_eq!(symmetry_constraints(&path), vec![]);
+    }
+
+    #[test]
+    fn test_dpiso_order() {
+        // Two stars with an L0 center, but only the L3 leaf of n0 continues
+        // into a path.
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L0)
+            |(n2:L1),(n3:L1),(n4:L2),(n5:L2),(n6:L3)
+            |(n7:L1),(n8:L1),(n9:L2),(n10:L2),(n11:L3)
+            |(n12:L4)
+            |(n0)-->(n2),(n0)-->(n3),(n0)-->(n4),(n0)-->(n5),(n0)-->(n6)
+            |(n1)-->(n7),(n1)-->(n8),(n1)-->(n9),(n1)-->(n10),(n1)-->(n11)
+            |(n6)-->(n12)
+            |",
+        );
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L1),(n3:L2),(n4:L2),(n5:L3),(n6:L4)
+            |(n0)-->(n1),(n0)-->(n2),(n0)-->(n3),(n0)-->(n4),(n0)-->(n5)
+            |(n5)-->(n6)
+            |",
+        );
+
+        let candidates = ldf_filter(&data_graph, &query_graph).unwrap();
+
+        assert_eq!(candidates.candidates(0), &[0, 1]);
+        assert_eq!(candidates.candidates(5), &[6]);
+        assert_eq!(candidates.candidates(6), &[12]);
+
+        // GQL starts at the path due to its single candidates, whereas
+        // DPiso starts at the center, which has fewer candidates per edge.
+        assert_eq!(
+            gql_order(&data_graph, &query_graph, &candidates),
+            vec![5, 6, 0, 1, 2, 3, 4]
+        );
+        assert_eq!(
+            dpiso_order(&data_graph, &query_graph, &candidates),
+            vec![0, 5, 1, 2, 3, 4, 6]
+        );
+
+        assert_eq!(crate::find(&data_graph, &query_graph, crate::Order::Gql), 4);
+        assert_eq!(
+            crate::find(&data_graph, &query_graph, crate::Order::Dpiso),
+            4
+        );
+    }
+
+    #[test]
+    fn test_ri_order() {
+        let data_graph = graph(TEST_GRAPH);
+        // a triangle (n1, n2, n3) with a tail (n3, n0)
+        let query_graph = graph(
+            "
+            |(n0:L4),(n1:L0),(n2:L1),(n3:L2)
+            |(n1)-->(n2)
+            |(n1)-->(n3)
+            |(n2)-->(n3)
+            |(n3)-->(n0)
+            |",
+        );
+
+        let candidates = ldf_filter(&data_graph, &query_graph).unwrap();
+
+        assert_eq!(candidates.candidates(0), &[4]);
+        assert_eq!(candidates.candidates(1), &[0]);
+        assert_eq!(candidates.candidates(2), &[1, 3]);
+        assert_eq!(candidates.candidates(3), &[2]);
+
+        // GQL picks the tail as soon as possible due to its single candidate,
+        // whereas RI closes the triangle first.
+        assert_eq!(
+            gql_order(&data_graph, &query_graph, &candidates),
+            vec![3, 1, 0, 2]
+        );
+        assert_eq!(ri_order(&query_graph, &candidates), vec![3, 1, 2, 0]);
+    }
+
+    #[test]
+    fn test_query_orbits() {
+        let diamond = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0)
+            |(n0)-->(n1),(n1)-->(n2),(n2)-->(n3),(n3)-->(n0),(n1)-->(n3)
+            |",
+        );
+
+        assert_eq!(query_orbits(&diamond), vec![0, 1, 0, 1]);
+
+        // All nodes have degree two, the cycle is mirrored at the L1 node.
+        let cycle = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L1),(n3:L0),(n4:L0)
+            |(n0)-->(n1),(n1)-->(n2),(n2)-->(n3),(n3)-->(n4),(n4)-->(n0)
+            |",
+        );
+
+        assert_eq!(query_orbits(&cycle), vec![0, 1, 2, 1, 0]);
+    }
+
+    const DATA_GRAPH_2: &str = "
+        |(n0:L0)
+        |(n1:L2)
+        |(n2:L1)
+        |(n3:L2)
+        |(n4:L1)
+        |(n5:L2)
+        |(n6:L1)
+        |(n7:L2)
+        |(n8:L3)
+        |(n9:L3)
+        |(n10:L3)
+        |(n11:L3)
+        |(n12:L3)
+        |(n13:L2)
+        |(n14:L3)
+        |(n0)-->(n1)
+        |(n0)-->(n2)
+        |(n0)-->(n3)
+        |(n0)-->(n4)
+        |(n0)-->(n5)
+        |(n0)-->(n6)
+        |(n0)-->(n7)
+        |(n1)-->(n2)
+        |(n1)-->(n8)
+        |(n2)-->(n9)
+        |(n2)-->(n10)
+        |(n3)-->(n4)
+        |(n3)-->(n10)
+        |(n4)-->(n5)
+        |(n4)-->(n10)
+        |(n4)-->(n11)
+        |(n4)-->(n12)
+        |(n5)-->(n12)
+        |(n6)-->(n12)
+        |(n6)-->(n13)
+        |(n7)-->(n14)
+        |(n9)-->(n10)
+        |";
+
+    #[test]
+    fn test_nlf_order() {
+        let data_graph = graph(DATA_GRAPH_2);
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L1),(n2:L2),(n3:L3)
+            |(n0)-->(n1),(n0)-->(n2),(n1)-->(n2),(n1)-->(n3),(n2)-->(n3)
+            |",
+        );
+        let candidates = ldf_filter(&data_graph, &query_graph).unwrap();
+
+        assert_eq!(candidates.candidate_count(0), 1);
+        assert_eq!(candidates.candidate_count(1), 3);
+        assert_eq!(candidates.candidate_count(2), 3);
+        assert_eq!(candidates.candidate_count(3), 3);
+
+        // n1 and n2 neighbor the only L0 node, n0 starts due to its single
+        // candidate in the GQL order.
+        let order = nlf_order(&data_graph, &query_graph, &candidates).unwrap();
+
+        assert_eq!(order, vec![1, 2, 0, 3]);
+        assert_eq!(
+            gql_order(&data_graph, &query_graph, &candidates),
+            vec![0, 1, 2, 3]
+        );
+        assert!(validate_order(&query_graph, &order).is_ok());
+        assert_eq!(
+            crate::find(&data_graph, &query_graph, crate::Order::Nlf),
+            crate::find(&data_graph, &query_graph, crate::Order::Gql)
+        );
+
+        let query_graph = Graph::from_gdl(
+            "(n0:L0),(n1:L1),(n0)-->(n1)",
+            crate::graph::LoadConfig::default(),
+        )
+        .unwrap();
+        let candidates = ldf_filter(&data_graph, &query_graph).unwrap();
+
+        assert!(matches!(
+            nlf_order(&data_graph, &query_graph, &candidates),
+            Err(Error::MissingNeighborLabelFrequencies)
+        ));
+    }
+
+    #[test]
+    fn test_describe() {
+        let data_graph = graph(TEST_GRAPH);
+        let line = graph("(n0:L0),(n1:L1),(n2:L2),(n0)-->(n1),(n1)-->(n2)");
+        let candidates = ldf_filter(&data_graph, &line).unwrap();
+        let order = gql_order(&data_graph, &line, &candidates);
+
+        let description = describe(&order, &line, &candidates);
+
+        assert_eq!(description.matches("candidates=").count(), order.len());
+        assert_eq!(
+            description,
+            "0(label=0, candidates=1) -> 1(label=1, candidates=2) -> 2(label=2, candidates=1)"
+        );
+
+        let wildcard = graph("(n0:L0),(n1),(n0)-->(n1)");
+        let candidates = ldf_filter(&data_graph, &wildcard).unwrap();
+
+        assert_eq!(
+            describe(&[0, 1], &wildcard, &candidates),
+            "0(label=0, candidates=1) -> 1(label=*, candidates=5)"
+        );
+    }
+
+    #[test]
+    fn test_bfs_order() {
+        let data_graph = graph(TEST_GRAPH);
+
+        let line = graph("(n0:L0),(n1:L1),(n2:L2),(n0)-->(n1),(n1)-->(n2)");
+        let candidates = ldf_filter(&data_graph, &line).unwrap();
+        let order = bfs_order(&line, &candidates);
+
+        assert_eq!(order, vec![0, 1, 2]);
+        assert!(validate_order(&line, &order).is_ok());
+
+        // The center n3 and the leaves n0 and n1 have a single candidate,
+        // the center wins due to its higher degree.
+        let star = graph(
+            "
+            |(n0:L0),(n1:L2),(n2:L1),(n3:L1)
+            |(n3)-->(n0),(n3)-->(n1),(n3)-->(n2)
+            |",
+        );
+        let candidates = ldf_filter(&data_graph, &star).unwrap();
+
+        assert_eq!(candidates.candidates(2), &[1, 3]);
+        assert_eq!(candidates.candidates(3), &[1]);
+
+        let order = bfs_order(&star, &candidates);
+
+        assert_eq!(order, vec![3, 0, 1, 2]);
+        assert!(validate_order(&star, &order).is_ok());
+        assert_eq!(
+            crate::find(&data_graph, &star, crate::Order::Bfs),
+            crate::find(&data_graph, &star, crate::Order::Gql)
+        );
+    }
+
+    #[test]
+    fn test_connectivity_order() {
+        let data_graph = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0),(n4:L0)
+            |(n0)-->(n1),(n0)-->(n2),(n0)-->(n3),(n0)-->(n4),(n1)-->(n2)
+            |(n1)-->(n3),(n1)-->(n4),(n2)-->(n3),(n2)-->(n4),(n3)-->(n4)
+            |",
+        );
+        // A 4-cycle with the chord n0-n2.
+        let query_graph = graph(
+            "
+            |(n0:L0),(n1:L0),(n2:L0),(n3:L0)
+            |(n0)-->(n1),(n1)-->(n2),(n2)-->(n3),(n3)-->(n0),(n0)-->(n2)
+            |",
+        );
+        let candidates =
+            Candidates::new(vec![vec![0], vec![0, 1], vec![0, 1, 2, 3], vec![0, 1, 2]]);
+
+        // n2 has two edges to n0 and n1, whereas n3 has fewer candidates.
+        let order = connectivity_order(&query_graph, &candidates);
+
+        assert_eq!(order, vec![0, 1, 2, 3]);
+        assert_eq!(
+            gql_order(&data_graph, &query_graph, &candidates),
+            vec![0, 1, 3, 2]
+        );
+        assert!(validate_order(&query_graph, &order).is_ok());
+
+        // Both orders find all 120 embeddings in the complete graph.
+        let gql = gql_order(&data_graph, &query_graph, &candidates);
+        for order in [order, gql].iter() {
+            let count = crate::find_with_order(
+                &data_graph,
+                &query_graph,
+                order,
+                |_| {},
+                crate::Config::default(),
+            );
+            assert_eq!(count.unwrap(), 120);
+        }
+        assert_eq!(
+            crate::find(&data_graph, &query_graph, crate::Order::Connectivity),
+            120
+        );
+    }
 }
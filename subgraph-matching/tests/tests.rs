@@ -1,8 +1,8 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::HashMap, path::PathBuf, time::Duration};
 use subgraph_matching::{
-    find,
-    graph::{load, Graph, LoadConfig},
-    Config, Enumeration, Filter, Order,
+    enumerate, filter, find,
+    graph::{load, load_with_metrics, Graph, LoadConfig},
+    order, CandidateBackend, Config, Enumeration, Filter, Order,
 };
 
 const CRATE_ROOT: &str = env!("CARGO_MANIFEST_DIR");
@@ -38,7 +38,7 @@ fn expected_counts() -> HashMap<String, usize> {
     std::fs::read_to_string(path)
         .unwrap()
         .lines()
-        .map(|line| line.split(":"))
+        .map(|line| line.split(':'))
         .map(|mut split| {
             (
                 split.next().unwrap().to_string(),
@@ -48,6 +48,47 @@ fn expected_counts() -> HashMap<String, usize> {
         .collect::<HashMap<_, _>>()
 }
 
+#[test]
+fn binary_round_trip() {
+    let data_graph = data_graph(LoadConfig::default());
+    let path = std::env::temp_dir().join(format!("suma-hprd-{}.bin", std::process::id()));
+
+    data_graph.write_binary(&path).unwrap();
+    let read = Graph::read_binary(&path);
+    std::fs::remove_file(&path).unwrap();
+    let read = read.unwrap();
+
+    assert_eq!(read.node_count(), data_graph.node_count());
+    assert_eq!(read.edge_count(), data_graph.edge_count());
+    assert_eq!(read.label_count(), data_graph.label_count());
+
+    for node in (0..data_graph.node_count()).step_by(97) {
+        assert_eq!(read.label(node), data_graph.label(node));
+        assert_eq!(read.neighbors(node), data_graph.neighbors(node));
+    }
+}
+
+#[test]
+fn load_metrics() {
+    let path = HPRD_PATH.iter().collect::<PathBuf>();
+
+    let (graph, metrics) = load_with_metrics(&path, LoadConfig::default()).unwrap();
+    assert_eq!(
+        graph.node_count(),
+        data_graph(LoadConfig::default()).node_count()
+    );
+    assert!(metrics.parse > Duration::ZERO);
+    assert!(metrics.build > Duration::ZERO);
+    assert_eq!(metrics.frequencies, None);
+
+    let (graph, metrics) =
+        load_with_metrics(&path, LoadConfig::with_neighbor_label_frequency()).unwrap();
+    assert!(graph.has_neighbor_label_frequencies());
+    assert!(metrics.parse > Duration::ZERO);
+    assert!(metrics.build > Duration::ZERO);
+    assert!(metrics.frequencies.unwrap() > Duration::ZERO);
+}
+
 #[test]
 fn filter_ldf_order_gql_enumeration_gql() {
     assert_expected_counts(Config::new(Filter::Ldf, Order::Gql, Enumeration::Gql))
@@ -62,6 +103,115 @@ fn filter_nlf_order_gql_enumeration_gql() {
     assert_expected_counts(Config::new(Filter::Nlf, Order::Gql, Enumeration::Gql))
 }
 
+#[test]
+fn filter_nlf2_order_gql_enumeration_gql() {
+    assert_expected_counts(Config::new(Filter::Nlf2, Order::Gql, Enumeration::Gql))
+}
+
+#[test]
+fn filter_cfl_order_gql_enumeration_gql() {
+    assert_expected_counts(Config::new(Filter::Cfl, Order::Gql, Enumeration::Gql))
+}
+
+#[test]
+fn filter_ldf_core_order_gql_enumeration_gql() {
+    assert_expected_counts(Config::new(Filter::LdfCore, Order::Gql, Enumeration::Gql))
+}
+
+#[test]
+fn filter_gql_order_ri_enumeration_gql() {
+    assert_expected_counts(Config::new(Filter::Gql, Order::Ri, Enumeration::Gql))
+}
+
+#[test]
+fn filter_gql_order_dpiso_enumeration_gql() {
+    assert_expected_counts(Config::new(Filter::Gql, Order::Dpiso, Enumeration::Gql))
+}
+
+#[test]
+fn filter_gql_order_bfs_enumeration_gql() {
+    assert_expected_counts(Config::new(Filter::Gql, Order::Bfs, Enumeration::Gql))
+}
+
+#[test]
+fn filter_gql_order_nlf_enumeration_gql() {
+    assert_expected_counts(Config::new(Filter::Gql, Order::Nlf, Enumeration::Gql))
+}
+
+#[test]
+fn filter_gql_order_connectivity_enumeration_gql() {
+    assert_expected_counts(Config::new(
+        Filter::Gql,
+        Order::Connectivity,
+        Enumeration::Gql,
+    ))
+}
+
+#[test]
+fn filter_gql_order_gql_enumeration_gql_failing_set() {
+    assert_expected_counts(Config::new(
+        Filter::Gql,
+        Order::Gql,
+        Enumeration::GqlFailingSet,
+    ))
+}
+
+#[test]
+fn filter_gql_order_gql_enumeration_vf2() {
+    assert_expected_counts(Config::new(Filter::Gql, Order::Gql, Enumeration::Vf2))
+}
+
+#[test]
+fn filter_gql_order_gql_enumeration_lftj() {
+    assert_expected_counts(Config::new(Filter::Gql, Order::Gql, Enumeration::Lftj))
+}
+
+#[test]
+fn filter_gql_order_gql_enumeration_gql_bitset() {
+    assert_expected_counts(
+        Config::builder()
+            .filter(Filter::Gql)
+            .gql_candidate_backend(CandidateBackend::Bitset)
+            .build(),
+    )
+}
+
+#[test]
+fn filter_gql_order_gql_enumeration_gql_par() {
+    let data_graph = data_graph(LoadConfig::default());
+    let expected_counts = expected_counts();
+
+    for (query_name, query_graph) in query_graphs(LoadConfig::default()) {
+        let mut candidates = filter::gql_filter(&data_graph, &query_graph).unwrap();
+        candidates.sort();
+        let order = order::gql_order(&data_graph, &query_graph, &candidates);
+
+        let count = enumerate::gql_par(&data_graph, &query_graph, &candidates, &order);
+        let sequential_count = enumerate::gql(&data_graph, &query_graph, &candidates, &order);
+        let counted = enumerate::count(&data_graph, &query_graph, &candidates, &order);
+
+        assert_eq!(count, sequential_count, "query: {}", query_name);
+        assert_eq!(counted, sequential_count, "query: {}", query_name);
+        assert_eq!(count, expected_counts[&query_name], "query: {}", query_name);
+    }
+}
+
+#[test]
+fn filter_gql_with_scratch() {
+    let data_graph = data_graph(LoadConfig::default());
+    let mut scratch = filter::GqlFilterScratch::new(&data_graph);
+
+    for (query_name, query_graph) in query_graphs(LoadConfig::default()) {
+        let expected = filter::gql_filter(&data_graph, &query_graph).unwrap();
+        let actual =
+            filter::gql_filter_with_scratch(&data_graph, &query_graph, &mut scratch).unwrap();
+
+        for ((_, expected), (_, actual)) in expected.iter().zip(actual.iter()) {
+            assert_eq!(actual, expected, "query: {}", query_name);
+        }
+    }
+}
+
 fn assert_expected_counts(config: Config) {
     let data_graph = data_graph(config.into());
     let expected_counts = expected_counts();